@@ -18,8 +18,20 @@ lazy_static!
   /// todo Должно автоматически собираться из парсера
   static ref Builtins: HashSet<&'static str> = {
     HashSet::from([
-      "println", "print", "clear", "go", "sleep", "exit",
-      "type", "mut", "randUInt", "len", "input", "exec", "execs"
+      "println", "print", "clear", "go", "sleep", "exit", "retry", "assert",
+      "type", "mut", "randUInt", "len", "input", "exec", "execs", "process",
+      "import", "spawn", "join", "alive",
+      "channel", "send", "recv", "tryRecv", "close", "select",
+      "after", "every", "cancel", "drift",
+      "split", "replace", "trim", "startsWith", "endsWith", "find", "slice",
+      "upper", "lower", "repeat", "parseInt", "parseFloat", "chars",
+      "abs", "min", "max", "clamp", "floor", "ceil", "round", "sqrt", "pow",
+      "exp", "ln", "log10", "sin", "cos", "tan", "asin", "acos", "atan", "atan2",
+      "hypot", "pi", "e", "isNan", "isInf",
+      "randInt", "randFloat", "randBool", "choice", "shuffle", "seed",
+      "readFile", "readBytes", "writeFile", "appendFile", "exists", "isDir", "listDir",
+      "mkdir", "remove", "rename", "copy", "fileSize", "modifiedTime",
+      "env", "setEnv", "envAll", "cwd", "chdir", "pid", "scriptPath", "hostname", "platform"
     ])
   };
 }
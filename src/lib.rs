@@ -6,9 +6,12 @@ include!("prelude.rs");
 
 // =================================================================================================
 
+use std::io::Write;
 use std::sync::{Arc, RwLock, RwLockWriteGuard};
 use crate::parser::bytes::Bytes;
-use crate::parser::parser::{parseLines, MainStructure};
+use crate::parser::budget::Budget;
+use crate::parser::runtime::Runtime;
+use crate::parser::vm::Engine;
 use crate::parser::structure::structure::{Structure, StructureMut};
 use crate::parser::structure::structureType::StructureType;
 use crate::tokenizer::tokenizer::readTokens;
@@ -28,9 +31,12 @@ mod analyzer;
 // =================================================================================================
 
 /// Основная структура-прокладка для создания оболочки между RTS-lib и другим проектом;
+/// Каждая оболочка работает в своей среде выполнения,
+/// поэтому несколько RTS в одном процессе не видят структуры и вывод друг друга
 pub struct RTS 
 {
   namespace: String,
+  runtime:   Arc<Runtime>,
 }
 
 impl RTS 
@@ -38,11 +44,22 @@ impl RTS
   /// Создаёт namespace структуру и RTS оболочку
   pub fn new(name: String) -> Self 
   {
-    // 
-    let main: RwLockWriteGuard<Structure> = MainStructure.write().unwrap();
+    let rts: RTS = RTS {
+      namespace: name,
+      runtime:   Runtime::new()
+    };
+    rts.pushNamespace();
+    rts
+  }
+
+  /// Добавляет namespace структуру в главную структуру среды
+  fn pushNamespace(&self) -> ()
+  {
+    let mainStructureLink: Arc<RwLock<Structure>> = self.runtime.getMainStructure();
+    let main: RwLockWriteGuard<Structure> = mainStructureLink.write().unwrap();
     main.pushStructure(
       Arc::new(RwLock::new(Structure::new(
-        Some(name.clone()),
+        Some(self.namespace.clone()),
         StructureMut::Constant,
         StructureType::List,
         // В линии структуры
@@ -57,14 +74,9 @@ impl RTS
             }
           ))
         ]),
-        Some( MainStructure.clone() ), // Ссылаемся на родителя
+        Some( mainStructureLink.clone() ), // Ссылаемся на родителя
       )))
     );
-    
-    //
-    RTS {
-      namespace: name
-    }
   }
   
   /// Добавляет структуру в namespace структуру
@@ -77,7 +89,8 @@ impl RTS
   ) 
   {
     //
-    let mainStructure: RwLockWriteGuard<Structure> = MainStructure.write().unwrap();
+    let mainStructureLink: Arc<RwLock<Structure>> = self.runtime.getMainStructure();
+    let mainStructure: RwLockWriteGuard<Structure> = mainStructureLink.write().unwrap();
     let namespaceStructureLink: Arc<RwLock<Structure>> = mainStructure.getStructureByName(self.namespace.as_str()).unwrap();
     let namespaceStructure: RwLockWriteGuard<Structure> = namespaceStructureLink.write().unwrap();
     namespaceStructure.pushStructure(
@@ -102,12 +115,56 @@ impl RTS
     );
   }
 
-  /// Запускает код
-  pub fn run(&self, script: &str) 
+  /// Устанавливает ограничения выполнения для следующих запусков кода
+  pub fn setBudget(&self, budget: Budget)
+  {
+    self.runtime.setBudget(budget);
+  }
+
+  /// Выбирает движок выполнения для следующих запусков кода
+  pub fn setEngine(&self, engine: Engine)
+  {
+    self.runtime.setEngine(engine);
+  }
+
+  /// Ставит зерно генератора случайных чисел; None - случайное зерно от системы
+  pub fn setSeed(&self, seed: Option<u64>)
+  {
+    self.runtime.setSeed(seed);
+  }
+
+  /// Ставит, куда пишут print и println; По умолчанию это stdout
+  pub fn setOutput(&self, output: Box<dyn Write + Send>)
+  {
+    self.runtime.setOutput(output);
+  }
+
+  /// Ставит аргументы программы (argc, argv); Путь запущенного файла argv.0 добавляется сам
+  pub fn setArguments(&self, arguments: Vec<String>)
+  {
+    self.runtime.setArguments(arguments);
+  }
+
+  /// Получает среду выполнения этой оболочки
+  pub fn getRuntime(&self) -> Arc<Runtime>
+  {
+    self.runtime.clone()
+  }
+
+  /// Сбрасывает состояние после прошлых запусков: структуры, код завершения, таймеры и потоки;
+  /// Структуры, добавленные через newStructure, тоже удаляются
+  pub fn reset(&self)
+  {
+    self.runtime.reset();
+    self.pushNamespace();
+  }
+
+  /// Запускает код;
+  /// Возвращает код завершения, budgetExitCode - если был исчерпан бюджет
+  pub fn run(&self, script: &str) -> i32
   {
-    // unsafe{_debugMode = true;}
     let buffer: Vec<u8> = script.as_bytes().to_vec();
-    parseLines( readTokens(buffer, unsafe{_debugMode}) );
+    self.runtime.run( readTokens(buffer, self.runtime.isDebugMode()) )
   }
   
   pub fn getNative(method: extern "C" fn(args: &[Token])) -> Bytes 
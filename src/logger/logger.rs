@@ -2,9 +2,7 @@
   Has both debug functions and for normal work
 */
 
-use crate::{
-  _debugMode,
-};
+use crate::parser::runtime::runtime;
 
 use termion::color::{Bg, Fg, Rgb, Reset};
 use termion::style;
@@ -36,14 +34,11 @@ pub fn formatPrint(string: &str) -> ()
 {
   print!("{}",&formatString(string));
 }
-
-static mut _result: String = String::new();
-
-static mut _i:            usize = 0;
-static mut _stringLength: usize = 0;
-
-static mut _stringChars:   Vec<char>   = Vec::new();
-static mut _string:        String      = String::new();
+// format print to stderr, so it does not mix with program output
+pub fn formatPrintError(string: &str) -> ()
+{
+  eprint!("{}",&formatString(string));
+}
 
 /*
   Formats a string, you can use flags:
@@ -61,72 +56,71 @@ static mut _string:        String      = String::new();
 // todo: if -> match
 pub fn formatString(string: &str) -> String 
 {
-  unsafe
   {
-    _result = String::new();
-
-    _i = 0;
-    _stringChars  = string.chars().collect();
-    _stringLength = _stringChars.len();
+    let mut result:       String    = String::new();
+    let mut i:            usize     = 0;
+    let     stringChars:  Vec<char> = string.chars().collect();
+    let     stringLength: usize     = stringChars.len();
+    let mut colorName:    String;
 
-    while _i < _stringLength 
+    while i < stringLength 
     { // special 
-      if _stringChars[_i] == '\\' && _i+1 < _stringLength &&
-         ((_i == 0) || (_i > 0 && _stringChars[_i-1] != '\\')) // Проверяем на экранировние
+      if stringChars[i] == '\\' && i+1 < stringLength &&
+         ((i == 0) || (i > 0 && stringChars[i-1] != '\\')) // Проверяем на экранировние
       {
-        match _stringChars[_i+1] 
+        match stringChars[i+1] 
         {
           // todo: Добавить \t и другие варианты
           'n' => 
           {
-            _i += 2;
-            _result.push_str("\n");
+            i += 2;
+            result.push_str("\n");
             continue;
           }
           'b' => 
           {
-            match _i+2 < _stringLength && _stringChars[_i+2] == 'g' 
+            match i+2 < stringLength && stringChars[i+2] == 'g' 
             {
               true => 
               { // bg
-                _i += 5;
-                _string = String::from_iter(
-                  _stringChars[_i.._stringLength]
+                i += 5;
+                colorName = String::from_iter(
+                  stringChars[i..stringLength]
                     .iter()
                     .take_while(|&&c| c != ')')
                 );
-                _result.push_str(&format!(
+                result.push_str(&format!(
                   "{}",
-                  Bg(hexToTermionColor(_string.as_str()).unwrap_or_else(|| Rgb(0, 0, 0)))
+                  Bg(hexToTermionColor(colorName.as_str()).unwrap_or_else(|| Rgb(0, 0, 0)))
                 ));
-                _i += _string.len()+1;
+                i += colorName.len()+1;
                 continue;
               }  
               false => 
               { // bold
-                _result.push_str( &format!("{}",style::Bold) );
-                _i += 2;
+                result.push_str( &format!("{}",style::Bold) );
+                i += 2;
                 continue;
               }
             }
           }
           'f' => 
           {
-            match _i+2 < _stringLength && _stringChars[_i+2] == 'g' 
+            match i+2 < stringLength && stringChars[i+2] == 'g' 
             {
               true => 
               { // fg
-                _i += 5;
-                _string = String::from_iter(
-                  _stringChars[_i.._stringLength]
+                i += 5;
+                colorName = String::from_iter(
+                  stringChars[i..stringLength]
                     .iter()
                     .take_while(|&&c| c != ')')
                 );
-                _result.push_str(&format!(
+                result.push_str(&format!(
                   "{}",
-                  Fg(hexToTermionColor(&_string).unwrap_or_else(|| Rgb(0, 0, 0)))
+                  Fg(hexToTermionColor(&colorName).unwrap_or_else(|| Rgb(0, 0, 0)))
                 ));
-                _i += _string.len()+1;
+                i += colorName.len()+1;
                 continue;
               }
               false => {}
@@ -134,14 +128,14 @@ pub fn formatString(string: &str) -> String
           }
           'c' => 
           { // clear
-            if _i+2 < _stringLength && _stringChars[_i+2] == 'b' 
+            if i+2 < stringLength && stringChars[i+2] == 'b' 
             {
-              match _i+3 < _stringLength && _stringChars[_i+3] == 'g' 
+              match i+3 < stringLength && stringChars[i+3] == 'g' 
               {
                 true =>
                 { // cbg
-                  _i += 4;
-                  _result.push_str(&format!(
+                  i += 4;
+                  result.push_str(&format!(
                     "{}",
                     Bg(Reset)
                   ));
@@ -149,8 +143,8 @@ pub fn formatString(string: &str) -> String
                 } 
                 false =>
                 { // cb
-                  _i += 3;
-                  _result.push_str(&format!(
+                  i += 3;
+                  result.push_str(&format!(
                     "{}",
                     style::NoBold
                   ));
@@ -158,14 +152,14 @@ pub fn formatString(string: &str) -> String
                 }
               }
             } else
-            if _i+2 < _stringLength && _stringChars[_i+2] == 'f' 
+            if i+2 < stringLength && stringChars[i+2] == 'f' 
             {
-              match _i+3 < _stringLength && _stringChars[_i+3] == 'g' 
+              match i+3 < stringLength && stringChars[i+3] == 'g' 
               {
                 true => 
                 { // cfg
-                  _i += 4;
-                  _result.push_str(&format!(
+                  i += 4;
+                  result.push_str(&format!(
                     "{}",
                     Fg(Reset)
                   ));
@@ -175,8 +169,8 @@ pub fn formatString(string: &str) -> String
               }
             } else 
             { // clear all
-              _i += 2;
-              _result.push_str(&format!(
+              i += 2;
+              result.push_str(&format!(
                 "{}",
                 style::Reset
               ));
@@ -185,19 +179,19 @@ pub fn formatString(string: &str) -> String
           }
           _ => 
           {
-            _result.push_str("\\");
-            _i += 1;
+            result.push_str("\\");
+            i += 1;
             continue;
           }
         }
       // basic
       } else 
       {
-        _result.push( _stringChars[_i] );
+        result.push( stringChars[i] );
       }
-      _i += 1;
+      i += 1;
     }
-    _result.clone()
+    result
   }
 }
 // separator log
@@ -212,11 +206,12 @@ pub fn logSeparator(text: &str) -> ()
 // возвращает описание выхода;
 pub fn logExit(code: i32) -> !
 {
+  let debugMode: bool = runtime().isDebugMode();
   match code == 0 
   {
     true => 
     { // В данном случае завершение успешно;
-      match unsafe{_debugMode}
+      match debugMode
       {
         true  => { formatPrint("   \\b┗\\fg(#1ae96b) Exit 0\\c \\fg(#f0f8ff)\\b:)\\c\n"); }
         false => {}
@@ -225,7 +220,7 @@ pub fn logExit(code: i32) -> !
     }
     false => 
     { // В данном случае завершение не успешное;
-      match unsafe{_debugMode}
+      match debugMode
       {
         true => 
         { 
@@ -243,8 +238,6 @@ pub fn logExit(code: i32) -> !
   }
 }
 // basic style log
-static mut _parts:       Vec<String> = Vec::new();
-static mut _outputParts: Vec<String> = Vec::new();
 pub fn log(textType: &str, text: &str) -> ()
 {
   match textType 
@@ -273,29 +266,29 @@ pub fn log(textType: &str, text: &str) -> ()
     } 
     "parserToken" => 
     { // AST token
-    unsafe{
-      _parts = text.split("|").map(|s| s.to_string()).collect();
-      _outputParts = Vec::new();
+    {
+      let parts: Vec<String> = text.split("|").map(|s| s.to_string()).collect();
+      let mut outputParts: Vec<String> = Vec::new();
       // first word no format
-      match _parts.first() 
+      match parts.first() 
       {
         Some(firstPart) => 
         {
-          _outputParts.push( formatString(firstPart) );
+          outputParts.push( formatString(firstPart) );
         }
         None => {}
       }
       // last word
-      for part in _parts.iter().skip(1) 
+      for part in parts.iter().skip(1) 
       {
-        _outputParts.push(
+        outputParts.push(
           formatString(&format!(
             "\\b\\fg(#d9d9d9){}\\c",
             part
           ))
         );
       }
-      println!("{}", _outputParts.join(""));
+      println!("{}", outputParts.join(""));
     }} 
     "ok" => 
     { // ok
@@ -347,28 +340,28 @@ pub fn log(textType: &str, text: &str) -> ()
     } 
     "path" => 
     { // path
-    unsafe{
-      _parts = text.split("->").map(|s| s.to_string()).collect();
-      _string = 
-        _parts.join(
+    {
+      let parts: Vec<String> = text.split("->").map(|s| s.to_string()).collect();
+      let path: String = 
+        parts.join(
           &formatString("\\fg(#f0f8ff)\\b->\\c")
         );
       formatPrint(&format!(
         "\\fg(#f0f8ff)\\b->\\c \\fg(#f0f8ff){}\\c\n",
-        _string
+        path
       ));
     }} 
     "line" => 
     { // line
-    unsafe{
-      _parts = text.split("|").map(|s| s.to_string()).collect();
-      _outputParts = Vec::new();
+    {
+      let parts: Vec<String> = text.split("|").map(|s| s.to_string()).collect();
+      let mut outputParts: Vec<String> = Vec::new();
       // left
-      match _parts.first() 
+      match parts.first() 
       {
         Some(firstPart) => 
         {
-          _outputParts.push(
+          outputParts.push(
             formatString(&format!(
               "  \\fg(#f0f8ff)\\b{} | \\c",
               firstPart.to_string()
@@ -378,11 +371,11 @@ pub fn log(textType: &str, text: &str) -> ()
         None => {}
       }
       // right
-      for part in _parts.iter().skip(1) 
+      for part in parts.iter().skip(1) 
       {
-        _outputParts.push(part.to_string());
+        outputParts.push(part.to_string());
       }
-      println!("{}",_outputParts.join(""));
+      println!("{}",outputParts.join(""));
     }}  
     _ => 
     { // basic
@@ -13,12 +13,15 @@ include!("prelude.rs");
 
 use std::{
   time::{Instant,Duration},
+  sync::Arc,
   env,
   io::{self, Read},
   fs::File
 };
 use crate::logger::logger::{log, logExit, logSeparator};
-use crate::parser::parser::parseLines;
+use crate::parser::runtime::{runtime, Runtime};
+use crate::parser::budget::{Budget, setBudget};
+use crate::parser::vm::{Engine, setEngine};
 use crate::parser::structure::ffi::zygote;
 use crate::tokenizer::tokenizer::readTokens;
 
@@ -27,6 +30,7 @@ mod tokenizer;
 mod parser;
 mod logger;
 mod packages;
+mod tester;
 
 // =================================================================================================
 
@@ -43,6 +47,12 @@ fn help() -> ()
   log("ok","run");
   log("ok","run <filename>");
   log("ok","run \"<script>\"");
+  log("ok","run --max-steps <lines> --timeout <ms> --max-depth <depth> <filename>");
+  log("ok","run --engine=<tree|vm> <filename>");
+  log("ok","run --seed <number> <filename>");
+  log("ok","test");
+  log("ok","test <paths>");
+  log("ok","test --engines <paths>");
   log("ok","package <empty>");
   log("ok","package help");
   log("ok","package local");
@@ -50,6 +60,64 @@ fn help() -> ()
   logExit(0);
 }
 
+/// Читает параметры бюджета `--max-steps`, `--timeout`, `--max-depth`,
+/// движок `--engine=` и зерно случайных чисел `--seed` перед файлом;
+/// Прочитанные параметры удаляются из values
+fn readOptions(values: &mut Vec<String>) -> Budget
+{
+  let mut budget: Budget = Budget::default();
+  while values.len() >= 1 && values[0].starts_with("--")
+  {
+    match values[0].split_once('=')
+    {
+      Some(("--engine", engine)) =>
+      {
+        setEngine( match engine
+        {
+          "tree" => Engine::Tree,
+          "vm"   => Engine::Vm,
+          _ =>
+          {
+            log("err", &format!("Unknown engine [{}]", engine));
+            logExit(1)
+          }
+        });
+        values.remove(0);
+        continue;
+      }
+      _ => {}
+    }
+    match values.len() >= 2
+    {
+      true  => {}
+      false => break
+    }
+    let number: usize = match values[1].parse::<usize>()
+    {
+      Ok(number) => number,
+      Err(_) =>
+      {
+        log("err", &format!("Expected number after [{}]", values[0]));
+        logExit(1)
+      }
+    };
+    match values[0].as_str()
+    {
+      "--max-steps" => budget.maxSteps = Some(number),
+      "--timeout"   => budget.timeout  = Some( Duration::from_millis(number as u64) ),
+      "--max-depth" => budget.maxDepth = Some(number),
+      "--seed"      => runtime().setSeed( Some(number as u64) ),
+      _ =>
+      {
+        log("err", &format!("Unknown option [{}]", values[0]));
+        logExit(1)
+      }
+    }
+    values.drain(..2);
+  }
+  budget
+}
+
 /// Основной метод для бинарника RTS;
 /// Позволяет работать с параметрами;
 /// Обладает режимом чтения файла или скрипта из строки;
@@ -83,9 +151,13 @@ fn main() -> io::Result<()>
     }
   }
   
+  // Программа запускается в среде выполнения по умолчанию
+  let runtime: Arc<Runtime> = runtime();
+
   // read key
   let mut runFile: bool = false;
   let mut buffer:  Vec<u8> = Vec::new();
+  let mut testStructure: Option<String> = None;
 
   let valuesLength: usize = (args.1).len();
 
@@ -107,38 +179,50 @@ fn main() -> io::Result<()>
           // packageApi(&args.1,valuesLength).await; todo
           logExit(0);
         },
+        "test" =>
+        { // test
+          logExit( tester::tester::runTests(&args.1) );
+        }
+        "test-run" if valuesLength >= 2 =>
+        { // Запуск одной test* структуры файла, используется командой test
+          readOptions(&mut args.1);
+          runtime.setFilePath( args.1[0].clone() );
+          testStructure = Some( args.1[1].clone() );
+          runFile = true;
+        }
         _ if (key == "run" || key == "drun") && valuesLength >= 1 =>
         { // run
+          setBudget( readOptions(&mut args.1) );
+          let valuesLength: usize = (args.1).len();
+          match valuesLength >= 1
+          {
+            true  => {}
+            false =>
+            {
+              log("err","Use [rts help] to get help");
+              logExit(1)
+            }
+          }
 
           match key == "drun" 
           { // debug mode ?
             false => {}
-            true  => unsafe{_debugMode = true;}
+            true  => runtime.setDebugMode(true)
           }
 
-          unsafe
-          {
-            _argc = valuesLength-1;
-            _argv = (args.1)[1..].to_vec();
-            _filePath = args.1[0].clone();
-          }
+          runtime.setArguments( (args.1)[1..].to_vec() );
+          runtime.setFilePath( args.1[0].clone() );
 
-          match unsafe{_debugMode} 
+          match runtime.isDebugMode()
           {
             false => {}
-            true  => { log("ok",&format!("Run [{}]",unsafe{&*_filePath})); }
+            true  => { log("ok",&format!("Run [{}]",runtime.getFilePath())); }
           }
 
-          unsafe{
-            // Проверяем, что мы запускаем файл или скрипт;
-            // todo: В данном случае это является временным решением,
-            //       чтобы сохранить run и drun, а также разделить скрипт и файлы;
-            let filePathEnd: String =
-              _filePath
-                .chars().rev().take(3)
-                .collect::<Vec<_>>().iter().rev().collect();
-            runFile = filePathEnd == ".rt";
-          }
+          // Проверяем, что мы запускаем файл или скрипт;
+          // todo: В данном случае это является временным решением,
+          //       чтобы сохранить run и drun, а также разделить скрипт и файлы;
+          runFile = runtime.getFilePath().ends_with(".rt");
 
           // run package
           // todo: run package
@@ -151,7 +235,7 @@ fn main() -> io::Result<()>
     }
   }
 
-  match unsafe{_debugMode}
+  match runtime.isDebugMode()
   {
     false => {}
     true => 
@@ -166,17 +250,17 @@ fn main() -> io::Result<()>
   {
     true => 
     { // Обработка файла
-      match unsafe{_debugMode} 
+      match runtime.isDebugMode()
       {
-        true  => { logSeparator(&format!("Running the file [{}] in debug mode",unsafe{&*_filePath})); }
+        true  => { logSeparator(&format!("Running the file [{}] in debug mode",runtime.getFilePath())); }
         false => {}
       }
       // open file
-      let mut file: File = match File::open(unsafe{&*_filePath}) 
+      let mut file: File = match File::open(runtime.getFilePath()) 
       {
         Ok(file) => 
         {
-          match unsafe{_debugMode} 
+          match runtime.isDebugMode()
           {
             true  => { log("ok","Opening was successful"); }
             false => {}
@@ -194,7 +278,7 @@ fn main() -> io::Result<()>
       {
         Ok(_) => 
         {
-          match unsafe{_debugMode} 
+          match runtime.isDebugMode()
           {
             true  => { log("ok","Reading was successful"); }
             false => {}
@@ -206,24 +290,30 @@ fn main() -> io::Result<()>
           logExit(1)
         }
       }
+      // test structure call after the file
+      match &testStructure
+      { None => {} Some(testStructure) =>
+      {
+        buffer.extend_from_slice( format!("\n{}()\n", testStructure).as_bytes() );
+      }}
     }
     false =>
     { // Обработка скрипта
       // run script
-      match unsafe{_debugMode}
+      match runtime.isDebugMode()
       {
         false => {}
         true  => { logSeparator("Running the script in debug mode"); }
       }
 
-      unsafe{ buffer = _filePath.clone().into_bytes(); }
+      buffer = runtime.getFilePath().into_bytes();
     }
   }
 
   // Начинаем чтение кода
-  parseLines( readTokens(buffer, unsafe{_debugMode}) );
+  let exitCode: i32 = runtime.run( readTokens(buffer, runtime.isDebugMode()) );
   
-  match unsafe{_debugMode} 
+  match runtime.isDebugMode()
   {
     // Замеры всего прошедшего времени работы
     false => {}
@@ -237,5 +327,5 @@ fn main() -> io::Result<()>
   // ** Для дополнительных тестов можно использовать hyperfine/perf
 
   // Возвращаем код завершения
-  logExit(unsafe{_exitCode});
+  logExit(exitCode);
 }
@@ -0,0 +1,330 @@
+use std::cell::Cell;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+#[cfg(not(target_family = "wasm"))]
+use crate::logger::logger::formatPrintError;
+use crate::parser::runtime::{readStackSize, withRuntime, Runtime};
+// =================================================================================================
+
+// Бюджет выполнения;
+// Ограничивает количество прочитанных линий, время работы и глубину вложенных вызовов.
+// Когда бюджет исчерпан, программа останавливается как через exit(),
+// но с кодом budgetExitCode, а в stderr выводится причина и структура, которая выполнялась.
+// Блокирующие ожидания (sleep, каналы, join, process) идут шагами waitSlice,
+// поэтому их тоже прерывает время бюджета и exit().
+//
+// Из CLI: rts run --max-steps 1000 --timeout 500 --max-depth 64 file.rt
+// Из библиотеки: RTS::setBudget(Budget { ... })
+
+// =================================================================================================
+
+/// Код завершения программы, остановленной из-за исчерпания бюджета
+pub const budgetExitCode: i32 = 124;
+
+/// Глубина вложенного чтения структур, если она не указана;
+/// Бесконечная рекурсия останавливается бюджетом, а не переполнением стека
+pub const defaultMaxDepth: usize = 10_000;
+
+/// Стек, который занимает один уровень вложенности, с запасом;
+/// В отладочной сборке кадры функций в несколько раз больше
+#[cfg(debug_assertions)]
+const depthStackSize: usize = 40*1024;
+#[cfg(not(debug_assertions))]
+const depthStackSize: usize = 16*1024;
+
+/// Наибольшая глубина, которую выдерживает стек потока чтения (readStackSize);
+/// maxDepth больше неё уменьшается до неё
+pub const stackMaxDepth: usize = readStackSize/depthStackSize;
+
+/// Ограничения выполнения; None - без ограничения
+#[derive(Clone, Debug, Default)]
+pub struct Budget
+{
+  /// Наибольшее количество прочитанных линий
+  pub maxSteps: Option<usize>,
+  /// Наибольшее время работы
+  pub timeout: Option<Duration>,
+  /// Наибольшая глубина вложенного чтения структур в одном потоке: вызовы методов и блоки;
+  /// Цикл на go() в конце блока идёт на месте и глубину не расходует.
+  /// None - defaultMaxDepth; Не больше stackMaxDepth, т.к. стек потока всё равно ограничен
+  pub maxDepth: Option<usize>
+}
+
+/// Бюджет одной среды выполнения
+#[derive(Default)]
+pub(crate) struct BudgetState
+{
+  /// Текущие ограничения
+  limits: Mutex<Budget>,
+  /// Время, после которого выполнение останавливается
+  deadline: Mutex< Option<Instant> >,
+  /// Прочитано линий во всех потоках
+  steps: AtomicUsize
+}
+
+thread_local!
+{
+  /// Глубина вложенного чтения структур в текущем потоке
+  static Depth: Cell<usize> = const { Cell::new(0) };
+}
+
+// =================================================================================================
+
+impl Runtime
+{
+  /// Устанавливает ограничения выполнения
+  pub fn setBudget(&self, budget: Budget) -> ()
+  {
+    *self.budget.limits.lock().unwrap() = budget;
+  }
+}
+
+/// Устанавливает ограничения выполнения текущей среды
+pub fn setBudget(budget: Budget) -> ()
+{
+  withRuntime(|runtime: &Runtime| runtime.setBudget(budget));
+}
+
+/// Начинает отсчёт бюджета заново; Вызывается перед запуском кода
+pub(crate) fn startBudget() -> ()
+{
+  withRuntime(|runtime: &Runtime|
+  {
+    runtime.budget.steps.store(0, Ordering::SeqCst);
+    *runtime.budget.deadline.lock().unwrap() = runtime.budget.limits.lock().unwrap().timeout
+      .map(|timeout: Duration| Instant::now()+timeout);
+  });
+}
+
+/// Останавливает программу и выводит причину
+fn exhaust(runtime: &Runtime, reason: &str, source: &str) -> ()
+{
+  // Если программа уже останавливается, то повторно не сообщаем
+  match runtime.exitOnce(budgetExitCode)
+  {
+    false => return,
+    true  => {}
+  }
+  #[cfg(not(target_family = "wasm"))]
+  formatPrintError(&format!(
+    "   \\fg(#e91a34)\\b-\\cb\\cfg \\fg(#f0f8ff)\\bBudget exhausted [{}] in [{}]\\c\n",
+    reason,
+    source
+  ));
+}
+
+// =================================================================================================
+
+/// Засчитывает прочитанную линию и проверяет время;
+/// Возвращает false, если бюджет исчерпан
+pub(crate) fn chargeStep(name: &str, lineIndex: usize) -> bool
+{
+  withRuntime(|runtime: &Runtime|
+  {
+    let steps: usize = runtime.budget.steps.fetch_add(1, Ordering::Relaxed)+1;
+    match runtime.budget.limits.lock().unwrap().maxSteps
+    {
+      Some(maxSteps) if steps > maxSteps =>
+      {
+        exhaust(runtime, "steps", &runtime.formatSource(name, lineIndex));
+        return false;
+      }
+      _ => {}
+    }
+    match isTimeOut(runtime)
+    {
+      true  =>
+      {
+        exhaust(runtime, "timeout", &runtime.formatSource(name, lineIndex));
+        false
+      }
+      false => true
+    }
+  })
+}
+
+/// Проверяет, что время выполнения вышло
+fn isTimeOut(runtime: &Runtime) -> bool
+{
+  runtime.budget.deadline.lock().unwrap()
+    .is_some_and(|deadline: Instant| Instant::now() >= deadline)
+}
+
+/// Проверяет время во время ожидания, когда линии не читаются;
+/// Возвращает наибольшее время, которое ещё можно ждать
+pub(crate) fn checkTime(source: &str) -> Option<Duration>
+{
+  withRuntime(|runtime: &Runtime|
+    match isTimeOut(runtime)
+    {
+      true  =>
+      {
+        exhaust(runtime, "timeout", source);
+        Some(Duration::ZERO)
+      }
+      false => runtime.budget.deadline.lock().unwrap()
+        .map(|deadline: Instant| deadline.saturating_duration_since(Instant::now()))
+    }
+  )
+}
+
+/// Наибольший шаг блокирующего ожидания, после которого ждущий снова проверяет exit и время
+const waitInterval: Duration = Duration::from_millis(10);
+
+/// Получает, сколько можно ждать до следующей проверки: не больше wait и waitInterval,
+/// и не дольше времени бюджета; None, если программа завершается и ждать больше не нужно
+pub(crate) fn waitSlice(source: &str, wait: Option<Duration>) -> Option<Duration>
+{
+  match withRuntime(|runtime: &Runtime| runtime.isExit())
+  { false => {} true =>
+  {
+    return None;
+  }}
+  let wait: Duration = wait.map_or(waitInterval, |wait: Duration| wait.min(waitInterval));
+  match checkTime(source)
+  {
+    None => Some(wait),
+    Some(left) if left.is_zero() => None,
+    Some(left) => Some(wait.min(left))
+  }
+}
+
+/// Входит во вложенное чтение структуры;
+/// Возвращает false, если глубина исчерпана, тогда выходить не нужно
+pub(crate) fn enterDepth(name: &str, lineIndex: usize) -> bool
+{
+  let depth: usize = Depth.with(|depth: &Cell<usize>| depth.get())+1;
+  withRuntime(|runtime: &Runtime|
+    match depth > runtime.budget.limits.lock().unwrap().maxDepth.unwrap_or(defaultMaxDepth).min(stackMaxDepth)
+    {
+      true =>
+      {
+        exhaust(runtime, "depth", &runtime.formatSource(name, lineIndex));
+        false
+      }
+      _ =>
+      {
+        Depth.with(|cell: &Cell<usize>| cell.set(depth));
+        true
+      }
+    }
+  )
+}
+
+/// Выходит из вложенного чтения структуры
+pub(crate) fn leaveDepth() -> ()
+{
+  Depth.with(|depth: &Cell<usize>| depth.set(depth.get().saturating_sub(1)));
+}
+
+// =================================================================================================
+
+#[cfg(test)]
+mod tests
+{
+  use std::io;
+  use std::sync::Arc;
+  use std::time::Duration;
+  use crate::parser::budget::{budgetExitCode, Budget};
+  use crate::parser::runtime::Runtime;
+  use crate::parser::vm::Engine;
+  use crate::tokenizer::tokenizer::readTokens;
+  // ===============================================================================================
+
+  /// Метод без выхода из рекурсии
+  const endlessRecursion: &str = "f(n) =\n  = f(n + 1)\nprintln(f(0))\n";
+
+  /// Вспомогательная функция:
+  /// Запускает код с ограничениями и получает код завершения
+  fn runWithBudget(script: &str, budget: Budget) -> i32
+  {
+    let runtime: Arc<Runtime> = Runtime::new();
+    runtime.setOutput(Box::new(io::sink()));
+    runtime.setBudget(budget);
+    runtime.run( readTokens(script.as_bytes().to_vec(), false) )
+  }
+
+  // ===============================================================================================
+
+  /// Проверяет, что бесконечная рекурсия останавливается по глубине, а не зависает
+  #[test]
+  fn recursionDepth() -> ()
+  {
+    for maxDepth in [3, 10, 30, 50]
+    {
+      let code: i32 = runWithBudget(endlessRecursion, Budget { maxDepth: Some(maxDepth), ..Budget::default() });
+      assert_eq!(code, budgetExitCode, "Глубина {}: ожидалась остановка по бюджету", maxDepth);
+    }
+  }
+
+  /// Проверяет, что бесконечная рекурсия останавливается по линиям, а не переполняет стек
+  #[test]
+  fn recursionSteps() -> ()
+  {
+    for maxSteps in [1, 5, 1000]
+    {
+      let code: i32 = runWithBudget(endlessRecursion, Budget { maxSteps: Some(maxSteps), ..Budget::default() });
+      assert_eq!(code, budgetExitCode, "Линии {}: ожидалась остановка по бюджету", maxSteps);
+    }
+  }
+
+  /// Проверяет, что без ограничений рекурсия останавливается по глубине по умолчанию
+  #[test]
+  fn recursionDefaultDepth() -> ()
+  {
+    assert_eq!(runWithBudget(endlessRecursion, Budget::default()), budgetExitCode);
+  }
+
+  /// Проверяет, что ожидание в каналах, потоках и процессах прерывается временем бюджета
+  #[test]
+  fn blockingWaits() -> ()
+  {
+    let scripts: [&str; 5] = [
+      "c = channel(U8)\nprintln(recv(c))\n",
+      "c = channel(U8)\nprintln(select(c))\n",
+      "c = channel(U8, 1)\nsend(c, 1)\nsend(c, 2)\n",
+      "w() =\n  c = channel(U8)\n  = recv(c)\nprintln(join(spawn(w)))\n",
+      "r = process(\"sh\", \"-c\", \"sleep 5 & sleep 5\")\n"
+    ];
+    for script in scripts
+    {
+      let code: i32 = runWithBudget(script, Budget { timeout: Some(Duration::from_millis(100)), ..Budget::default() });
+      assert_eq!(code, budgetExitCode, "Ожидалась остановка по времени:\n{}", script);
+    }
+  }
+
+  /// Проверяет, что цикл на go() не расходует глубину вложенности ни на одном движке
+  #[test]
+  fn goLoopDepth() -> ()
+  {
+    let script: &str = "i~: UInt = 0\n? i < 20000\n  i++\n  ? i > 20000\n    println(i)\n  go(1)\n";
+    for engine in [Engine::Tree, Engine::Vm]
+    {
+      let runtime: Arc<Runtime> = Runtime::new();
+      runtime.setOutput(Box::new(io::sink()));
+      runtime.setEngine(engine);
+      runtime.setBudget(Budget { maxDepth: Some(10), ..Budget::default() });
+      assert_eq!(runtime.run( readTokens(script.as_bytes().to_vec(), false) ), 0, "{:?}", engine);
+    }
+  }
+
+  /// Проверяет, что глубина больше той, что выдерживает стек, останавливается бюджетом
+  #[test]
+  fn recursionStackDepth() -> ()
+  {
+    let code: i32 = runWithBudget(endlessRecursion, Budget { maxDepth: Some(usize::MAX), ..Budget::default() });
+    assert_eq!(code, budgetExitCode);
+  }
+
+  /// Проверяет, что рекурсия, которая укладывается в бюджет, завершается как обычно
+  #[test]
+  fn recursionWithinBudget() -> ()
+  {
+    let script: &str = "fact(n) =\n  = match n\n    0 -> 1\n    ? -> n * fact(n - 1)\nprintln(fact(20))\n";
+    let budget: Budget = Budget { maxSteps: Some(10_000), timeout: Some(Duration::from_secs(10)), maxDepth: Some(100) };
+    assert_eq!(runWithBudget(script, budget), 0);
+  }
+
+  // ===============================================================================================
+}
@@ -0,0 +1,333 @@
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use crate::parser::parser::readLines;
+use crate::parser::structure::structure::{Structure, StructureMut};
+use crate::parser::structure::structureType::StructureType;
+use crate::parser::structure::tokenValue::calculate::calculate;
+use crate::tokenizer::types::line::Line;
+use crate::tokenizer::types::token::Token;
+use crate::tokenizer::types::tokenType::TokenType;
+// =================================================================================================
+
+// Блок сопоставления (match);
+// В отличие от цепочки `? cond`, здесь выражение-субъект вычисляется только один раз,
+// после чего каждое вложение-ветка проверяется против него сверху вниз:
+//
+//   match x
+//     1 -> println("one")      # литерал
+//     2..5 -> println("few")   # диапазон (включительно)
+//     UInt -> println("uint")  # type() имя
+//     U8 -> println("u8")      # stype() имя
+//     None -> println("none")  # пустое значение
+//     (a, Any, 3)              # форма списка со связыванием a
+//       println(a)
+//     ?                        # ветка по умолчанию
+//       println("other")
+//
+// Может использоваться как выражение: `y = match x`, тогда ветки отдают значение
+// через `-> value` или через `= value` внутри вложения.
+
+// =================================================================================================
+
+/// Ключевое слово блока сопоставления
+pub(super) const matchKeyword: &str = "match";
+
+/// Проверяет, что токен является ключевым словом match
+pub(super) fn isMatchKeyword(token: &Token) -> bool
+{
+  *token.getDataType() == TokenType::Word &&
+    token.getData().toString().as_deref() == Some(matchKeyword)
+}
+
+// =================================================================================================
+
+/// Вычисляет субъект, после чего ищет первую подходящую ветку и запускает её;
+/// Возвращает значение ветки, либо None, если ни одна ветка не подошла
+pub(super) fn matchStructure(
+  arms: &[Arc<RwLock<Line>>],
+  parentLink: Arc<RwLock<Structure>>,
+  mut subjectTokens: Vec<Token>
+) -> Token
+{
+  // Если субъект это просто имя структуры, то запоминаем её для stype() и списков
+  let subjectStructure: Option< Arc<RwLock<Structure>> > =
+    match subjectTokens.len() == 1 && *subjectTokens[0].getDataType() == TokenType::Word
+    {
+      false => None,
+      true =>
+        parentLink.read().unwrap()
+          .getStructureByName( &subjectTokens[0].getData().toString().unwrap_or_default() )
+    };
+  // Субъект вычисляется ровно один раз
+  let subject: Token =
+  {
+    let parent: RwLockReadGuard<Structure> = parentLink.read().unwrap();
+    parent.expression(&mut subjectTokens)
+  };
+  let subjectElements: Option< Vec<Token> > = getSubjectElements(&parentLink, &subject, &subjectStructure);
+
+  for armLink in arms
+  {
+    let arm: RwLockReadGuard<Line> = armLink.read().unwrap();
+    let armTokens: &Vec<Token> = match &arm.tokens
+    {
+      None => continue, // Пустые линии не являются ветками
+      Some(tokens) => tokens
+    };
+
+    // Делим ветку на образец и значение после ->
+    let pointerIndex: Option<usize> = armTokens.iter()
+      .position(|token: &Token| *token.getDataType() == TokenType::Pointer);
+    let (pattern, valueTokens): (&[Token], Option< Vec<Token> >) = match pointerIndex
+    {
+      None => (&armTokens[..], None),
+      Some(pointerIndex) => (&armTokens[..pointerIndex], Some(armTokens[pointerIndex+1..].to_vec()))
+    };
+
+    // Проверяем образец, получая возможные связывания
+    let mut bindings: Vec<(String, Token)> = Vec::new();
+    let isDefault: bool = pattern.len() == 1 && *pattern[0].getDataType() == TokenType::Question;
+    match isDefault ||
+          matchPattern(&parentLink, pattern, &subject, &subjectStructure, &subjectElements, &mut bindings)
+    {
+      false => continue,
+      true => {}
+    }
+
+    // Ветка подошла, создаём временную структуру ветки
+    let armStructure: Structure = Structure::newBlock(matchKeyword, arm.lines.clone(), parentLink.clone());
+    for (name, value) in bindings
+    {
+      armStructure.pushStructure(
+        Arc::new(RwLock::new(Structure::new(
+          Some(name),
+          StructureMut::Constant,
+          value.clone().getStructureType(),
+          Some(vec![ Arc::new(RwLock::new(Line {
+            tokens: Some(vec![value]),
+            indent: None,
+            lines:  None,
+            parent: None
+          })) ]),
+          None
+        )))
+      );
+    }
+    let armStructureLink: Arc<RwLock<Structure>> = Arc::new(RwLock::new(armStructure));
+    drop(arm);
+
+    // Значение ветки в одну линию
+    let mut result: Token = match valueTokens
+    {
+      None => Token::newEmpty(TokenType::None),
+      Some(mut valueTokens) => match valueTokens.is_empty()
+      {
+        true  => Token::newEmpty(TokenType::None),
+        false => armStructureLink.read().unwrap().expression(&mut valueTokens)
+      }
+    };
+    // Вложение ветки; может вернуть значение через `= value`
+    readLines(armStructureLink.clone());
+    match armStructureLink.read().unwrap().result.clone()
+    { None => {} Some(armResult) =>
+    {
+      result = armResult;
+    }}
+    return result;
+  }
+
+  // Ни одна ветка не подошла
+  Token::newEmpty(TokenType::None)
+}
+
+// =================================================================================================
+
+/// Получает элементы субъекта, если он является списком
+fn getSubjectElements(
+  parentLink: &Arc<RwLock<Structure>>,
+  subject: &Token,
+  subjectStructure: &Option< Arc<RwLock<Structure>> >
+) -> Option< Vec<Token> >
+{
+  // Вложенное значение (например, результат обращения к структуре с вложением)
+  match &subject.lines
+  { None => {} Some(lines) =>
+  {
+    return lines.first()
+      .map(|line: &Line| line.tokens.clone().unwrap_or_default());
+  }}
+
+  // Структура с несколькими линиями
+  let structureLink: Arc<RwLock<Structure>> = match subjectStructure
+  {
+    Some(structureLink) => structureLink.clone(),
+    None => match *subject.getDataType() == TokenType::Link
+    {
+      false => return None,
+      true => parentLink.read().unwrap()
+        .getStructureByName( &subject.getData().toString().unwrap_or_default() )?
+    }
+  };
+  let lines: Vec< Arc<RwLock<Line>> > = structureLink.read().unwrap().lines.clone()?;
+  match lines.len() > 1
+  {
+    false => None,
+    true =>
+    {
+      let parent: RwLockReadGuard<Structure> = parentLink.read().unwrap();
+      Some(
+        lines.iter()
+          .map(|line: &Arc<RwLock<Line>>|
+            parent.expression( &mut line.read().unwrap().tokens.clone().unwrap_or_default() )
+          )
+          .collect()
+      )
+    }
+  }
+}
+
+/// Проверяет равенство двух значений через calculate
+fn isEqual(left: &Token, right: &Token) -> bool
+{
+  calculate(&TokenType::Equals, left, right).getData().toString().as_deref() == Some("1")
+}
+
+/// Проверяет образец ветки против субъекта
+fn matchPattern(
+  parentLink: &Arc<RwLock<Structure>>,
+  pattern: &[Token],
+  subject: &Token,
+  subjectStructure: &Option< Arc<RwLock<Structure>> >,
+  subjectElements: &Option< Vec<Token> >,
+  bindings: &mut Vec<(String, Token)>
+) -> bool
+{
+  match pattern.len()
+  {
+    0 => false,
+    1 =>
+    {
+      let patternToken: &Token = &pattern[0];
+      match *patternToken.getDataType()
+      {
+        TokenType::Any => true, // Подходит всё
+        TokenType::None => *subject.getDataType() == TokenType::None,
+        TokenType::CircleBracketBegin =>
+        { // Форма списка
+          match subjectElements
+          {
+            None => false,
+            Some(elements) => matchListPattern(parentLink, patternToken, elements, bindings)
+          }
+        }
+        TokenType::Word =>
+        {
+          match patternToken.getStructureTypeSimple()
+          {
+            StructureType::Custom(_) =>
+            { // Это имя структуры, сравниваем с её значением
+              let value: Token = parentLink.read().unwrap()
+                .expression(&mut vec![patternToken.clone()]);
+              isEqual(subject, &value)
+            }
+            patternType =>
+            { // Это stype() имя
+              let subjectType: StructureType = match subjectStructure
+              {
+                Some(structureLink) => structureLink.read().unwrap().dataType.clone(),
+                None => subject.clone().getStructureType()
+              };
+              subjectType == patternType
+            }
+          }
+        }
+        _ =>
+        { // type() имя у ключевых слов без данных, либо литерал
+          match patternToken.isPrimitive() && patternToken.getData().getAll().is_none()
+          {
+            true  => subject.getDataType().to_string() == patternToken.getDataType().to_string(),
+            false =>
+            {
+              let value: Token = parentLink.read().unwrap()
+                .expression(&mut vec![patternToken.clone()]);
+              isEqual(subject, &value)
+            }
+          }
+        }
+      }
+    }
+    _ =>
+    { // Диапазон `from..to`, включительно
+      let dotIndex: Option<usize> = pattern.iter()
+        .position(|token: &Token| *token.getDataType() == TokenType::Dot);
+      match dotIndex
+      {
+        Some(dotIndex) if dotIndex+1 < pattern.len() && *pattern[dotIndex+1].getDataType() == TokenType::Dot =>
+        {
+          let parent: RwLockReadGuard<Structure> = parentLink.read().unwrap();
+          let from: Token = parent.expression(&mut pattern[..dotIndex].to_vec());
+          let to:   Token = parent.expression(&mut pattern[dotIndex+2..].to_vec());
+          calculate(&TokenType::GreaterThanOrEquals, subject, &from).getData().toString().as_deref() == Some("1") &&
+            calculate(&TokenType::LessThanOrEquals, subject, &to).getData().toString().as_deref() == Some("1")
+        }
+        _ =>
+        { // Иначе это выражение, сравниваем с его значением
+          let value: Token = parentLink.read().unwrap()
+            .expression(&mut pattern.to_vec());
+          isEqual(subject, &value)
+        }
+      }
+    }
+  }
+}
+
+/// Проверяет форму списка `(a, Any, 3)`; слова связываются с элементами
+fn matchListPattern(
+  parentLink: &Arc<RwLock<Structure>>,
+  patternToken: &Token,
+  elements: &[Token],
+  bindings: &mut Vec<(String, Token)>
+) -> bool
+{
+  let patternLines: &Vec<Line> = match &patternToken.lines
+  {
+    None => return elements.is_empty(),
+    Some(lines) => lines
+  };
+  match patternLines.len() == elements.len()
+  {
+    false => return false,
+    true => {}
+  }
+
+  for (patternLine, element) in patternLines.iter().zip(elements.iter())
+  {
+    let elementPattern: Vec<Token> = patternLine.tokens.clone().unwrap_or_default();
+    match elementPattern.len() == 1
+    {
+      true => match *elementPattern[0].getDataType()
+      {
+        TokenType::Any => {}
+        TokenType::Word =>
+        { // Связывание
+          bindings.push((
+            elementPattern[0].getData().toString().unwrap_or_default(),
+            element.clone()
+          ));
+        }
+        _ => match matchPattern(parentLink, &elementPattern, element, &None, &None, bindings)
+        {
+          true => {}
+          false => return false
+        }
+      }
+      false => match matchPattern(parentLink, &elementPattern, element, &None, &None, bindings)
+      {
+        true => {}
+        false => return false
+      }
+    }
+  }
+  true
+}
+
+// =================================================================================================
@@ -1,3 +1,9 @@
 pub mod bytes;
 pub(super) mod parser;
+mod matchBlock;
+pub(crate) mod unexpected;
+pub(crate) mod reactive;
+pub mod budget;
+pub mod vm;
+pub mod runtime;
 pub mod structure;
\ No newline at end of file
@@ -1,18 +1,27 @@
-use std::sync::{Arc, LazyLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
-use crate::{_argc, _argv, _exit};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use crate::parser::runtime::{runtime, withRuntime, Runtime};
 use crate::parser::bytes::Bytes;
-use crate::parser::structure::structure::{Structure, StructureMut};
+use crate::parser::structure::structure::{pushFields, valueLines, Structure, StructureMut};
 use crate::tokenizer::types::line::Line;
 use crate::tokenizer::types::token::{Token};
 use crate::tokenizer::types::tokenType::{TokenType};
 use crate::tokenizer::tools::splitByType::splitByType;
+use crate::parser::matchBlock::{isMatchKeyword, matchStructure};
+use crate::parser::structure::methods::import::searchImport;
+use crate::parser::unexpected::{searchHandler, unexpected};
+use crate::parser::reactive::searchReaction;
+use crate::parser::structure::ir::{getLineIr, Ir};
+use crate::parser::budget::{chargeStep, enterDepth, leaveDepth, startBudget};
+use crate::parser::vm::{isVmEngine, runLine};
+use crate::parser::structure::methods::timer::runEventLoop;
+#[cfg(not(target_family = "wasm"))]
+use crate::parser::structure::methods::timer::timerReport;
 #[cfg(not(target_family = "wasm"))]
-use crate::_debugMode;
 #[cfg(not(target_family = "wasm"))]
 use crate::logger::logger::{log, logSeparator};
 #[cfg(not(target_family = "wasm"))]
 use std::time::{Duration, Instant};
-use crate::parser::structure::structureType::StructureType;
+use crate::parser::structure::structureType::{Overflow, StructureType};
 // =================================================================================================
 
 // Предоставляет механизмы для парсинга токенов,
@@ -21,7 +30,7 @@ use crate::parser::structure::structureType::StructureType;
 // =================================================================================================
 
 /// Проверяет, что переданный dataType является математическим оператором
-fn isMathOperator(dataType: TokenType) -> bool 
+pub(super) fn isMathOperator(dataType: TokenType) -> bool 
 {
   matches!(dataType, 
     TokenType::Equals         | // =
@@ -79,28 +88,7 @@ fn searchReturn(line: &RwLockReadGuard<Line>, structureLink: Arc<RwLock<Structur
         structure.expression(&mut lineTokens)
       };
 
-      let mut structure: RwLockWriteGuard<Structure> = structureLink.write().unwrap();
-
-      // Структура ожидает какой-то тип в результате,
-      // либо это может быть TokenType:None. Но мы просто будем менять data
-
-      match structure.result
-      {
-        Some(_) =>
-        { // Вариант, в котором результат ожидает возвращение определённого типа данных;
-          match &mut structure.result
-          { None => {} Some(structureResult) =>
-          { // Присваиваем новую data результату;
-            structureResult.setData( newResultData.getData() );
-          }}
-        }
-        _ =>
-        { // Вариант, в котором тип результата был не указан;
-          // Используем expression, чтобы получить результат выражения;
-          // Присваиваем новый результат;
-          structure.result = Some( newResultData );
-        }
-      }
+      setResult(&structureLink, newResultData);
 
       // Всё успешно, это был результат
       true
@@ -108,10 +96,39 @@ fn searchReturn(line: &RwLockReadGuard<Line>, structureLink: Arc<RwLock<Structur
   }
 }
 
+/// Присваивает результат структуры
+pub(crate) fn setResult(structureLink: &Arc<RwLock<Structure>>, newResultData: Token) -> ()
+{
+  let mut structure: RwLockWriteGuard<Structure> = structureLink.write().unwrap();
+
+  // Структура ожидает какой-то тип в результате,
+  // либо это может быть TokenType:None. Но мы просто будем менять data
+
+  match structure.result
+  {
+    Some(_) =>
+    { // Вариант, в котором результат ожидает возвращение определённого типа данных;
+      match &mut structure.result
+      { None => {} Some(structureResult) =>
+      { // Присваиваем новую data результату;
+        structureResult.setData( newResultData.getData() );
+      }}
+    }
+    _ =>
+    { // Вариант, в котором тип результата был не указан;
+      // Используем expression, чтобы получить результат выражения;
+      // Присваиваем новый результат;
+      structure.result = Some( newResultData );
+    }
+  }
+}
+
 // =================================================================================================
 
 /// Читает линейную запись
-fn linearStructure(lineTokens: &Vec<Token>, parentLink: Arc<RwLock<Structure>>) -> bool 
+/// Линейная запись `a = value`;
+/// value - правая часть, если она уже посчитана (виртуальной машиной), иначе она считается здесь
+pub(super) fn linearStructure(lineTokens: &Vec<Token>, value: Option<Token>, parentLink: Arc<RwLock<Structure>>) -> bool 
 {
   // Получаем тип операции
   let opType: TokenType = lineTokens.iter().find_map(|token| 
@@ -134,7 +151,8 @@ fn linearStructure(lineTokens: &Vec<Token>, parentLink: Arc<RwLock<Structure>>)
         let mut parts: Vec<Line> = splitByType(lineTokens.clone(), &[opType]); // todo: Тут точно клонирование ?
 
         leftValue = std::mem::take(&mut parts[0].tokens).unwrap();
-        rightValue = std::mem::take(&mut parts[1].tokens);
+        // У ++ -- ** // %% ^^ правой части может не быть
+        rightValue = parts.get_mut(1).and_then(|part: &mut Line| std::mem::take(&mut part.tokens));
       }
       true => 
       { // Операции не было
@@ -146,6 +164,7 @@ fn linearStructure(lineTokens: &Vec<Token>, parentLink: Arc<RwLock<Structure>>)
   let structureName: String;
   let structureMutability: StructureMut;
   let mut structureType: StructureType;
+  let structureOverflow: Overflow;
   { // Определяем тип данных у левой части выражения
     let (structureNameTokens, structureTypeTokens): (Vec<Token>, Option< Vec<Token> >) = 
     {
@@ -179,22 +198,28 @@ fn linearStructure(lineTokens: &Vec<Token>, parentLink: Arc<RwLock<Structure>>)
     //
     structureName = structureNameTokens[0].getData().toString().unwrap(); // Имя точно есть
     structureMutability = structureMutabilityType;
-    structureType = match structureTypeTokens 
+    structureType = match &structureTypeTokens 
     {
       None => StructureType::None,
       Some(structureTypeTokens) =>
         structureTypeTokens[0].getStructureTypeSimple()
     };
+    // После типа может быть указана политика переполнения: `a: U8 wrap`
+    structureOverflow = structureTypeTokens.as_ref()
+      .and_then(|structureTypeTokens: &Vec<Token>| structureTypeTokens.get(1))
+      .and_then(Overflow::fromToken)
+      .unwrap_or_default();
   };
   
   drop(leftValue);
 
   // Получаем родительскую структуру;
-  // Ищем в родительской структуре, есть ли там похожая на structureName
+  // Ищем в родительской структуре, есть ли там похожая на structureName;
+  // Выше метода не смотрим: запись в методе объявляет локальную структуру, а не меняет внешнюю
   let structureLink: Option< Arc<RwLock<Structure>> > =
   {
     parentLink.read().unwrap()
-      .getStructureByName(&structureName)
+      .getWritableStructureByName(&structureName)
   };
   
   match structureLink
@@ -211,15 +236,27 @@ fn linearStructure(lineTokens: &Vec<Token>, parentLink: Arc<RwLock<Structure>>)
 
       match structureMut
       {
-        StructureMut::Constant => {} // Константные структуры изменить нельзя
+        StructureMut::Constant =>
+        { // Константные структуры изменить нельзя, об этом можно узнать через обработчик
+          unexpected(
+            &parent,
+            "constant",
+            vec![ Token::new(TokenType::String, structureName) ],
+            0
+          );
+        }
         StructureMut::Final | StructureMut::Variable | StructureMut::Dynamic =>
         { // Всё остальное изменить можно
-          parent.structureOp(
-            structureLink,
-            opType,
-            structureMut,
-            rightValue.unwrap_or_default()
-          );
+          match value
+          {
+            Some(value) => parent.setStructureValue(structureLink, structureMut, value),
+            None => parent.structureOp(
+              structureLink,
+              opType,
+              structureMut,
+              rightValue.unwrap_or_default()
+            )
+          }
           //
         }
       }
@@ -231,15 +268,18 @@ fn linearStructure(lineTokens: &Vec<Token>, parentLink: Arc<RwLock<Structure>>)
     { // Если мы не нашли структуру, то создаём новую
       // и работаем с правой частью выражения
 
-      // Закидываем новую структуру в родительскую структуру
-      let parentStructure: RwLockWriteGuard<Structure> = parentLink.write().unwrap();
-
       // Вычисляем правое выражение?
+      // Запись в родителя берётся только после вычисления,
+      // потому что выражение может обращаться к родителю через вложенные структуры
       match structureMutability == StructureMut::Final
       { true => {} false =>
       { 
         let hasTokens: bool = rightValue.is_none();
-        let mut value: Token = parentStructure.expression(&mut rightValue.unwrap());
+        let mut value: Token = match value
+        {
+          Some(value) => value,
+          None => parentLink.read().unwrap().expression(&mut rightValue.unwrap())
+        };
         match structureType == StructureType::None
         {
           true =>
@@ -253,7 +293,8 @@ fn linearStructure(lineTokens: &Vec<Token>, parentLink: Arc<RwLock<Structure>>)
               // Вычисляется он по типу из результата правой части выражения
               true => structureType = value.getStructureType(),
               // Требуется выполнить преобразование в указанный тип данных
-              false => Structure::normalizeToken(&mut value, structureType.clone())
+              false => parentLink.read().unwrap()
+                .normalizeOverflow(&structureName, &mut value, structureType.clone(), structureOverflow)
             }
         }
         //
@@ -264,25 +305,19 @@ fn linearStructure(lineTokens: &Vec<Token>, parentLink: Arc<RwLock<Structure>>)
         }}
       }}
 
-      // Создаём структуру
-      parentStructure.pushStructure(
-        Arc::new(RwLock::new(Structure::new(
-          Some(structureName),
-          structureMutability,
-          structureType,
-          Some(vec![
-            Arc::new(RwLock::new(
-              Line {
-                tokens: rightValue,
-                indent: None,
-                lines:  None,
-                parent: None // todo Назначить родителя?
-              }
-            ))
-          ]),
-          None
-        )))
+      // Закидываем новую структуру в родительскую структуру
+      let mut structure: Structure = Structure::new(
+        Some(structureName),
+        structureMutability,
+        structureType,
+        Some( valueLines(rightValue.clone()) ), // todo Назначить родителя линиям?
+        None
       );
+      structure.overflow = structureOverflow;
+      let structureLink: Arc<RwLock<Structure>> = Arc::new(RwLock::new(structure));
+      pushFields(&structureLink, rightValue.as_ref());
+      let parentStructure: RwLockWriteGuard<Structure> = parentLink.write().unwrap();
+      parentStructure.pushStructure(structureLink);
       
       //
       return true;
@@ -318,6 +353,65 @@ pub(super) fn searchStructure(line: &RwLockReadGuard<Line>, parentLink: Arc<RwLo
   let firstTokenType: &TokenType = lineTokens[0].getDataType(); // Тип первого токена в строке
   let lineLines: Option< Vec< Arc<RwLock<Line>> > > = line.lines.clone(); // Вложенные линии
 
+  // Обработчик непредвиденного поведения `! kind`
+  match searchHandler(lineTokens, &lineLines, &parentLink)
+  {
+    false => {}
+    true => return true
+  }
+
+  // Реакция `name = react expression` или блок `react a, b`
+  match searchReaction(&**line as *const Line as usize, lineTokens, &lineLines, &parentLink)
+  {
+    false => {}
+    true => return true
+  }
+
+  // Блок сопоставления `match subject` или его значение `name = match subject`
+  match &lineLines
+  { None => {} Some(arms) =>
+  {
+    let equalsIndex: Option<usize> = lineTokens.iter()
+      .position(|token: &Token| *token.getDataType() == TokenType::Equals);
+    match equalsIndex
+    {
+      None if isMatchKeyword(&lineTokens[0]) =>
+      { // Блок сопоставления как инструкция
+        matchStructure(arms, parentLink, lineTokens[1..].to_vec());
+        return true;
+      }
+      Some(equalsIndex) if equalsIndex+1 < lineTokensLength && isMatchKeyword(&lineTokens[equalsIndex+1]) =>
+      { // Блок сопоставления как выражение, его значение уходит в левую часть
+        let value: Token = matchStructure(arms, parentLink.clone(), lineTokens[equalsIndex+2..].to_vec());
+        let mut tokens: Vec<Token> = lineTokens[..=equalsIndex].to_vec();
+        tokens.push(value);
+        return match equalsIndex == 0
+        {
+          false => linearStructure(&tokens, None, parentLink),
+          true  =>
+          { // Это `= match subject`, значит результат структуры
+            let returnLine: RwLock<Line> = RwLock::new(Line {
+              tokens: Some(tokens),
+              indent: None,
+              lines:  None,
+              parent: None
+            });
+            let found: bool = searchReturn(&returnLine.read().unwrap(), parentLink);
+            found
+          }
+        };
+      }
+      _ => {}
+    }
+  }}
+
+  // Импорт `import("path")` или `name = import("path")`
+  match lineLines.is_none() && searchImport(lineTokens, parentLink.clone())
+  {
+    false => {}
+    true => return true
+  }
+
   if *firstTokenType == TokenType::Word
   { // Если мы видим TokenType::Word в начале строки, 
     // это значит, что это либо структура, либо линейная запись
@@ -442,7 +536,7 @@ pub(super) fn searchStructure(line: &RwLockReadGuard<Line>, parentLink: Arc<RwLo
       }
       None =>
       { // Это линейная запись
-        return linearStructure(lineTokens, parentLink);
+        return linearStructure(lineTokens, None, parentLink);
       }
     }
   } else 
@@ -503,82 +597,68 @@ pub(super) fn searchStructure(line: &RwLockReadGuard<Line>, parentLink: Arc<RwLo
       //
     }
     // После нахождения всех возможных условных блоков,
-    // начинаем читать их условия и выполнять
-    let mut conditionTruth: bool = false; // заранее создаём true/false ячейку
-    for conditionLink in &mut conditions 
-    { // Итак, мы читает ссылки на условия в цикле;
-      // после чего мы берём само условие на чтение
-      let condition: RwLockReadGuard<Line> = conditionLink.read().unwrap();
-      match &condition.tokens
-      { None => {} Some(tokens) =>
-      {
-        match tokens.len() > 1
+    // начинаем читать их условия и выполнять;
+    // Если выбранный блок закончился на go(), то условия читаются заново здесь же
+    let mut repeat: bool = true;
+    while repeat
+    {
+      repeat = false;
+      let mut conditionTruth: bool = false; // заранее создаём true/false ячейку
+      for conditionLink in &mut conditions 
+      { // Итак, мы читает ссылки на условия в цикле;
+        // после чего мы берём само условие на чтение
+        let condition: RwLockReadGuard<Line> = conditionLink.read().unwrap();
+        match &condition.tokens
+        { None => {} Some(tokens) =>
         {
-          true =>
-          { // Если условие больше чем просто один токен TokenType::Question,
-            // то значит там обычное if/elif условие
-            { // проверяем верность условия;
-              let mut conditionTokens: Vec<Token> = tokens.clone(); // todo: no clone ? fix its please
-              // Удаляем TokenType::Question токен
-              conditionTokens.remove(0);
-              // И проверяем
-              conditionTruth =
-              { // Получаем string ответ от expression, true/false
-                let expressionResult: Option<String> =
-                  parentLink.read().unwrap() // для этого берём родительскую линию;
-                    .expression(&mut conditionTokens)
-                    .getData().toString(); // и её токены.
-                // Итоговый boolean результат
-                match expressionResult
-                {
-                  Some(expressionResult) => { expressionResult == "1" }
-                  None => { false }
-                }
-              };
+          match tokens.len() > 1
+          {
+            true =>
+            { // Если условие больше чем просто один токен TokenType::Question,
+              // то значит там обычное if/elif условие
+              { // проверяем верность условия;
+                let mut conditionTokens: Vec<Token> = tokens.clone(); // todo: no clone ? fix its please
+                // Удаляем TokenType::Question токен
+                conditionTokens.remove(0);
+                // И проверяем
+                conditionTruth =
+                { // Получаем string ответ от expression, true/false
+                  let expressionResult: Option<String> =
+                    parentLink.read().unwrap() // для этого берём родительскую линию;
+                      .expression(&mut conditionTokens)
+                      .getData().toString(); // и её токены.
+                  // Итоговый boolean результат
+                  match expressionResult
+                  {
+                    Some(expressionResult) => { expressionResult == "1" }
+                    None => { false }
+                  }
+                };
+              }
+              // Если условие верно
+              match conditionTruth
+              { false => {} true =>
+              { // Читаем блок во временной структуре
+                let lines: Option< Vec< Arc<RwLock<Line>> > > = condition.lines.clone();
+                let _ = drop(condition);
+                repeat = readBlock("if-elif", lines, parentLink.clone());
+                break; // end
+              }}
             }
-            // Если условие верно
-            match conditionTruth
-            { false => {} true =>
-            { // Создаём новую временную структуру условного блока
-              let structure: Arc<RwLock<Structure>> =
-                Arc::new(RwLock::new(
-                  Structure::new(
-                    Some(String::from("if-elif")),
-                    StructureMut::Constant,
-                    StructureType::Method, // todo может быть что-то другое ?
-                    condition.lines.clone(),
-                    Some(parentLink.clone())
-                  )
-                ));
-              // После создания, читаем эту структуру
+            // В случае если в токенах условия просто TokenType::Question,
+            // значит это else блок
+            false => if !conditionTruth
+            { // Читаем блок во временной структуре
+              let lines: Option< Vec< Arc<RwLock<Line>> > > = condition.lines.clone();
               let _ = drop(condition);
-              readLines(structure);
+              repeat = readBlock("else", lines, parentLink.clone());
               break; // end
-            }}
-          }
-          // В случае если в токенах условия просто TokenType::Question,
-          // значит это else блок
-          false => if !conditionTruth
-          { // создаём новую временную структуру условного блока
-            let structure: Arc<RwLock<Structure>> =
-              Arc::new(RwLock::new(
-                Structure::new(
-                  Some(String::from("else")),
-                  StructureMut::Constant,
-                  StructureType::Method, // todo может быть что-то другое ?
-                  condition.lines.clone(),
-                  Some(parentLink.clone())
-                )
-              ));
-            // После создания, читаем эту структуру
-            let _ = drop(condition);
-            readLines(structure);
-            break; // end
+            }
           }
-        }
+          //
+        }}
         //
-      }}
-      //
+      }
     }
 
     // и только после прочтения всех блоков, 
@@ -591,39 +671,86 @@ pub(super) fn searchStructure(line: &RwLockReadGuard<Line>, parentLink: Arc<RwLo
 
 // =================================================================================================
 
-/// Основная структура; В неё вкладываются остальные;
-/// В эту структуру будут переданы стартовые параметры;
-/// Неизменяемая; Действует во время всей жизни программы;
-pub static MainStructure: LazyLock< Arc<RwLock<Structure>> > =
-  LazyLock::new(|| {
-    Arc::new(
-      RwLock::new(
-        Structure::new(
-          Some(String::from("main")),
-          StructureMut::Constant,
-          StructureType::Method,
-          None,
-          None,
+/// Проверяет, что линия - это вызов go() без вложенных линий;
+/// В скобках может быть только значение, т.к. при повторе блока на месте оно не считается
+fn isGoLine(line: &Line) -> bool
+{
+  let tokens: &[Token] = line.tokens.as_deref().unwrap_or_default();
+  line.lines.is_none() && tokens.len() == 2 &&
+    *tokens[0].getDataType() == TokenType::Word &&
+    tokens[0].getData().toString().is_some_and(|name: String| name == "go") &&
+    *tokens[1].getDataType() == TokenType::CircleBracketBegin &&
+    tokens[1].lines.as_ref().is_none_or(|lines: &Vec<Line>|
+      lines.iter().all(|line: &Line| match line.tokens.as_deref()
+      {
+        None => true,
+        Some(tokens) => tokens.len() <= 1 && tokens.iter().all(|token: &Token|
+          matches!(token.getDataType(),
+            TokenType::None  | TokenType::Int    | TokenType::UInt   |
+            TokenType::Float | TokenType::UFloat | TokenType::Bool
+          )
         )
-      )
+      })
     )
-  });
+}
+
+/// Читает условный блок во временной структуре;
+/// Если блок заканчивается на go(), то она не вызывается, а возвращается true -
+/// тогда цепочка условий читается заново на том же месте, а не вложенным вызовом,
+/// поэтому такой цикл не расходует глубину вложенности и стек
+pub(crate) fn readBlock(name: &str, lines: Option< Vec< Arc<RwLock<Line>> > >, parentLink: Arc<RwLock<Structure>>) -> bool
+{
+  let goIndex: Option<usize> = lines.as_ref().and_then(|lines: &Vec< Arc<RwLock<Line>> >|
+    match lines.last().is_some_and(|line: &Arc<RwLock<Line>>| isGoLine(&line.read().unwrap()))
+    {
+      true  => Some(lines.len()-1),
+      false => None
+    }
+  );
+  let blockLines: Option< Vec< Arc<RwLock<Line>> > > = match goIndex
+  {
+    None => lines,
+    Some(goIndex) => lines.map(|mut lines: Vec< Arc<RwLock<Line>> >|
+    {
+      lines.truncate(goIndex);
+      lines
+    })
+  };
+  readLines(Arc::new(RwLock::new( Structure::newBlock(name, blockLines, parentLink) )));
+  match goIndex
+  {
+    None => false,
+    // go() - это тоже линия блока, она расходует бюджет
+    Some(goIndex) => !withRuntime(|runtime: &Runtime| runtime.isExit()) && chargeStep(name, goIndex)
+  }
+}
 
 // =================================================================================================
 
 /// Это основная функция для парсинга строк;
-/// Она разделена на подготовительную часть, и часть запуска readLine()
+/// Она разделена на подготовительную часть, и часть запуска readLine();
+/// Линии выполняются в главной структуре текущей среды выполнения
 pub fn parseLines(tokenizerLinesLinks: Vec< Arc<RwLock<Line>> >) -> ()
 { // Начинается подготовка к запуску
+  let runtime: Arc<Runtime> = runtime();
+  let mainStructure: Arc<RwLock<Structure>> = runtime.getMainStructure();
+  // argv начинается с пути запущенного файла, как в C; У скрипта из строки путь пустой
+  let filePath: String = runtime.getFilePath();
+  let arguments: Vec<String> =
+    std::iter::once( match filePath.ends_with(".rt") { true => filePath, false => String::new() } )
+      .chain( runtime.getArguments() )
+      .collect();
+  // Таймеры этой среды срабатывают в потоке, который её запустил
+  runtime.setOwnerThread();
   #[cfg(not(target_family = "wasm"))]
-  match unsafe{_debugMode} 
+  match runtime.isDebugMode()
   { false => {} true  =>
   {
     logSeparator("Preparation");
   }}
 
   { // Присваиваем в главную структуру
-    let mut main: RwLockWriteGuard<Structure> = MainStructure.write().unwrap();
+    let mut main: RwLockWriteGuard<Structure> = mainStructure.write().unwrap();
 
     // Присваиваем линии от Tokenizer
     main.lines = Some(tokenizerLinesLinks);
@@ -642,7 +769,7 @@ pub fn parseLines(tokenizerLinesLinks: Vec< Arc<RwLock<Line>> >) -> ()
               tokens: Some(vec![
                 Token::new( 
                   TokenType::UInt,
-                  Bytes::new( unsafe{_argc.to_string()} )
+                  Bytes::new( arguments.len().to_string() )
                 )
               ]),
               indent: None,
@@ -651,13 +778,13 @@ pub fn parseLines(tokenizerLinesLinks: Vec< Arc<RwLock<Line>> >) -> ()
             }
           ))
         ]),
-        Some( MainStructure.clone() ), // Ссылаемся на родителя
+        Some( mainStructure.clone() ), // Ссылаемся на родителя
       )))
     );
 
     // argv
     let mut argv: Vec< Arc<RwLock<Line>> > = Vec::new();
-    for a in unsafe{&_argv}
+    for a in &arguments
     {
       argv.push(
         Arc::new(RwLock::new( // Добавляем линию с 1 токеном
@@ -682,56 +809,58 @@ pub fn parseLines(tokenizerLinesLinks: Vec< Arc<RwLock<Line>> >) -> ()
         StructureMut::Constant, // Неизменяемая;
         StructureType::List,    // Список;
         Some(argv),             // В линии структуры добавляем все argv линии;
-        Some( MainStructure.clone() ),  // ссылаемся на родителя
+        Some( mainStructure.clone() ),  // ссылаемся на родителя
       )))
     );
   }
 
   // Выводим arch & argv
   #[cfg(not(target_family = "wasm"))]
-  unsafe
+  match runtime.isDebugMode()
+  { false => {} true =>
   {
-    match _debugMode
-    { false => {} true =>
-    {
-      log("ok", &format!("argc [{}]", _argc));
-      match _argc > 0
-      { false => {} true =>
-      {
-        log("ok", &format!("argv {:?}", _argv));
-      }}
-    }}
-  }
+    log("ok", &format!("argc [{}]", arguments.len()));
+    log("ok", &format!("argv {:?}", arguments));
+  }}
 
   // Подготовка закончена, читаем линии
   #[cfg(not(target_family = "wasm"))]
   let startTime: Instant = Instant::now(); // Получаем текущее время для debug замера
   #[cfg(not(target_family = "wasm"))]
-  match unsafe{ _debugMode }
+  match runtime.isDebugMode()
   { false => {} true  =>
   {
     logSeparator("Interpretation");
   }}
   
   // Передаём ссылку на структуру и запускаем
-  readLines(MainStructure.clone());
+  startBudget();
+  readLines(mainStructure);
+  // Файл прочитан, но программа работает, пока остаются таймеры или потоки
+  runEventLoop();
   // Далее идут debug замеры
   #[cfg(not(target_family = "wasm"))]
-  match unsafe{_debugMode} 
+  match runtime.isDebugMode()
   { false => {} true =>
   {
     let endTime:  Instant  = Instant::now();    // Получаем текущее время
     let duration: Duration = endTime-startTime; // Получаем сколько всего прошло
     logSeparator("End");
     log("ok",&format!("Parser duration [{:?}]",duration));
+    for report in timerReport()
+    {
+      log("ok", &report);
+    }
   }}
 }
+
 /// Эта функция занимается чтением блоков по ссылке на них
 /// todo: исправить переполнение стека
 pub fn readLines(structureLink: Arc<RwLock<Structure>>) -> ()
 { // Получаем сколько линий вложено в структуру,
   // а также индекс чтения строк (у каждой структуры он свой, чтобы не путать чтение)
-  let (lineIndex, linesLength): (*mut usize, usize) = 
+  // Имя структуры нужно бюджету, чтобы указать место, где он закончился
+  let (lineIndex, linesLength, name): (*mut usize, usize, String) = 
   {
     let structure: RwLockReadGuard<Structure> = structureLink.read().unwrap(); // Читаем структуру
     (
@@ -744,17 +873,25 @@ pub fn readLines(structureLink: Arc<RwLock<Structure>>) -> ()
           lines.len()
         }
         //
-      }
+      },
+      structure.name.clone().unwrap_or_default()
     )
     //
   };
 
+  // Каждое чтение структуры - это уровень вложенности, который ограничен бюджетом
+  match enterDepth(&name, unsafe{*lineIndex})
+  {
+    false => return,
+    true  => {}
+  }
+
   // Выполнение программы происходит до тех пор,
   // пока не будет всё прочитано, либо 
-  // пока не будет вызван _exitCode на true
+  // пока не будет вызван exit()
   let mut lineLink: Arc< RwLock<Line> >;
 
-  while unsafe{_exit == false} && unsafe{*lineIndex < linesLength}
+  while !withRuntime(|runtime: &Runtime| runtime.isExit()) && unsafe{*lineIndex < linesLength}
   { // Если мы читаем строки, то создаём сразу ссылку на текущую линию
     lineLink =
     { // Получаем её через чтение текущей структуры;
@@ -771,6 +908,19 @@ pub fn readLines(structureLink: Arc<RwLock<Structure>>) -> ()
       unsafe{*lineIndex += 1}
       continue;
     }}
+    // Каждая прочитанная линия расходует бюджет
+    match chargeStep(&name, unsafe{*lineIndex})
+    {
+      false => break,
+      true  => {}
+    }
+    // Виртуальная машина выполняет линии, которые уже переведены в байткод
+    match isVmEngine() && runLine(&lineLink, &line, &structureLink, lineIndex)
+    { false => {} true =>
+    {
+      unsafe{*lineIndex += 1}
+      continue;
+    }}
     // Если всё хорошо, то начинаем читать через специальные функции;
     // Ищем структуры
     match !searchStructure(&line, structureLink.clone(), lineIndex)
@@ -781,12 +931,10 @@ pub fn readLines(structureLink: Arc<RwLock<Structure>>) -> ()
       { false => {} true =>
       { // Ищем линейные выражения
 
-        let tokens: &mut Vec<Token> =
-          &mut line
-            .tokens.clone() // Клонируем токены, для сохранения возможности повторного запуска
-            .unwrap_or_default(); // todo плохо
+        // Линия компилируется один раз, повторные запуски берут её из кэша
+        let ir: Arc<Ir> = getLineIr(&lineLink, &line);
         structureLink.read().unwrap()
-          .expression(tokens);
+          .evaluate(&ir);
       }}
     }}
     // Идём дальше
@@ -795,6 +943,7 @@ pub fn readLines(structureLink: Arc<RwLock<Structure>>) -> ()
   // Сбрасываем указатель линий для текущей структуры на 0
   // Для того чтобы можно было запускать повторно
   unsafe{*lineIndex = 0}
+  leaveDepth();
 }
 
 // =================================================================================================
\ No newline at end of file
@@ -0,0 +1,395 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use crate::parser::parser::readLines;
+use crate::parser::runtime::withRuntime;
+use crate::parser::structure::structure::{Structure, StructureMut};
+use crate::parser::structure::structureType::{Overflow, StructureType};
+use crate::parser::unexpected::unexpected;
+use crate::tokenizer::types::line::Line;
+use crate::tokenizer::types::token::Token;
+use crate::tokenizer::types::tokenType::TokenType;
+// =================================================================================================
+
+// Реактивные зависимости между структурами;
+// Реакция привязывается к структурам-источникам и запускается,
+// когда любой из них изменяется через structureOp:
+//
+//   a~ = 1
+//   b~ = 2
+//   sum = react a + b   # пересчитывается при изменении a или b
+//   react sum           # запускается при изменении sum
+//     println(sum)
+//   a = 10              # sum = 12, затем println(sum)
+//
+// Все затронутые реакции запускаются по одному разу в порядке зависимостей,
+// а при равенстве - в порядке объявления. Реакция, которая во время своей работы
+// снова изменила свой источник, не запускается повторно; это непредвиденное поведение `cycle`.
+//
+// У каждого объявления есть не больше одной действующей реакции: когда линия объявления
+// читается снова (например, при повторном вызове метода), прошлая реакция отвязывается от своих источников.
+
+// =================================================================================================
+
+/// Ключевое слово реакции
+const reactKeyword: &str = "react";
+
+/// Счётчик реакций; Определяет порядок объявления
+static ReactionCounter: AtomicUsize = AtomicUsize::new(0);
+
+/// Реакции одной среды выполнения
+#[derive(Default)]
+pub(crate) struct ReactionState
+{
+  /// Действующая реакция по адресу линии, которая её объявила
+  declared: Mutex< HashMap<usize, Arc<Reaction>> >
+}
+
+impl ReactionState
+{
+  /// Забывает все объявления; Реакции остаются привязанными к своим источникам
+  pub(crate) fn clear(&self) -> ()
+  {
+    self.declared.lock().unwrap().clear();
+  }
+}
+
+/// Реакция на изменение структур-источников
+pub(crate) struct Reaction
+{
+  /// Порядок объявления
+  id: usize,
+  /// Структуры-источники
+  sources: Vec< Arc<RwLock<Structure>> >,
+  /// Пересчитываемая структура и её выражение; None для блока
+  target: Option<( Arc<RwLock<Structure>>, Vec<Token> )>,
+  /// Указанный тип пересчитываемой структуры; None, если он берётся из значения
+  dataType: StructureType,
+  /// Линии блока; None для пересчитываемой структуры
+  lines: Option< Vec< Arc<RwLock<Line>> > >,
+  /// Структура, в которой была объявлена реакция
+  scope: Arc<RwLock<Structure>>,
+  /// Реакция сейчас выполняется
+  running: AtomicBool
+}
+
+/// Проверяет, что токен является ключевым словом react
+pub(super) fn isReactKeyword(token: &Token) -> bool
+{
+  *token.getDataType() == TokenType::Word &&
+    token.getData().toString().as_deref() == Some(reactKeyword)
+}
+
+// =================================================================================================
+
+/// Собирает структуры-источники по именам в выражении, включая вложенные скобки
+fn collectSources(scope: &Structure, tokens: &[Token], sources: &mut Vec< Arc<RwLock<Structure>> >) -> ()
+{
+  for token in tokens
+  {
+    match *token.getDataType()
+    {
+      TokenType::Word | TokenType::Link =>
+      {
+        let name: String = token.getData().toString().unwrap_or_default();
+        match scope.getStructureByName(&name)
+        { None => {} Some(sourceLink) =>
+        {
+          match sources.iter().any(|source: &Arc<RwLock<Structure>>| Arc::ptr_eq(source, &sourceLink))
+          {
+            true  => {}
+            false => sources.push(sourceLink)
+          }
+        }}
+      }
+      _ => {}
+    }
+    match &token.lines
+    { None => {} Some(lines) =>
+    {
+      for line in lines
+      {
+        collectSources(scope, line.tokens.as_deref().unwrap_or_default(), sources);
+      }
+    }}
+  }
+}
+
+/// Отвязывает реакцию от всех её источников
+fn detachReaction(reaction: &Arc<Reaction>) -> ()
+{
+  for sourceLink in &reaction.sources
+  {
+    sourceLink.read().unwrap()
+      .reactions.write().unwrap()
+      .retain(|attached: &Arc<Reaction>| !Arc::ptr_eq(attached, reaction));
+  }
+}
+
+/// Привязывает реакцию ко всем её источникам;
+/// Прошлая реакция того же объявления отвязывается
+fn attachReaction(reaction: Reaction, declaration: usize) -> ()
+{
+  let reaction: Arc<Reaction> = Arc::new(reaction);
+  let previous: Option< Arc<Reaction> > = withRuntime(|runtime|
+    runtime.reactions.declared.lock().unwrap().insert(declaration, reaction.clone())
+  );
+  match previous
+  { None => {} Some(previous) =>
+  {
+    detachReaction(&previous);
+  }}
+  for sourceLink in &reaction.sources
+  {
+    sourceLink.read().unwrap()
+      .reactions.write().unwrap()
+      .push(reaction.clone());
+  }
+}
+
+/// Пересчитывает структуру реакции по её выражению
+fn recompute(
+  targetLink: &Arc<RwLock<Structure>>,
+  tokens:     &[Token],
+  dataType:   &StructureType,
+  scope:      &Arc<RwLock<Structure>>
+) -> ()
+{
+  let mut value: Token = scope.read().unwrap().expression(&mut tokens.to_vec());
+  match *dataType == StructureType::None
+  { true => {} false =>
+  { // Обработчик overflow может читать структуру, поэтому приводим до записи
+    let (name, overflow): (String, Overflow) =
+    {
+      let target: RwLockReadGuard<Structure> = targetLink.read().unwrap();
+      (target.name.clone().unwrap_or_default(), target.overflow)
+    };
+    scope.read().unwrap().normalizeOverflow(&name, &mut value, dataType.clone(), overflow);
+  }}
+  let mut target: RwLockWriteGuard<Structure> = targetLink.write().unwrap();
+  match *dataType == StructureType::None
+  { false => {} true =>
+  {
+    target.dataType = value.getStructureType();
+  }}
+  target.lines = Some(vec![
+    Arc::new(RwLock::new(Line {
+      tokens: Some(vec![value]),
+      indent: None,
+      lines:  None,
+      parent: None
+    }))
+  ]);
+}
+
+// =================================================================================================
+
+/// Ищет объявление реакции:
+///   - `name = react expression` или `name: Type = react expression`
+///   - `react a, b` с вложением
+///
+/// `declaration` - адрес линии объявления; По нему заменяется прошлая реакция этой линии
+///
+/// Возвращает true, если линия была объявлением реакции
+pub(super) fn searchReaction(
+  declaration: usize,
+  lineTokens: &[Token],
+  lineLines: &Option< Vec< Arc<RwLock<Line>> > >,
+  parentLink: &Arc<RwLock<Structure>>
+) -> bool
+{
+  match lineLines
+  {
+    Some(lines) =>
+    { // Блок, который запускается при изменении источников
+      match isReactKeyword(&lineTokens[0])
+      {
+        false => return false,
+        true => {}
+      }
+      let mut sources: Vec< Arc<RwLock<Structure>> > = Vec::new();
+      collectSources(&parentLink.read().unwrap(), &lineTokens[1..], &mut sources);
+      attachReaction(Reaction {
+        id: ReactionCounter.fetch_add(1, Ordering::SeqCst),
+        sources,
+        target: None,
+        dataType: StructureType::None,
+        lines: Some(lines.clone()),
+        scope: parentLink.clone(),
+        running: AtomicBool::new(false)
+      }, declaration);
+      true
+    }
+    None =>
+    { // Структура, которая пересчитывается при изменении источников
+      let equalsIndex: usize = match lineTokens.iter()
+        .position(|token: &Token| *token.getDataType() == TokenType::Equals)
+      {
+        Some(equalsIndex) if equalsIndex+1 < lineTokens.len() && isReactKeyword(&lineTokens[equalsIndex+1]) => equalsIndex,
+        _ => return false
+      };
+      let name: String = lineTokens[0].getData().toString().unwrap_or_default();
+      let (dataType, overflow): (StructureType, Overflow) = match equalsIndex
+      {
+        3 if *lineTokens[1].getDataType() == TokenType::Colon =>
+          (lineTokens[2].getStructureTypeSimple(), Overflow::Saturate),
+        4 if *lineTokens[1].getDataType() == TokenType::Colon =>
+          (lineTokens[2].getStructureTypeSimple(), Overflow::fromToken(&lineTokens[3]).unwrap_or_default()),
+        _ => (StructureType::None, Overflow::Saturate)
+      };
+      let tokens: Vec<Token> = lineTokens[equalsIndex+2..].to_vec();
+
+      // Блок может читаться повторно (например, тело метода), тогда реакция уже есть
+      match parentLink.read().unwrap().getOwnStructureByName(&name)
+      {
+        Some(_) => return true,
+        None => {}
+      }
+
+      let mut sources: Vec< Arc<RwLock<Structure>> > = Vec::new();
+      collectSources(&parentLink.read().unwrap(), &tokens, &mut sources);
+
+      // Пересчитываемая структура, значение которой меняет только реакция,
+      // поэтому снаружи она неизменяемая
+      let mut target: Structure = Structure::new(
+        Some(name),
+        StructureMut::Constant,
+        dataType.clone(),
+        None,
+        None
+      );
+      target.overflow = overflow;
+      let targetLink: Arc<RwLock<Structure>> = Arc::new(RwLock::new(target));
+      recompute(&targetLink, &tokens, &dataType, parentLink);
+      parentLink.read().unwrap().pushStructure(targetLink.clone());
+
+      attachReaction(Reaction {
+        id: ReactionCounter.fetch_add(1, Ordering::SeqCst),
+        sources,
+        target: Some((targetLink, tokens)),
+        dataType,
+        lines: None,
+        scope: parentLink.clone(),
+        running: AtomicBool::new(false)
+      }, declaration);
+      true
+    }
+  }
+}
+
+// =================================================================================================
+
+/// Собирает все реакции, которые затронет изменение структуры, в том числе через пересчёт
+fn collectReactions(sourceLink: &Arc<RwLock<Structure>>, affected: &mut BTreeMap< usize, Arc<Reaction> >) -> ()
+{
+  let reactions: Vec< Arc<Reaction> > =
+    sourceLink.read().unwrap()
+      .reactions.read().unwrap()
+      .clone();
+  for reaction in reactions
+  {
+    match affected.insert(reaction.id, reaction.clone())
+    {
+      Some(_) => {} // Уже собрана
+      None => match &reaction.target
+      { None => {} Some((targetLink, _)) =>
+      {
+        collectReactions(targetLink, affected);
+      }}
+    }
+  }
+}
+
+/// Проверяет, что реакция `from` пересчитывает один из источников реакции `to`
+fn isDependency(from: &Reaction, to: &Reaction) -> bool
+{
+  match &from.target
+  {
+    None => false,
+    Some((targetLink, _)) =>
+      to.sources.iter().any(|source: &Arc<RwLock<Structure>>| Arc::ptr_eq(source, targetLink))
+  }
+}
+
+/// Запускает реакцию
+fn runReaction(reaction: &Reaction) -> ()
+{
+  match &reaction.target
+  {
+    Some((targetLink, tokens)) => recompute(targetLink, tokens, &reaction.dataType, &reaction.scope),
+    None =>
+    {
+      let structureLink: Arc<RwLock<Structure>> = Arc::new(RwLock::new(
+        Structure::newBlock(reactKeyword, reaction.lines.clone(), reaction.scope.clone())
+      ));
+      readLines(structureLink);
+    }
+  }
+}
+
+/// Запускает все реакции, зависимые от изменённой структуры;
+/// Каждая реакция запускается один раз, после всех реакций, от которых она зависит
+pub(crate) fn propagate(sourceLink: &Arc<RwLock<Structure>>) -> ()
+{
+  let mut affected: BTreeMap< usize, Arc<Reaction> > = BTreeMap::new();
+  collectReactions(sourceLink, &mut affected);
+  match affected.len() == 0
+  {
+    true  => return,
+    false => {}
+  }
+
+  // Сортировка по зависимостям; При равенстве раньше идёт раньше объявленная
+  let mut dependencies: BTreeMap<usize, usize> = BTreeMap::new();
+  for (id, reaction) in &affected
+  {
+    let count: usize = affected.values()
+      .filter(|from: &&Arc<Reaction>| isDependency(from, reaction))
+      .count();
+    dependencies.insert(*id, count);
+  }
+  let mut ready: BTreeSet<usize> = dependencies.iter()
+    .filter(|(_, count)| **count == 0)
+    .map(|(id, _)| *id)
+    .collect();
+
+  while let Some(id) = ready.pop_first()
+  {
+    let reaction: Arc<Reaction> = affected[&id].clone();
+    match reaction.running.swap(true, Ordering::SeqCst)
+    {
+      true =>
+      { // Реакция изменила свой же источник
+        let scope: RwLockReadGuard<Structure> = reaction.scope.read().unwrap();
+        unexpected(
+          &scope,
+          "cycle",
+          vec![ Token::new(TokenType::String, sourceLink.read().unwrap().name.clone().unwrap_or_default()) ],
+          0
+        );
+      }
+      false =>
+      {
+        runReaction(&reaction);
+        reaction.running.store(false, Ordering::SeqCst);
+      }
+    }
+
+    for (nextId, next) in &affected
+    {
+      match isDependency(&reaction, next)
+      { false => {} true =>
+      {
+        let count: &mut usize = dependencies.get_mut(nextId).unwrap();
+        *count -= 1;
+        match *count == 0
+        {
+          true  => { ready.insert(*nextId); }
+          false => {}
+        }
+      }}
+    }
+  }
+}
+
+// =================================================================================================
@@ -0,0 +1,377 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::panic;
+use std::thread::{self, ScopedJoinHandle, ThreadId};
+use crate::parser::budget::BudgetState;
+use crate::parser::parser::parseLines;
+use crate::parser::vm::VmState;
+use crate::parser::reactive::ReactionState;
+use crate::parser::structure::ir::IrState;
+use crate::parser::structure::methods::channel::ChannelState;
+use crate::parser::structure::methods::import::ImportState;
+use crate::parser::structure::methods::random::RandomState;
+use crate::parser::structure::methods::thread::ThreadState;
+use crate::parser::structure::methods::timer::TimerState;
+use crate::parser::structure::structure::{Structure, StructureMut};
+use crate::parser::structure::structureType::StructureType;
+use crate::tokenizer::types::line::Line;
+// =================================================================================================
+
+// Среда выполнения;
+// Всё, что относится к одной запущенной программе: главная структура, аргументы, состояние выхода,
+// вывод, настройки (debug, бюджет, движок, зерно), таймеры, потоки, каналы, импорты, реакции,
+// генератор случайных чисел и кэши скомпилированных линий - принадлежит одному Runtime.
+// Поэтому в одном процессе может работать несколько независимых программ, в том числе из разных потоков.
+//
+// Код работает с текущим Runtime потока: он ставится на время Runtime::run и передаётся потокам spawn().
+// Если текущего нет, используется Runtime по умолчанию, с которым работает бинарник rts
+// и статические функции вроде parseLines, setBudget и setEngine.
+//
+// Общими для процесса остаются только интернированные имена структур.
+
+// =================================================================================================
+
+/// Среда выполнения одной программы
+pub struct Runtime
+{
+  /// Главная структура; В неё вкладываются остальные
+  main: RwLock< Arc<RwLock<Structure>> >,
+
+  /// Завершилась ли программа
+  exit: AtomicBool,
+  /// Значение, которое вернёт программа при завершении
+  exitCode: AtomicI32,
+
+  /// Путь запущенного файла, либо сам скрипт
+  filePath: RwLock<String>,
+  /// Полный путь запущенного файла; Не меняется после chdir, для скрипта из строки пустой
+  fullPath: RwLock<String>,
+  /// Аргументы программы
+  arguments: RwLock< Vec<String> >,
+  /// Вывод дебага
+  debugMode: AtomicBool,
+  /// Куда пишет print и println
+  output: Mutex< Box<dyn Write + Send> >,
+  /// Выбран ли движок Engine::Vm
+  pub(crate) vmEngine: AtomicBool,
+
+  /// Поток, в котором работает цикл событий
+  owner: Mutex< Option<ThreadId> >,
+
+  pub(crate) budget:    BudgetState,
+  pub(crate) timers:    TimerState,
+  pub(crate) threads:   ThreadState,
+  pub(crate) channels:  ChannelState,
+  pub(crate) imports:   ImportState,
+  pub(crate) random:    RandomState,
+  pub(crate) reactions: ReactionState,
+  pub(crate) ir:        IrState,
+  pub(crate) vm:        VmState
+}
+
+/// Размер стека потоков, которые читают линии
+pub(crate) const readStackSize: usize = 256*1024*1024;
+
+/// Runtime по умолчанию
+static DefaultRuntime: LazyLock< Arc<Runtime> > = LazyLock::new(Runtime::new);
+
+thread_local!
+{
+  /// Runtime, с которым работает текущий поток
+  static Current: RefCell< Option< Arc<Runtime> > > = const { RefCell::new(None) };
+}
+
+/// Создаёт пустую главную структуру
+fn newMainStructure() -> Arc<RwLock<Structure>>
+{
+  Arc::new(RwLock::new(
+    Structure::new(
+      Some(String::from("main")),
+      StructureMut::Constant,
+      StructureType::Method,
+      None,
+      None,
+    )
+  ))
+}
+
+// =================================================================================================
+
+/// Получает текущий Runtime потока
+pub fn runtime() -> Arc<Runtime>
+{
+  Current.with(|current: &RefCell< Option< Arc<Runtime> > >|
+    match &*current.borrow()
+    {
+      Some(runtime) => runtime.clone(),
+      None => DefaultRuntime.clone()
+    }
+  )
+}
+
+/// Выполняет действие с текущим Runtime потока без копирования ссылки на него
+pub(crate) fn withRuntime<R>(action: impl FnOnce(&Runtime) -> R) -> R
+{
+  Current.with(|current: &RefCell< Option< Arc<Runtime> > >|
+    match &*current.borrow()
+    {
+      Some(runtime) => action(runtime),
+      None => action(&DefaultRuntime)
+    }
+  )
+}
+
+/// Делает Runtime текущим для потока, пока жив RuntimeGuard
+pub(crate) fn enterRuntime(runtime: Arc<Runtime>) -> RuntimeGuard
+{
+  RuntimeGuard {
+    previous: Current.with(|current: &RefCell< Option< Arc<Runtime> > >| current.replace(Some(runtime)))
+  }
+}
+
+/// Возвращает прошлый текущий Runtime потока
+pub(crate) struct RuntimeGuard
+{
+  previous: Option< Arc<Runtime> >
+}
+
+impl Drop for RuntimeGuard
+{
+  fn drop(&mut self)
+  {
+    let previous: Option< Arc<Runtime> > = self.previous.take();
+    Current.with(|current: &RefCell< Option< Arc<Runtime> > >| current.replace(previous));
+  }
+}
+
+// =================================================================================================
+
+impl Runtime
+{
+  /// Создаёт новую среду выполнения; Вывод по умолчанию идёт в stdout
+  pub fn new() -> Arc<Self>
+  {
+    Arc::new(Runtime {
+      main:      RwLock::new( newMainStructure() ),
+      exit:      AtomicBool::new(false),
+      exitCode:  AtomicI32::new(0),
+      filePath:  RwLock::new(String::new()),
+      fullPath:  RwLock::new(String::new()),
+      arguments: RwLock::new(Vec::new()),
+      debugMode: AtomicBool::new(false),
+      output:    Mutex::new( Box::new(io::stdout()) ),
+      vmEngine:  AtomicBool::new(false),
+      owner:     Mutex::new(None),
+      budget:    BudgetState::default(),
+      timers:    TimerState::default(),
+      threads:   ThreadState::default(),
+      channels:  ChannelState::default(),
+      imports:   ImportState::default(),
+      random:    RandomState::default(),
+      reactions: ReactionState::default(),
+      ir:        IrState::default(),
+      vm:        VmState::default()
+    })
+  }
+
+  /// Запускает линии от Tokenizer в этой среде;
+  /// Возвращает код завершения
+  pub fn run(self: &Arc<Self>, lines: Vec< Arc<RwLock<Line>> >) -> i32
+  {
+    // Вложенные вызовы читаются рекурсивно, поэтому линии читаются в потоке с большим стеком
+    #[cfg(not(target_family = "wasm"))]
+    thread::scope(|scope|
+    {
+      let reader: ScopedJoinHandle<'_, ()> = thread::Builder::new()
+        .name( String::from("main") )
+        .stack_size(readStackSize)
+        .spawn_scoped(scope, ||
+        {
+          let _guard: RuntimeGuard = enterRuntime(self.clone());
+          parseLines(lines);
+        })
+        .expect("Failed to start the main thread");
+      match reader.join()
+      {
+        Ok(_) => {}
+        Err(payload) => panic::resume_unwind(payload)
+      }
+    });
+    #[cfg(target_family = "wasm")]
+    {
+      let _guard: RuntimeGuard = enterRuntime(self.clone());
+      parseLines(lines);
+    }
+    self.getExitCode()
+  }
+
+  /// Сбрасывает среду для следующего запуска: главную структуру, состояние выхода,
+  /// таймеры, потоки, каналы, импорты, реакции, генератор случайных чисел и кэши линий;
+  /// Настройки, аргументы и вывод остаются
+  #[allow(dead_code)] // Вызывается через RTS из lib.rs; Бинарник rts собирает модули сам и его не вызывает
+  pub fn reset(&self) -> ()
+  {
+    *self.main.write().unwrap() = newMainStructure();
+    self.exit.store(false, Ordering::SeqCst);
+    self.exitCode.store(0, Ordering::SeqCst);
+    self.timers.clear();
+    self.threads.clear();
+    self.channels.clear();
+    self.imports.clear();
+    self.random.clear();
+    self.reactions.clear();
+    self.ir.clear();
+    self.vm.clear();
+  }
+
+  // ===============================================================================================
+
+  /// Получает главную структуру
+  pub fn getMainStructure(&self) -> Arc<RwLock<Structure>>
+  {
+    self.main.read().unwrap().clone()
+  }
+
+  /// Проверяет, что программа завершается
+  pub fn isExit(&self) -> bool
+  {
+    self.exit.load(Ordering::Relaxed)
+  }
+
+  /// Получает код завершения
+  pub fn getExitCode(&self) -> i32
+  {
+    self.exitCode.load(Ordering::SeqCst)
+  }
+
+  /// Останавливает программу с кодом завершения
+  pub fn exit(&self, code: i32) -> ()
+  {
+    self.exitCode.store(code, Ordering::SeqCst);
+    self.exit.store(true, Ordering::SeqCst);
+  }
+
+  /// Останавливает программу, если она ещё не останавливается;
+  /// Возвращает false, если программа уже останавливалась
+  pub(crate) fn exitOnce(&self, code: i32) -> bool
+  {
+    match self.exit.swap(true, Ordering::SeqCst)
+    {
+      true  => false,
+      false =>
+      {
+        self.exitCode.store(code, Ordering::SeqCst);
+        true
+      }
+    }
+  }
+
+  /// Ставит код завершения, не останавливая программу
+  pub fn setExitCode(&self, code: i32) -> ()
+  {
+    self.exitCode.store(code, Ordering::SeqCst);
+  }
+
+  // ===============================================================================================
+
+  /// Получает путь запущенного файла, либо сам скрипт
+  pub fn getFilePath(&self) -> String
+  {
+    self.filePath.read().unwrap().clone()
+  }
+
+  /// Ставит путь запущенного файла
+  pub fn setFilePath(&self, filePath: String) -> ()
+  {
+    *self.fullPath.write().unwrap() = match filePath.ends_with(".rt")
+    {
+      true  => fs::canonicalize(&filePath)
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| filePath.clone()),
+      false => String::new()
+    };
+    *self.filePath.write().unwrap() = filePath;
+  }
+
+  /// Получает полный путь запущенного файла; Пустой, если запущен скрипт из строки
+  pub fn getFullPath(&self) -> String
+  {
+    self.fullPath.read().unwrap().clone()
+  }
+
+  /// Получает место выполнения структуры для сообщений: файл, структура и линия
+  pub(crate) fn getSource(&self, structure: &Structure) -> String
+  {
+    self.formatSource(structure.name.as_deref().unwrap_or_default(), structure.lineIndex)
+  }
+
+  /// Получает место выполнения по имени структуры и номеру линии
+  pub(crate) fn formatSource(&self, name: &str, lineIndex: usize) -> String
+  {
+    let filePath: String = self.getFilePath();
+    format!(
+      "{}:{}:{}",
+      match filePath.ends_with(".rt") { true => filePath, false => String::from("script") },
+      name,
+      lineIndex+1
+    )
+  }
+
+  /// Получает аргументы программы
+  pub fn getArguments(&self) -> Vec<String>
+  {
+    self.arguments.read().unwrap().clone()
+  }
+
+  /// Ставит аргументы программы
+  pub fn setArguments(&self, arguments: Vec<String>) -> ()
+  {
+    *self.arguments.write().unwrap() = arguments;
+  }
+
+  /// Проверяет, включён ли вывод дебага
+  pub fn isDebugMode(&self) -> bool
+  {
+    self.debugMode.load(Ordering::Relaxed)
+  }
+
+  /// Включает или выключает вывод дебага
+  pub fn setDebugMode(&self, debugMode: bool) -> ()
+  {
+    self.debugMode.store(debugMode, Ordering::SeqCst);
+  }
+
+  // ===============================================================================================
+
+  /// Ставит, куда пишет print и println
+  #[allow(dead_code)] // Вызывается через RTS из lib.rs и в тестах
+  pub fn setOutput(&self, output: Box<dyn Write + Send>) -> ()
+  {
+    *self.output.lock().unwrap() = output;
+  }
+
+  /// Пишет вывод программы
+  pub(crate) fn write(&self, text: &str) -> ()
+  {
+    let mut output: MutexGuard< Box<dyn Write + Send> > = self.output.lock().unwrap();
+    let _ = output.write_all(text.as_bytes());
+    let _ = output.flush();
+  }
+
+  /// Делает текущий поток тем, в котором работает цикл событий
+  pub(crate) fn setOwnerThread(&self) -> ()
+  {
+    *self.owner.lock().unwrap() = Some( thread::current().id() );
+  }
+
+  /// Проверяет, что это поток, в котором работает цикл событий
+  pub(crate) fn isOwnerThread(&self) -> bool
+  {
+    *self.owner.lock().unwrap() == Some( thread::current().id() )
+  }
+}
+
+// =================================================================================================
@@ -2,6 +2,7 @@ use std::any::Any;
 use libloading::Library;
 use libffi::middle::{Arg, Cif, CodePtr, Type};
 use std::ffi::c_void;
+use num_bigint::{BigInt, BigUint, Sign};
 use serde::{Deserialize, Serialize};
 use crate::parser::structure::ffi::zygote;
 use crate::parser::structure::ffi::zygote::{FFIRequest, FFIResponse};
@@ -67,29 +68,39 @@ impl TryFrom<&mut Token> for FFIValue
     match dataType
     {
       TokenType::UInt =>
-      {
-        if let Ok(value) = data.parse::<u128>()
+      { // Целое больше 64 бит сводится к пределу U64
+        let parsed: Option<u128> = data.parse::<u128>().ok()
+          .or_else(|| data.parse::<BigUint>().ok().map(|_| u128::MAX));
+        if let Some(value) = parsed
         {
           if value <= u8::MAX as u128         { Ok(FFIValue::U8(value as u8))         }
           else if value <= u16::MAX as u128   { Ok(FFIValue::U16(value as u16))       }
           else if value <= u32::MAX as u128   { Ok(FFIValue::U32(value as u32))       }
           else if value <= u64::MAX as u128   { Ok(FFIValue::U64(value as u64))       }
           else if value <= usize::MAX as u128 { Ok(FFIValue::Usize(value as usize))   }
-          else { Err(format!("UInt out of range: {}", value)) }
+          else { Ok(FFIValue::U64(u64::MAX)) }
         } else {
           Err(format!("Failed to parse UInt: {}", data))
         }
       }
       TokenType::Int =>
-      {
-        if let Ok(value) = data.parse::<i128>()
+      { // Целое за пределами 64 бит сводится к пределу I64 по знаку
+        let parsed: Option<i128> = data.parse::<i128>().ok()
+          .or_else(|| data.parse::<BigInt>().ok().map(|value: BigInt|
+            match value.sign()
+            {
+              Sign::Minus => i128::MIN,
+              _ => i128::MAX
+            }
+          ));
+        if let Some(value) = parsed
         {
           if value >= i8::MIN as i128 && value <= i8::MAX as i128            { Ok(FFIValue::I8(value as i8))       }
           else if value >= i16::MIN as i128 && value <= i16::MAX as i128     { Ok(FFIValue::I16(value as i16))     }
           else if value >= i32::MIN as i128 && value <= i32::MAX as i128     { Ok(FFIValue::I32(value as i32))     }
           else if value >= i64::MIN as i128 && value <= i64::MAX as i128     { Ok(FFIValue::I64(value as i64))     }
           else if value >= isize::MIN as i128 && value <= isize::MAX as i128 { Ok(FFIValue::Isize(value as isize)) }
-          else { Err(format!("Int out of range: {}", value)) }
+          else { Ok(FFIValue::I64( value.clamp(i64::MIN as i128, i64::MAX as i128) as i64 )) }
         } else {
           Err(format!("Failed to parse Int: {}", data))
         }
@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, RwLockWriteGuard, Weak};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::parser::runtime::withRuntime;
+use crate::parser::structure::structure::Structure;
+use crate::parser::structure::tokenValue::calculate::getTokenValue;
+use crate::parser::structure::tokenValue::precedence::{evaluateOperands, isExpressionOperator, separateExponentSigns, Operand};
+use crate::parser::structure::tokenValue::value::Value;
+use crate::tokenizer::tokenizer::readTokens;
+use crate::tokenizer::types::line::Line;
+use crate::tokenizer::types::token::Token;
+use crate::tokenizer::types::tokenType::TokenType;
+// =================================================================================================
+
+// Скомпилированные выражения;
+// Линия один раз переводится в дерево операндов и операторов,
+// в котором литералы уже разобраны в Value, скобки уже разобраны во вложенные выражения,
+// вызовы уже найдены, а тела форматных строк уже разбиты на текст и выражения.
+//
+// Результат хранится по ссылке на линию, поэтому повторное чтение линии (циклы, go(), методы,
+// чтение значения структуры) не клонирует и не просматривает её токены заново.
+// Кэши принадлежат среде выполнения (Runtime) и освобождаются вместе с ней.
+// Линии не меняются после Tokenizer, а новый код - это всегда новые линии,
+// поэтому кэш не нужно сбрасывать: запись устаревает вместе со своей линией.
+//
+// Компилируется только то, что вычисляется так же, как в Structure::expression;
+// Всё остальное (ссылки, форматные строки среди других токенов и т.д.)
+// остаётся токенами и вычисляется через expression, как и раньше.
+
+// =================================================================================================
+
+/// Скомпилированное выражение
+pub(crate) enum Ir
+{
+  /// Выражение, которое вычисляется по токенам через expression
+  Tokens(Vec<Token>),
+  /// Выражение из одного токена
+  Single(Single),
+  /// Операнды и операторы в исходном порядке
+  Chain(Vec<Node>)
+}
+
+/// Выражение из одного токена
+#[derive(Clone)]
+pub(crate) enum Single
+{
+  /// Токен, который возвращается как есть
+  Literal(Token),
+  /// Имя структуры
+  Word(Token),
+  /// Форматная строка, её части и тип без formatted
+  Format(Token, Arc< Vec<FormatPart> >, TokenType)
+}
+
+/// Элемент выражения из нескольких токенов
+pub(crate) enum Node
+{
+  /// Токен, который не меняется, и его заранее полученное значение
+  Literal(Token, Value),
+  /// Имя структуры
+  Name(Token),
+  /// Выражение в круглых скобках
+  Group(Arc<Ir>),
+  /// Вызов метода: имя, скобка параметров и выражение этой скобки
+  Call(Token, Token, Arc<Ir>)
+}
+
+/// Часть форматной строки
+pub(crate) enum FormatPart
+{
+  /// Обычный текст
+  Text(String),
+  /// Выражение между {}
+  Expression(Arc<Ir>)
+}
+
+/// Кэши выражений одной среды выполнения
+#[derive(Default)]
+pub(crate) struct IrState
+{
+  /// Скомпилированные линии
+  lines: LineCache<Ir>,
+  /// Разобранные форматные строки по их телу
+  formats: RwLock< HashMap< String, Arc< Vec<FormatPart> > > >
+}
+
+impl IrState
+{
+  /// Забывает все скомпилированные линии и форматные строки
+  pub(crate) fn clear(&self) -> ()
+  {
+    self.lines.clear();
+    self.formats.write().unwrap().clear();
+  }
+}
+
+/// Наибольшее количество разобранных форматных строк;
+/// Строки не привязаны к линиям, поэтому при переполнении кэш очищается целиком
+const formatCacheLength: usize = 4096;
+
+// =================================================================================================
+
+/// Наименьшее количество записей кэша линий, после которого удаляются записи освобождённых линий
+const lineCachePruneLength: usize = 4096;
+
+/// Кэш того, что получено из линии, по адресу линии;
+/// Слабая ссылка не даёт освободить адрес, пока запись существует
+pub(crate) struct LineCache<T>
+{
+  entries: RwLock< HashMap< usize, (Weak<RwLock<Line>>, Arc<T>) > >,
+  /// Количество записей для следующей очистки;
+  /// Растёт вместе с кэшем, чтобы очистка не проходила по живым линиям при каждой записи
+  nextPrune: AtomicUsize
+}
+
+impl<T> LineCache<T>
+{
+  pub(crate) fn new() -> Self
+  {
+    LineCache {
+      entries: RwLock::new(HashMap::new()),
+      nextPrune: AtomicUsize::new(lineCachePruneLength)
+    }
+  }
+
+  /// Получает запись линии, создавая её при первом обращении
+  pub(crate) fn get(&self, lineLink: &Arc<RwLock<Line>>, create: impl FnOnce() -> T) -> Arc<T>
+  {
+    let key: usize = Arc::as_ptr(lineLink) as usize;
+    match self.entries.read().unwrap().get(&key)
+    {
+      Some((weakLine, value)) if weakLine.upgrade().is_some_and(|cached: Arc<RwLock<Line>>| Arc::ptr_eq(&cached, lineLink)) =>
+        return value.clone(),
+      _ => {}
+    }
+
+    let value: Arc<T> = Arc::new( create() );
+    let mut entries: RwLockWriteGuard< HashMap< usize, (Weak<RwLock<Line>>, Arc<T>) > > = self.entries.write().unwrap();
+    match entries.len() >= self.nextPrune.load(Ordering::Relaxed)
+    {
+      false => {}
+      true  =>
+      {
+        entries.retain(|_, (weakLine, _)| weakLine.strong_count() > 0);
+        self.nextPrune.store((entries.len()*2).max(lineCachePruneLength), Ordering::Relaxed);
+      }
+    }
+    entries.insert(key, (Arc::downgrade(lineLink), value.clone()));
+    value
+  }
+
+  /// Удаляет все записи
+  pub(crate) fn clear(&self) -> ()
+  {
+    self.entries.write().unwrap().clear();
+    self.nextPrune.store(lineCachePruneLength, Ordering::Relaxed);
+  }
+}
+
+impl<T> Default for LineCache<T>
+{
+  fn default() -> Self
+  {
+    LineCache::new()
+  }
+}
+
+// =================================================================================================
+
+/// Проверяет, что это форматный тип, и получает тип без formatted
+fn getUnformattedType(dataType: &TokenType) -> Option<TokenType>
+{
+  match dataType
+  {
+    TokenType::FormattedRawString => Some(TokenType::RawString),
+    TokenType::FormattedString    => Some(TokenType::String),
+    TokenType::FormattedChar      => Some(TokenType::Char),
+    _ => None
+  }
+}
+
+/// Компилирует одиночный токен
+fn compileSingle(token: &Token) -> Ir
+{
+  match *token.getDataType()
+  {
+    TokenType::Link => Ir::Tokens( vec![token.clone()] ),
+    TokenType::Word => Ir::Single( Single::Word(token.clone()) ),
+    TokenType::None =>
+    {
+      let mut literal: Token = token.clone();
+      literal.setDataType(TokenType::None);
+      Ir::Single( Single::Literal(literal) )
+    }
+    dataType => match (getUnformattedType(&dataType), token.getData().toString())
+    {
+      (Some(newType), Some(data)) => Ir::Single( Single::Format(token.clone(), getFormatTemplate(&data), newType) ),
+      _ => Ir::Single( Single::Literal(token.clone()) )
+    }
+  }
+}
+
+/// Компилирует выражение в круглых скобках;
+/// None, если скобку нельзя скомпилировать
+fn compileGroup(bracket: &Token) -> Option<Node>
+{
+  match &bracket.lines
+  {
+    None => Some( literalNode(Token::newEmpty(TokenType::None)) ),
+    Some(lines) => match &lines.first()?.tokens
+    {
+      None => Some( literalNode(Token::newEmpty(TokenType::None)) ),
+      Some(tokens) => Some( Node::Group(Arc::new(compile(tokens))) )
+    }
+  }
+}
+
+/// Создаёт неизменяемый элемент выражения
+fn literalNode(token: Token) -> Node
+{
+  let value: Value = getTokenValue(&token);
+  Node::Literal(token, value)
+}
+
+/// Компилирует выражение из токенов
+pub(crate) fn compile(tokens: &[Token]) -> Ir
+{
+  let length: usize = tokens.len();
+  match length
+  {
+    0 => return Ir::Tokens(Vec::new()),
+    1 => return compileSingle(&tokens[0]),
+    _ => {}
+  }
+  match separateExponentSigns(tokens)
+  { None => {} Some(separated) =>
+  {
+    return compile(&separated);
+  }}
+
+  let fallback = || Ir::Tokens( tokens.to_vec() );
+  let mut nodes: Vec<Node> = Vec::with_capacity(length);
+  let mut i: usize = 0;
+  while i < length
+  {
+    let token: &Token = &tokens[i];
+    let nextIsBracket: bool = i+1 < length && *tokens[i+1].getDataType() == TokenType::CircleBracketBegin;
+    match *token.getDataType()
+    {
+      // Такие варианты expression вычисляет с учётом соседних токенов
+      TokenType::Link | TokenType::FormattedRawString | TokenType::FormattedString | TokenType::FormattedChar =>
+        return fallback(),
+      TokenType::None =>
+      {
+        let mut literal: Token = token.clone();
+        literal.setDataType(TokenType::None);
+        nodes.push( literalNode(literal) );
+      }
+      TokenType::CircleBracketBegin => match compileGroup(token)
+      {
+        None => return fallback(),
+        Some(node) => nodes.push(node)
+      },
+      TokenType::Word if nextIsBracket =>
+      {
+        let bracket: &Token = &tokens[i+1];
+        match compileGroup(bracket)
+        {
+          Some(Node::Group(group)) => nodes.push( Node::Call(token.clone(), bracket.clone(), group) ),
+          Some(Node::Literal(..)) => nodes.push( Node::Call(token.clone(), bracket.clone(), Arc::new(Ir::Tokens(Vec::new()))) ),
+          _ => return fallback()
+        }
+        i += 1; // Скобка уже прочитана
+      }
+      dataType if isExpressionOperator(&dataType) => nodes.push( literalNode(token.clone()) ),
+      _ if nextIsBracket => return fallback(),
+      TokenType::Word => nodes.push( Node::Name(token.clone()) ),
+      _ => nodes.push( literalNode(token.clone()) )
+    }
+    i += 1;
+  }
+  Ir::Chain(nodes)
+}
+
+// =================================================================================================
+
+/// Получает скомпилированное выражение линии, компилируя её при первом обращении
+pub(crate) fn getLineIr(lineLink: &Arc<RwLock<Line>>, line: &Line) -> Arc<Ir>
+{
+  // Значения структур - это линии из одного токена, которые заменяются при каждом присвоении;
+  // Их компиляция дешевле записи в кэш
+  let tokens: &[Token] = line.tokens.as_deref().unwrap_or_default();
+  match tokens.len() <= 1
+  {
+    true  => Arc::new( compile(tokens) ),
+    false => withRuntime(|runtime| runtime.ir.lines.get(lineLink, || compile(tokens)))
+  }
+}
+
+/// Разбирает тело форматной строки на текст и выражения между {}
+fn parseFormat(tokenData: &str) -> Vec<FormatPart>
+{
+  let mut parts:            Vec<FormatPart> = Vec::new();
+  let mut text:             String          = String::new(); // Текст до следующего выражения
+  let mut expressionBuffer: String          = String::new(); // Буфер для выражения между {}
+  let mut expressionRead:   bool            = false;         // Флаг чтения в буфер выражения
+
+  for c in tokenData.chars()
+  {
+    match c
+    {
+      '{' => expressionRead = true,
+      '}' =>
+      {
+        expressionRead = false;
+        expressionBuffer += "\n"; // Это нужно чтобы успешно завершить чтение линии
+        let tokens: Vec<Token> = readTokens(expressionBuffer.as_bytes().to_vec(), false)
+          .first()
+          .and_then(|line: &Arc<RwLock<Line>>| line.read().unwrap().tokens.clone())
+          .unwrap_or_default();
+        match text.len() == 0
+        {
+          true  => {}
+          false => parts.push( FormatPart::Text(std::mem::take(&mut text)) )
+        }
+        parts.push( FormatPart::Expression(Arc::new(compile(&tokens))) );
+        expressionBuffer = String::new();
+      }
+      _ => match expressionRead
+      {
+        true  => expressionBuffer.push(c),
+        false => text.push(c)
+      }
+    }
+  }
+  match text.len() == 0
+  {
+    true  => {}
+    false => parts.push( FormatPart::Text(text) )
+  }
+  parts
+}
+
+/// Получает разобранное тело форматной строки
+pub(crate) fn getFormatTemplate(tokenData: &str) -> Arc< Vec<FormatPart> >
+{
+  withRuntime(|runtime|
+  {
+    match runtime.ir.formats.read().unwrap().get(tokenData)
+    {
+      Some(parts) => return parts.clone(),
+      None => {}
+    }
+    let parts: Arc< Vec<FormatPart> > = Arc::new( parseFormat(tokenData) );
+    let mut formats: RwLockWriteGuard< HashMap< String, Arc< Vec<FormatPart> > > > = runtime.ir.formats.write().unwrap();
+    match formats.len() >= formatCacheLength
+    {
+      false => {}
+      true  => formats.clear()
+    }
+    formats.insert(tokenData.to_string(), parts.clone());
+    parts
+  })
+}
+
+// =================================================================================================
+
+impl Structure
+{
+  /// Вычисляет скомпилированное выражение;
+  /// Результат такой же, как у expression по исходным токенам
+  pub(crate) fn evaluate(&self, ir: &Ir) -> Token
+  {
+    match ir
+    {
+      Ir::Tokens(tokens) => self.expression(&mut tokens.clone()),
+      Ir::Single(single) => self.evaluateSingle(single),
+      Ir::Chain(nodes) => self.evaluateChain(nodes)
+    }
+  }
+
+  /// Вычисляет форматную строку по её частям
+  pub(crate) fn evaluateFormat(&self, parts: &[FormatPart]) -> String
+  {
+    let mut result: String = String::new();
+    for part in parts
+    {
+      match part
+      {
+        FormatPart::Text(text) => result += text,
+        FormatPart::Expression(ir) => match self.evaluate(ir).getData().toString()
+        { None => {} Some(expressionData) =>
+        {
+          result += &expressionData;
+        }}
+      }
+    }
+    result
+  }
+
+  /// Вычисляет выражение из одного токена
+  pub(crate) fn evaluateSingle(&self, single: &Single) -> Token
+  {
+    match single
+    {
+      Single::Literal(token) => token.clone(),
+      Single::Word(token) =>
+      {
+        let mut result: Token = token.clone();
+        let data: String = token.getData().toString().unwrap_or_default();
+        let linkResult: Token = self.linkExpression(None, &mut vec![data], None);
+        result.setDataType( *linkResult.getDataType() );
+        result.setData( linkResult.getData() );
+        result
+      }
+      Single::Format(token, parts, newType) =>
+      {
+        let mut result: Token = token.clone();
+        result.setData( self.evaluateFormat(parts) );
+        result.setDataType(*newType);
+        result
+      }
+    }
+  }
+
+  /// Вычисляет выражение из нескольких элементов;
+  /// Сначала получает значения всех операндов, после чего считает операции с учётом их порядка
+  fn evaluateChain(&self, nodes: &[Node]) -> Token
+  {
+    // Токены и их значения, если они уже известны
+    let mut values: Vec<Operand> = Vec::with_capacity(nodes.len());
+    for node in nodes
+    {
+      match node
+      {
+        Node::Literal(token, value) => values.push( (token.clone(), Some(value.clone())) ),
+        Node::Name(token) =>
+        {
+          let mut value: Vec<Token> = vec![token.clone()];
+          self.replaceStructureByName(&mut value, 0);
+          values.push( (value.remove(0), None) );
+        }
+        Node::Group(ir) => values.push( (self.evaluate(ir), None) ),
+        Node::Call(token, bracket, group) =>
+        {
+          let mut value: Vec<Token> = vec![token.clone(), bracket.clone()];
+          let mut valueLength: usize = 2;
+          self.callExpression(&mut value, &mut valueLength, 0);
+          values.push( (value.remove(0), None) );
+          match valueLength == 2
+          {
+            false => {}
+            true  => values.push( (self.evaluate(group), None) ) // Метод из ссылки оставляет скобку
+          }
+        }
+      }
+    }
+
+    evaluateOperands(values)
+  }
+}
+
+// =================================================================================================
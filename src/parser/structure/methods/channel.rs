@@ -0,0 +1,254 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use crate::parser::budget::waitSlice;
+use crate::parser::runtime::{runtime, Runtime};
+use crate::parser::structure::structure::Structure;
+use crate::parser::structure::structureType::StructureType;
+use crate::tokenizer::types::token::Token;
+// =================================================================================================
+
+// Каналы для передачи значений между потоками;
+// channel(Type) создаёт неограниченный канал, channel(Type, capacity) - ограниченный,
+// и возвращает номер канала:
+//
+//   numbers = channel(U8, 2)
+//   send(numbers, 300)          # в канал попадёт 255, значение приводится к типу канала
+//   println(recv(numbers))      # ждёт значение
+//   println(recv(numbers, 100)) # ждёт не больше 100 мс, иначе None
+//   println(tryRecv(numbers))   # не ждёт, None если канал пуст
+//   close(numbers)              # recv после опустошения вернёт None
+//
+// select(a, b, ...) ждёт, пока в одном из каналов появится значение, и возвращает его номер;
+// Если все каналы закрыты и пусты, то возвращает None.
+//
+// Ожидание в send, recv и select прерывается exit() и временем бюджета (--timeout);
+// Тогда send вернёт false, а recv и select - None.
+//
+// Значения копируются в канал как токены, поэтому потоки не делят между собой структуры.
+//
+// Каналы принадлежат среде выполнения (Runtime). Закрытый канал удаляется, как только из него
+// получено последнее значение; Для send, recv и select он ведёт себя так же, как закрытый и пустой.
+
+// =================================================================================================
+
+/// Канал и его очередь значений
+struct Channel
+{
+  /// Тип значений канала
+  dataType: StructureType,
+  /// Ёмкость; None для неограниченного канала
+  capacity: Option<usize>,
+  /// Отправленные, но ещё не полученные значения
+  queue: VecDeque<Token>,
+  /// Канал закрыт для отправки
+  closed: bool
+}
+
+/// Каналы одной среды выполнения
+#[derive(Default)]
+pub(crate) struct ChannelState
+{
+  /// Все каналы по их номеру
+  channels: Mutex< HashMap<usize, Channel> >,
+  /// Общее условие будит ожидающих при любом изменении каналов, что нужно для select
+  changed: Condvar,
+  /// Номер последнего созданного канала; Номера удалённых каналов не используются повторно
+  lastId: AtomicUsize
+}
+
+impl ChannelState
+{
+  /// Забывает все каналы; Ожидающие send, recv и select получат отказ
+  pub(crate) fn clear(&self) -> ()
+  {
+    self.channels.lock().unwrap().clear();
+    self.changed.notify_all();
+  }
+}
+
+/// Удаляет канал, если он закрыт и в нём не осталось значений
+fn releaseChannel(channels: &mut HashMap<usize, Channel>, id: usize) -> ()
+{
+  match channels.get(&id)
+  {
+    Some(channel) if channel.closed && channel.queue.is_empty() =>
+    {
+      channels.remove(&id);
+    }
+    _ => {}
+  }
+}
+
+/// Ждёт изменения каналов до крайнего срока;
+/// Ожидание идёт шагами, чтобы заметить exit() и время бюджета.
+/// Возвращает None, если срок вышел, либо программа завершается
+fn waitChannels<'a>(
+  state: &ChannelState,
+  channels: MutexGuard<'a, HashMap<usize, Channel>>,
+  deadline: Option<Instant>,
+  source: &str
+) -> Option< MutexGuard<'a, HashMap<usize, Channel>> >
+{
+  let left: Option<Duration> = match deadline
+  {
+    None => None,
+    Some(deadline) =>
+    {
+      let now: Instant = Instant::now();
+      match now >= deadline
+      {
+        true  => return None,
+        false => Some(deadline-now)
+      }
+    }
+  };
+  let wait: Duration = waitSlice(source, left)?;
+  Some( state.changed.wait_timeout(channels, wait).unwrap().0 )
+}
+
+// =================================================================================================
+
+/// Создаёт канал и возвращает его номер
+pub fn newChannel(dataType: StructureType, capacity: Option<usize>) -> usize
+{
+  let runtime: Arc<Runtime> = runtime();
+  let id: usize = runtime.channels.lastId.fetch_add(1, Ordering::SeqCst)+1;
+  let mut channels: MutexGuard< HashMap<usize, Channel> > = runtime.channels.channels.lock().unwrap();
+  channels.insert(id, Channel {
+    dataType,
+    // Канал нулевой ёмкости не смог бы передать ни одного значения
+    capacity: capacity.map(|capacity: usize| capacity.max(1)),
+    queue: VecDeque::new(),
+    closed: false
+  });
+  id
+}
+
+/// Отправляет значение в канал, приводя его к типу канала;
+/// Если канал заполнен, то ждёт места.
+/// Возвращает false, если канала нет, он закрыт, либо программа завершается
+pub fn sendChannel(id: usize, mut value: Token) -> bool
+{
+  let runtime: Arc<Runtime> = runtime();
+  let mut channels: MutexGuard< HashMap<usize, Channel> > = runtime.channels.channels.lock().unwrap();
+  loop
+  {
+    let channel: &mut Channel = match channels.get_mut(&id)
+    {
+      None => return false,
+      Some(channel) => channel
+    };
+    match channel.closed
+    {
+      true  => return false,
+      false => {}
+    }
+    match channel.capacity
+    {
+      Some(capacity) if channel.queue.len() >= capacity =>
+      { // Ждём, пока кто-то получит значение или закроет канал
+        channels = match waitChannels(&runtime.channels, channels, None, "send")
+        {
+          None => return false,
+          Some(channels) => channels
+        };
+      }
+      _ =>
+      {
+        match channel.dataType
+        {
+          StructureType::None | StructureType::Any => {}
+          _ => Structure::normalizeToken(&mut value, channel.dataType.clone())
+        }
+        channel.queue.push_back(value);
+        runtime.channels.changed.notify_all();
+        return true;
+      }
+    }
+  }
+}
+
+/// Получает значение из канала; Ждёт его не дольше timeout, если он указан.
+/// Возвращает None, если канала нет, время вышло, канал закрыт и пуст, либо программа завершается
+pub fn recvChannel(id: usize, timeout: Option<Duration>) -> Option<Token>
+{
+  let deadline: Option<Instant> = timeout.map(|timeout: Duration| Instant::now()+timeout);
+  let runtime: Arc<Runtime> = runtime();
+  let mut channels: MutexGuard< HashMap<usize, Channel> > = runtime.channels.channels.lock().unwrap();
+  loop
+  {
+    let channel: &mut Channel = channels.get_mut(&id)?;
+    match channel.queue.pop_front()
+    {
+      Some(value) =>
+      { // Освободилось место для отправителей
+        releaseChannel(&mut channels, id);
+        runtime.channels.changed.notify_all();
+        return Some(value);
+      }
+      None => match channel.closed
+      {
+        true  => return None,
+        false => channels = waitChannels(&runtime.channels, channels, deadline, "recv")?
+      }
+    }
+  }
+}
+
+/// Получает значение из канала без ожидания
+pub fn tryRecvChannel(id: usize) -> Option<Token>
+{
+  let runtime: Arc<Runtime> = runtime();
+  let mut channels: MutexGuard< HashMap<usize, Channel> > = runtime.channels.channels.lock().unwrap();
+  let value: Token = channels.get_mut(&id)?.queue.pop_front()?;
+  releaseChannel(&mut channels, id);
+  runtime.channels.changed.notify_all();
+  Some(value)
+}
+
+/// Закрывает канал; Уже отправленные значения ещё можно получить
+pub fn closeChannel(id: usize) -> ()
+{
+  let runtime: Arc<Runtime> = runtime();
+  let mut channels: MutexGuard< HashMap<usize, Channel> > = runtime.channels.channels.lock().unwrap();
+  match channels.get_mut(&id)
+  { None => {} Some(channel) =>
+  {
+    channel.closed = true;
+    releaseChannel(&mut channels, id);
+    runtime.channels.changed.notify_all();
+  }}
+}
+
+/// Ждёт значение в одном из каналов и возвращает номер этого канала;
+/// Каналы проверяются по порядку. None, если все каналы закрыты и пусты, либо программа завершается
+pub fn selectChannels(ids: &[usize]) -> Option<usize>
+{
+  let runtime: Arc<Runtime> = runtime();
+  let mut channels: MutexGuard< HashMap<usize, Channel> > = runtime.channels.channels.lock().unwrap();
+  loop
+  {
+    let mut open: bool = false;
+    for id in ids
+    {
+      match channels.get(id)
+      { None => {} Some(channel) =>
+      {
+        match channel.queue.is_empty()
+        {
+          false => return Some(*id),
+          true  => open |= !channel.closed
+        }
+      }}
+    }
+    match open
+    {
+      false => return None,
+      true  => channels = waitChannels(&runtime.channels, channels, None, "select")?
+    }
+  }
+}
+
+// =================================================================================================
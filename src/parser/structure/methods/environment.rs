@@ -0,0 +1,117 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use crate::parser::runtime::{withRuntime, Runtime};
+use crate::tokenizer::types::token::Token;
+use crate::tokenizer::types::tokenType::TokenType;
+// =================================================================================================
+
+// Окружение процесса;
+// env(name), setEnv(name, value), envAll(), cwd(), chdir(path), pid(), scriptPath(), hostname()
+// и platform().
+//
+// Переменные окружения и текущий каталог общие для всего процесса, а не для одной среды выполнения.
+// envAll() возвращает список строк "NAME=value" по алфавиту, его можно передать в process(env = ...).
+// Если chdir не удался, то это непредвиденное поведение `fs` со значениями: путь и описание ошибки.
+
+// =================================================================================================
+
+/// Получает значение переменной окружения; None, если её нет
+pub(crate) fn getEnv(name: &str) -> Token
+{
+  match env::var(name)
+  {
+    Ok(value) => Token::new(TokenType::String, value),
+    Err(_) => Token::newEmpty(TokenType::None)
+  }
+}
+
+/// Ставит переменную окружения; Без значения переменная удаляется.
+/// Возвращает false, если такое имя или значение система не примет
+pub(crate) fn setEnv(name: &str, value: Option<String>) -> bool
+{
+  match name.is_empty() || name.contains(['=', '\0']) || value.as_ref().is_some_and(|value: &String| value.contains('\0'))
+  { false => {} true =>
+  {
+    return false;
+  }}
+  match value
+  {
+    Some(value) => env::set_var(name, value),
+    None => env::remove_var(name)
+  }
+  true
+}
+
+/// Получает все переменные окружения списком "NAME=value" по алфавиту
+pub(crate) fn envAll() -> Token
+{
+  let mut variables: Vec<String> = env::vars()
+    .map(|(name, value): (String, String)| format!("{}={}", name, value))
+    .collect();
+  variables.sort();
+  Token::newList(
+    variables.into_iter()
+      .map(|variable: String| Token::new(TokenType::String, variable))
+      .collect()
+  )
+}
+
+/// Получает текущий каталог процесса
+pub(crate) fn cwd() -> Token
+{
+  match env::current_dir()
+  {
+    Ok(path) => Token::new(TokenType::String, path.to_string_lossy().to_string()),
+    Err(_) => Token::newEmpty(TokenType::None)
+  }
+}
+
+/// Меняет текущий каталог процесса
+pub(crate) fn chdir(path: &Path) -> Result<Token, String>
+{
+  env::set_current_dir(path)
+    .map(|_| Token::new(TokenType::Bool, String::from("1")))
+    .map_err(|error| error.to_string())
+}
+
+/// Получает номер текущего процесса
+pub(crate) fn pid() -> Token
+{
+  Token::new(TokenType::UInt, std::process::id().to_string())
+}
+
+/// Получает полный путь запущенного файла; None, если запущен скрипт из строки
+pub(crate) fn scriptPath() -> Token
+{
+  let fullPath: String = withRuntime(|runtime: &Runtime| runtime.getFullPath());
+  match fullPath.is_empty()
+  {
+    true  => Token::newEmpty(TokenType::None),
+    false => Token::new(TokenType::String, fullPath)
+  }
+}
+
+/// Получает имя машины; None, если его не удалось узнать
+pub(crate) fn hostname() -> Token
+{
+  let hostname: Option<String> = ["/proc/sys/kernel/hostname", "/etc/hostname"].iter()
+    .find_map(|path: &&str| fs::read_to_string(path).ok())
+    .or_else(|| env::var("HOSTNAME").ok())
+    .or_else(|| env::var("COMPUTERNAME").ok())
+    .map(|hostname: String| hostname.trim().to_string())
+    .filter(|hostname: &String| !hostname.is_empty());
+  match hostname
+  {
+    Some(hostname) => Token::new(TokenType::String, hostname),
+    None => Token::newEmpty(TokenType::None)
+  }
+}
+
+/// Получает название системы: linux, macos, windows и т.д.
+pub(crate) fn platform() -> Token
+{
+  Token::new(TokenType::String, String::from(env::consts::OS))
+}
+
+// =================================================================================================
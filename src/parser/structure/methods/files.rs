@@ -0,0 +1,160 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::parser::runtime::{withRuntime, Runtime};
+use crate::tokenizer::types::token::Token;
+use crate::tokenizer::types::tokenType::TokenType;
+// =================================================================================================
+
+// Файловая система;
+// readFile, readBytes, writeFile, appendFile, listDir, mkdir, remove, rename, copy, fileSize,
+// modifiedTime, а также exists и isDir.
+//
+// Относительные пути считаются от каталога запущенного файла, а для скрипта из строки - от текущего каталога.
+// Если операция не удалась, то это непредвиденное поведение `fs` со значениями: путь и описание ошибки.
+
+// =================================================================================================
+
+/// Итог файловой операции: значение, либо описание ошибки
+pub(crate) type FileResult = Result<Token, String>;
+
+/// Получает путь относительно каталога запущенного файла
+pub(crate) fn resolvePath(path: &str) -> PathBuf
+{
+  let path: &Path = Path::new(path);
+  match path.is_absolute()
+  {
+    true  => path.to_path_buf(),
+    false =>
+    {
+      // Полный путь не зависит от chdir
+      let fullPath: String = withRuntime(|runtime: &Runtime| runtime.getFullPath());
+      let base: PathBuf = Path::new(&fullPath).parent().map(Path::to_path_buf).unwrap_or_default();
+      match base.as_os_str().is_empty()
+      {
+        true  => Path::new(".").join(path),
+        false => base.join(path)
+      }
+    }
+  }
+}
+
+/// Создаёт токен UInt из числа
+fn newUInt(number: impl ToString) -> Token
+{
+  Token::new( TokenType::UInt, number.to_string() )
+}
+
+/// Создаёт токен Bool для успешной операции
+fn newDone() -> Token
+{
+  Token::new( TokenType::Bool, String::from("1") )
+}
+
+// =================================================================================================
+
+/// Читает файл как строку
+pub(crate) fn readFile(path: &Path) -> FileResult
+{
+  fs::read_to_string(path)
+    .map(|text: String| Token::new(TokenType::String, text))
+    .map_err(|error| error.to_string())
+}
+
+/// Читает файл как список байтов UInt
+pub(crate) fn readBytes(path: &Path) -> FileResult
+{
+  fs::read(path)
+    .map(|bytes: Vec<u8>| Token::newList( bytes.into_iter().map(newUInt).collect() ))
+    .map_err(|error| error.to_string())
+}
+
+/// Записывает байты в файл, либо дописывает их в конец файла;
+/// Возвращает количество записанных байтов
+pub(crate) fn writeFile(path: &Path, bytes: &[u8], append: bool) -> FileResult
+{
+  OpenOptions::new()
+    .create(true)
+    .write(true)
+    .append(append)
+    .truncate(!append)
+    .open(path)
+    .and_then(|mut file: fs::File| file.write_all(bytes))
+    .map(|_| newUInt(bytes.len()))
+    .map_err(|error| error.to_string())
+}
+
+/// Получает имена в каталоге по алфавиту
+pub(crate) fn listDir(path: &Path) -> FileResult
+{
+  let entries: fs::ReadDir = fs::read_dir(path).map_err(|error| error.to_string())?;
+  let mut names: Vec<String> = entries
+    .filter_map(|entry| entry.ok())
+    .map(|entry: fs::DirEntry| entry.file_name().to_string_lossy().to_string())
+    .collect();
+  names.sort();
+  Ok( Token::newList(
+    names.into_iter()
+      .map(|name: String| Token::new(TokenType::String, name))
+      .collect()
+  ))
+}
+
+/// Создаёт каталог вместе с недостающими родителями
+pub(crate) fn mkdir(path: &Path) -> FileResult
+{
+  fs::create_dir_all(path)
+    .map(|_| newDone())
+    .map_err(|error| error.to_string())
+}
+
+/// Удаляет файл, либо каталог со всем содержимым
+pub(crate) fn remove(path: &Path) -> FileResult
+{
+  let result: io::Result<()> = match path.is_dir()
+  {
+    true  => fs::remove_dir_all(path),
+    false => fs::remove_file(path)
+  };
+  result
+    .map(|_| newDone())
+    .map_err(|error| error.to_string())
+}
+
+/// Переименовывает или перемещает файл
+pub(crate) fn rename(from: &Path, to: &Path) -> FileResult
+{
+  fs::rename(from, to)
+    .map(|_| newDone())
+    .map_err(|error| error.to_string())
+}
+
+/// Копирует файл; Возвращает количество скопированных байтов
+pub(crate) fn copy(from: &Path, to: &Path) -> FileResult
+{
+  fs::copy(from, to)
+    .map(newUInt)
+    .map_err(|error| error.to_string())
+}
+
+/// Получает размер файла в байтах
+pub(crate) fn fileSize(path: &Path) -> FileResult
+{
+  fs::metadata(path)
+    .map(|metadata: fs::Metadata| newUInt(metadata.len()))
+    .map_err(|error| error.to_string())
+}
+
+/// Получает время последнего изменения файла в миллисекундах от начала эпохи Unix
+pub(crate) fn modifiedTime(path: &Path) -> FileResult
+{
+  let modified: SystemTime = fs::metadata(path)
+    .and_then(|metadata: fs::Metadata| metadata.modified())
+    .map_err(|error| error.to_string())?;
+  modified.duration_since(UNIX_EPOCH)
+    .map(|time| newUInt(time.as_millis()))
+    .map_err(|error| error.to_string())
+}
+
+// =================================================================================================
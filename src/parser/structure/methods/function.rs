@@ -1,16 +1,33 @@
+use std::cmp::Ordering;
 use std::io;
-use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus, Output};
 use std::str::SplitWhitespace;
 use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+use num_bigint::{BigInt, Sign};
 use crate::parser::structure::structure::{Structure, StructureMut};
 use crate::tokenizer::types::token::{Token};
 use crate::tokenizer::types::tokenType::TokenType;
 #[cfg(not(target_family = "wasm"))]
-use rand::Rng;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
 use crate::parser::structure::methods::parameters::{Parameters};
 use crate::parser::structure::structureType::StructureType;
+use crate::parser::structure::tokenValue::calculate::{getTokenValue, getValueToken};
+use crate::parser::structure::tokenValue::value::{repeatString, Value};
+use crate::parser::structure::structures::Structures;
+use crate::parser::unexpected::{unexpected, Unexpected};
+use crate::parser::structure::methods::thread::{isThreadAlive, joinThread, spawnThread};
+use crate::parser::structure::methods::timer::{cancelTimer, getTimerDrift, newTimer};
+#[cfg(not(target_family = "wasm"))]
+use crate::parser::structure::methods::random::{reseed, withRng};
+use crate::parser::structure::methods::files::{self, resolvePath, FileResult};
+use crate::parser::structure::methods::process::{runProcess, ProcessOptions};
+use crate::parser::structure::methods::environment;
+use crate::parser::structure::methods::channel::{newChannel, recvChannel, selectChannels, sendChannel, tryRecvChannel};
 use crate::tokenizer::types::line::Line;
+use crate::parser::runtime::runtime;
+use crate::parser::parser::readLines;
 
 // =================================================================================================
 /// Это набор базовых функций
@@ -28,7 +45,7 @@ impl Function
       value[i].setData(None);
     } else
     {
-      match parameters.getExpression(structure, 0)
+      match Function::getListParameter(structure, parameters, 0)
       {
         None => 
         {
@@ -38,7 +55,11 @@ impl Function
         Some(p0) =>
         {
           value[i].setDataType( TokenType::String );
-          value[i].setData( p0.getDataType().to_string() );
+          value[i].setData( match p0.getList().is_some() || p0.getRecord().is_some()
+          { // Список хранится как Link без данных
+            true  => StructureType::List.to_string(),
+            false => p0.getDataType().to_string()
+          });
         }
       };
     }
@@ -175,24 +196,170 @@ impl Function
       let randomNumber: usize =
         match min < max
         {
-          true  => rand::rng().random_range(min..=max),
+          true  => withRng(|rng: &mut StdRng| rng.random_range(min..=max)),
           false => 0
         };
       value[i].setDataType( TokenType::UInt );
       value[i].setData( randomNumber.to_string() );
     }
   }
+
+  /// Возвращаем случайное число типа Int от min до max включительно;
+  /// Если min больше max, то будет None
+  fn randInt(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    value[i] = Token::newEmpty(TokenType::None);
+    #[cfg(not(target_family = "wasm"))]
+    match (Function::getInteger(structure, parameters, 0), Function::getInteger(structure, parameters, 1))
+    {
+      (Some(min), Some(max)) if min <= max =>
+      {
+        let randomNumber: i64 = withRng(|rng: &mut StdRng| rng.random_range(min..=max));
+        value[i] = Token::new( TokenType::Int, randomNumber.to_string() );
+      }
+      _ => {}
+    }
+  }
+
+  /// Возвращаем случайное дробное число от min до max, не включая max;
+  /// Без параметров это число от 0 до 1, а если min больше max, то будет None
+  fn randFloat(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    value[i] = Token::newEmpty(TokenType::None);
+    #[cfg(not(target_family = "wasm"))]
+    {
+      let getBound = |index: usize, default: f64| -> Option<f64>
+      {
+        match parameters.getExpression(structure, index)
+        {
+          Some(bound) if *bound.getDataType() != TokenType::None => getTokenValue(&bound).toF64(),
+          _ => Some(default) // Пустой параметр
+        }
+      };
+      let randomNumber: Option<f64> = match (getBound(0, 0.0), getBound(1, 1.0))
+      {
+        (Some(min), Some(max)) if min < max && (max - min).is_finite() =>
+          Some( withRng(|rng: &mut StdRng| rng.random_range(min..max)) ),
+        (Some(min), Some(max)) if min == max => Some(min),
+        _ => None
+      };
+      match randomNumber
+      { None => {} Some(randomNumber) =>
+      { // Тип зависит от пределов, а не от выпавшего числа
+        let min: f64 = getBound(0, 0.0).unwrap_or_default();
+        value[i] = Token::new(
+          match min < 0.0
+          {
+            true  => TokenType::Float,
+            false => TokenType::UFloat
+          },
+          Value::Float(randomNumber).to_string()
+        );
+      }}
+    }
+  }
+
+  /// Возвращаем случайный Bool; Вероятность 1 задаётся параметром, по умолчанию 0.5
+  fn randBool(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    value[i] = Token::newEmpty(TokenType::None);
+    #[cfg(not(target_family = "wasm"))]
+    {
+      let probability: Option<f64> = match parameters.getExpression(structure, 0)
+      {
+        Some(p0) if *p0.getDataType() != TokenType::None => getTokenValue(&p0).toF64(),
+        _ => Some(0.5) // Пустой параметр
+      };
+      match probability
+      {
+        Some(probability) if !probability.is_nan() =>
+        {
+          let result: bool = withRng(|rng: &mut StdRng| rng.random_bool( probability.clamp(0.0, 1.0) ));
+          Function::setBool(value, i, result);
+        }
+        _ => {}
+      }
+    }
+  }
+
+  /// Возвращаем случайный элемент списка; Пустой список или не список даёт None
+  fn choice(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    value[i] = Token::newEmpty(TokenType::None);
+    #[cfg(not(target_family = "wasm"))]
+    match Function::getListParameter(structure, parameters, 0)
+    {
+      Some(p0) => match p0.getList()
+      {
+        Some(elements) if !elements.is_empty() =>
+        {
+          let index: usize = withRng(|rng: &mut StdRng| rng.random_range(0..elements.len()));
+          value[i] = elements[index].clone();
+        }
+        _ => {}
+      }
+      None => {}
+    }
+  }
+
+  /// Возвращаем новый список с элементами списка в случайном порядке
+  fn shuffle(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    value[i] = Token::newEmpty(TokenType::None);
+    #[cfg(not(target_family = "wasm"))]
+    match Function::getListParameter(structure, parameters, 0)
+    {
+      Some(p0) => match p0.getList()
+      {
+        Some(elements) =>
+        {
+          let mut elements: Vec<Token> = elements.clone();
+          withRng(|rng: &mut StdRng| elements.shuffle(rng));
+          value[i] = Token::newList(elements);
+        }
+        None => {}
+      }
+      None => {}
+    }
+  }
+
+  /// Перезапускает генератор случайных чисел с зерном;
+  /// После этого случайные значения повторяются от запуска к запуску
+  fn seed(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    value[i] = Token::newEmpty(TokenType::None);
+    #[cfg(not(target_family = "wasm"))]
+    match Function::getText(structure, parameters, 0)
+    {
+      Some(text) =>
+      { // Отрицательное зерно берётся по его битам
+        match text.parse::<u64>().ok().or_else(|| text.parse::<i64>().ok().map(|seed: i64| seed as u64))
+        {
+          Some(seed) => reseed(seed),
+          None => {}
+        }
+      }
+      None => {}
+    }
+  }
   
   // ===============================================================================================
   
   /// Получаем размер структуры
   fn len(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
   {
-    match parameters.getExpression(structure,0)
+    match Function::getListParameter(structure, parameters, 0)
     { None => {} Some(p0) =>
     {
       match *p0.getDataType()
       {
+        _ if p0.getList().is_some() =>
+        { // Получаем количество элементов списка
+          value[i] = Token::new(
+            TokenType::UInt,
+            p0.getList().unwrap().len().to_string()
+          );
+        }
         TokenType::None =>
         { // Результат 0
           value[i] = Token::new( TokenType::UInt, String::from("0") );
@@ -201,6 +368,14 @@ impl Function
         { // Получаем размер символа
           value[i] = Token::new( TokenType::UInt, String::from("1") );
         }
+        TokenType::Int | TokenType::UInt =>
+        { // Получаем количество цифр целого любой длины
+          value[i] = Token::new(
+            TokenType::UInt,
+            p0.getData().toString().unwrap_or_default()
+              .trim_start_matches('-').len().to_string()
+          );
+        }
         TokenType::String | TokenType::RawString =>
         { // Получаем размер строки
           value[i] = Token::new(
@@ -244,6 +419,349 @@ impl Function
     }}
   }
   
+  // ===============================================================================================
+
+  /// Получает параметр так же, как getExpression, но имя структуры-списка
+  /// даёт весь список, даже если в нём один элемент или нет элементов
+  fn getListParameter(structure: &Structure, parameters: &Parameters, index: usize) -> Option<Token>
+  {
+    match parameters.get(index)?.tokens.as_deref()
+    {
+      Some([token]) if *token.getDataType() == TokenType::Word &&
+        token.getData().toString()
+          .and_then(|name: String| structure.getStructureByName(&name))
+          .is_some() =>
+      {
+        let mut value: Vec<Token> = vec![ token.clone() ];
+        structure.replaceStructureByName(&mut value, 0);
+        value.pop()
+      }
+      _ => parameters.getExpression(structure, index)
+    }
+  }
+
+  /// Получает текст параметра; None, если параметра нет или он пустой
+  fn getText(structure: &Structure, parameters: &Parameters, index: usize) -> Option<String>
+  {
+    let parameter: Token = parameters.getExpression(structure, index)?;
+    match *parameter.getDataType()
+    {
+      TokenType::None => None,
+      _ => parameter.getData().toString()
+    }
+  }
+
+  /// Получает целое число из параметра
+  fn getInteger(structure: &Structure, parameters: &Parameters, index: usize) -> Option<i64>
+  {
+    Function::getText(structure, parameters, index)?.parse::<i64>().ok()
+  }
+
+  /// Превращает строки в список строк
+  fn newTextList<'a>(parts: impl Iterator<Item = &'a str>) -> Token
+  {
+    Token::newList(
+      parts
+        .map(|part: &str| Token::new( TokenType::String, part.to_string() ))
+        .collect()
+    )
+  }
+
+  /// Переводит индекс символа в границы строки;
+  /// Отрицательный индекс считается от конца строки
+  fn clampIndex(index: i64, length: usize) -> usize
+  {
+    match index < 0
+    {
+      true  => length.saturating_sub( index.unsigned_abs() as usize ),
+      false => (index as usize).min(length)
+    }
+  }
+
+  /// Ставит результат в виде строки
+  fn setText(value: &mut [Token], i: usize, text: String)
+  {
+    value[i] = Token::new( TokenType::String, text );
+  }
+
+  /// Ставит результат в виде Bool
+  fn setBool(value: &mut [Token], i: usize, result: bool)
+  {
+    value[i] = Token::new(
+      TokenType::Bool,
+      match result
+      {
+        true  => String::from("1"),
+        false => String::from("0")
+      }
+    );
+  }
+
+  /// Разделяет строку по разделителю и возвращает список частей;
+  /// Без разделителя строка делится по пробельным символам
+  fn split(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let text: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    value[i] = match Function::getText(structure, parameters, 1)
+    {
+      Some(separator) if !separator.is_empty() => Function::newTextList( text.split(separator.as_str()) ),
+      _ => Function::newTextList( text.split_whitespace() )
+    };
+  }
+
+  /// Соединяет элементы списка в строку через разделитель
+  fn joinText(structure: &Structure, parameters: &Parameters, elements: &[Token], value: &mut Vec<Token>, i: usize)
+  {
+    let separator: String = Function::getText(structure, parameters, 1).unwrap_or_default();
+    let parts: Vec<String> = elements.iter()
+      .map(|element: &Token| element.getData().toString().unwrap_or_default())
+      .collect();
+    Function::setText(value, i, parts.join(&separator));
+  }
+
+  /// Заменяет все вхождения подстроки
+  fn replace(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let text: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    let result: String = match Function::getText(structure, parameters, 1)
+    {
+      Some(from) if !from.is_empty() =>
+        text.replace(&from, &Function::getText(structure, parameters, 2).unwrap_or_default()),
+      _ => text // Пустую подстроку не заменяем
+    };
+    Function::setText(value, i, result);
+  }
+
+  /// Убирает пробельные символы по краям строки
+  fn trim(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let text: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    Function::setText(value, i, text.trim().to_string());
+  }
+
+  /// Проверяет, что строка начинается с подстроки
+  fn startsWith(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let text: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    let prefix: String = Function::getText(structure, parameters, 1).unwrap_or_default();
+    Function::setBool(value, i, text.starts_with(&prefix));
+  }
+
+  /// Проверяет, что строка заканчивается подстрокой
+  fn endsWith(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let text: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    let suffix: String = Function::getText(structure, parameters, 1).unwrap_or_default();
+    Function::setBool(value, i, text.ends_with(&suffix));
+  }
+
+  /// Возвращает номер символа, с которого начинается подстрока;
+  /// None, если подстроки нет
+  fn find(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let text: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    let pattern: String = Function::getText(structure, parameters, 1).unwrap_or_default();
+    value[i] = match text.find(&pattern)
+    {
+      None => Token::newEmpty(TokenType::None),
+      Some(byteIndex) => Token::new(
+        TokenType::UInt,
+        text[..byteIndex].chars().count().to_string()
+      )
+    };
+  }
+
+  /// Возвращает часть строки от start до end по номерам символов;
+  /// Отрицательные номера считаются от конца, а без end берётся всё до конца строки
+  fn slice(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let text: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    let length: usize = text.chars().count();
+    let start: usize = Function::clampIndex( Function::getInteger(structure, parameters, 1).unwrap_or_default(), length );
+    let end: usize = match Function::getInteger(structure, parameters, 2)
+    {
+      None => length,
+      Some(end) => Function::clampIndex(end, length)
+    };
+    Function::setText(
+      value, i,
+      text.chars().skip(start).take( end.saturating_sub(start) ).collect()
+    );
+  }
+
+  /// Переводит строку в верхний регистр
+  fn upper(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let text: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    Function::setText(value, i, text.to_uppercase());
+  }
+
+  /// Переводит строку в нижний регистр
+  fn lower(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let text: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    Function::setText(value, i, text.to_lowercase());
+  }
+
+  /// Повторяет строку count раз;
+  /// None, если результат длиннее maxRepeatLength, так же как у `text * count`
+  fn repeat(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let text: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    let count: BigInt = Function::getText(structure, parameters, 1)
+      .and_then(|count: String| count.parse::<BigInt>().ok())
+      .unwrap_or_default();
+    value[i] = getValueToken( repeatString(&text, &Value::fromBigInt(count, false)) );
+  }
+
+  /// Получает целое число из строки; None, если это не целое число
+  fn parseInt(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let text: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    value[i] = match text.trim().parse::<BigInt>()
+    {
+      Err(_) => Token::newEmpty(TokenType::None),
+      Ok(number) => Token::new(
+        match number.sign()
+        {
+          Sign::Minus => TokenType::Int,
+          _ => TokenType::UInt
+        },
+        number.to_string()
+      )
+    };
+  }
+
+  /// Получает дробное число из строки; None, если это не число
+  fn parseFloat(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let text: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    value[i] = match text.trim().parse::<f64>()
+    {
+      Ok(number) if number.is_finite() => getValueToken( Value::Float(number) ),
+      _ => Token::newEmpty(TokenType::None)
+    };
+  }
+
+  /// Разбирает строку на список символов
+  fn chars(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let text: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    value[i] = Token::newList(
+      text.chars()
+        .map(|c: char| Token::new( TokenType::Char, c.to_string() ))
+        .collect()
+    );
+  }
+
+  // ===============================================================================================
+
+  /// Получает параметр вместе с его значением, если это число
+  fn getNumber(structure: &Structure, parameters: &Parameters, index: usize) -> Option<(Token, Value)>
+  {
+    let token: Token = parameters.getExpression(structure, index)?;
+    let number: Value = getTokenValue(&token);
+    match number.toF64()
+    {
+      None => None,
+      Some(_) => Some((token, number))
+    }
+  }
+
+  /// Вычисляет функцию от одного числа; Не число даёт None
+  fn math(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize, function: fn(Value) -> Value)
+  {
+    value[i] = match Function::getNumber(structure, parameters, 0)
+    {
+      None => Token::newEmpty(TokenType::None),
+      Some((_, x)) => getValueToken( function(x) )
+    };
+  }
+
+  /// Вычисляет функцию от двух чисел через f64; Если одно из них не число, то будет None
+  fn math2(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize, function: fn(f64, f64) -> f64)
+  {
+    value[i] = match (Function::getNumber(structure, parameters, 0), Function::getNumber(structure, parameters, 1))
+    {
+      (Some((_, x)), Some((_, y))) => getValueToken(
+        Value::Float( function(x.toF64().unwrap_or_default(), y.toF64().unwrap_or_default()) )
+      ),
+      _ => Token::newEmpty(TokenType::None)
+    };
+  }
+
+  /// Возводит число в степень так же, как оператор ^
+  fn pow(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    value[i] = match (Function::getNumber(structure, parameters, 0), Function::getNumber(structure, parameters, 1))
+    {
+      (Some((_, x)), Some((_, y))) => getValueToken( x.pow(y) ),
+      _ => Token::newEmpty(TokenType::None)
+    };
+  }
+
+  /// Выбирает наименьшее (Less) или наибольшее (Greater) число;
+  /// Числа передаются параметрами или одним списком, а результат сохраняет свой тип
+  fn extremum(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize, order: Ordering)
+  {
+    let tokens: Vec<Token> = match Function::getListParameter(structure, parameters, 0)
+    {
+      Some(p0) if p0.getList().is_some() && parameters.getAll().is_some_and(|all: &Vec<Line>| all.len() == 1) =>
+        p0.getList().unwrap().clone(),
+      _ => parameters.getAllExpressions(structure).unwrap_or_default()
+    };
+    let mut result: Option<(Token, Value)> = None;
+    for token in tokens
+    {
+      let number: Value = getTokenValue(&token);
+      match number.toF64()
+      { Some(_) => {} None =>
+      { // Среди параметров есть не число
+        result = None;
+        break;
+      }}
+      match &result
+      {
+        Some((_, best)) if number.partial_cmp(best) != Some(order) => {}
+        _ => result = Some((token, number))
+      }
+    }
+    value[i] = match result
+    {
+      None => Token::newEmpty(TokenType::None),
+      Some((token, _)) => token
+    };
+  }
+
+  /// Ограничивает число пределами min и max;
+  /// Если min больше max, то будет None
+  fn clamp(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    value[i] = match (
+      Function::getNumber(structure, parameters, 0),
+      Function::getNumber(structure, parameters, 1),
+      Function::getNumber(structure, parameters, 2)
+    )
+    {
+      (Some(_), Some((_, min)), Some((_, max))) if min > max => Token::newEmpty(TokenType::None),
+      (Some((_, x)), Some((min, minValue)), Some(_)) if x < minValue => min,
+      (Some((_, x)), Some(_), Some((max, maxValue))) if x > maxValue => max,
+      (Some((x, _)), Some(_), Some(_)) => x,
+      _ => Token::newEmpty(TokenType::None)
+    };
+  }
+
+  /// Проверяет число через check: NaN или бесконечность;
+  /// Не число даёт None
+  fn isFloatKind(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize, check: fn(f64) -> bool)
+  {
+    match Function::getNumber(structure, parameters, 0).and_then(|(_, x)| x.toF64())
+    {
+      None => value[i] = Token::newEmpty(TokenType::None),
+      Some(x) => Function::setBool(value, i, check(x))
+    }
+  }
+
   // ===============================================================================================
   
   /// Получаем результат ввода
@@ -261,8 +779,7 @@ impl Function
         // todo: возможно потом это лучше убрать,
         //       т.к. программист сам может вызвать
         //       такое через иные методы
-        print!("{}",data);
-        io::stdout().flush().unwrap(); // forced withdrawal of old
+        runtime().write(&data);
       }}
     }}
 
@@ -282,33 +799,199 @@ impl Function
     }
   }
   
+  // ===============================================================================================
+
+  /// Выполняет файловую операцию и ставит её результат;
+  /// Если она не удалась, то это непредвиденное поведение `fs`, которое можно повторить через retry()
+  fn fileOperation(structure: &Structure, value: &mut Vec<Token>, i: usize, path: &str, operation: impl Fn() -> FileResult)
+  {
+    let mut attempt: usize = 0;
+    loop
+    {
+      match operation()
+      {
+        Ok(result) =>
+        {
+          value[i] = result;
+          break;
+        }
+        Err(error) =>
+        {
+          let values: Vec<Token> = vec![
+            Token::new( TokenType::String, path.to_string() ),
+            Token::new( TokenType::String, error )
+          ];
+          match unexpected(structure, "fs", values, attempt)
+          {
+            Unexpected::Retry => attempt += 1,
+            Unexpected::Substitute(result) => { Function::setUnexpected(value, i, Some(result)); break; }
+            Unexpected::Nothing => { Function::setUnexpected(value, i, None); break; }
+          }
+        }
+      }
+    }
+  }
+
+  /// Выполняет файловую операцию над путём из первого параметра
+  fn fileCall(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize, operation: fn(&Path) -> FileResult)
+  {
+    let path: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    let fullPath: PathBuf = resolvePath(&path);
+    Function::fileOperation(structure, value, i, &path, || operation(&fullPath));
+  }
+
+  /// Выполняет файловую операцию, которой нужны два пути: откуда и куда
+  fn fileCall2(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize, operation: fn(&Path, &Path) -> FileResult)
+  {
+    let from: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    let to: String = Function::getText(structure, parameters, 1).unwrap_or_default();
+    let (fullFrom, fullTo): (PathBuf, PathBuf) = (resolvePath(&from), resolvePath(&to));
+    Function::fileOperation(structure, value, i, &from, || operation(&fullFrom, &fullTo));
+  }
+
+  /// Проверяет путь из первого параметра
+  fn fileCheck(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize, check: fn(&Path) -> bool)
+  {
+    let path: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    Function::setBool(value, i, check( &resolvePath(&path) ));
+  }
+
+  /// Записывает в файл строку, либо список байтов; Если append, то дописывает в конец файла
+  fn writeFile(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize, append: bool)
+  {
+    let path: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    let fullPath: PathBuf = resolvePath(&path);
+    let data: Option<Token> = Function::getListParameter(structure, parameters, 1);
+    let bytes: Result<Vec<u8>, String> = match data.as_ref().and_then(|data: &Token| data.getList())
+    {
+      Some(elements) => elements.iter()
+        .map(|element: &Token|
+        {
+          let byte: String = element.getData().toString().unwrap_or_default();
+          byte.parse::<u8>().map_err(|_| format!("Byte out of range [{}]", byte))
+        })
+        .collect(),
+      None => Ok(
+        data
+          .filter(|data: &Token| *data.getDataType() != TokenType::None)
+          .and_then(|data: Token| data.getData().toString())
+          .unwrap_or_default()
+          .into_bytes()
+      )
+    };
+    Function::fileOperation(structure, value, i, &path, ||
+      match &bytes
+      {
+        Ok(bytes) => files::writeFile(&fullPath, bytes, append),
+        Err(error) => Err( error.clone() )
+      }
+    );
+  }
+
+  // ===============================================================================================
+  
+  /// Собирает команду из строки вида `command arg1 arg2`
+  fn command(data: &str) -> Result<Command, String>
+  {
+    let mut parts: SplitWhitespace<'_> = data.split_whitespace();
+    match parts.next()
+    {
+      None => Err( String::from("No command found") ),
+      Some(command) =>
+      {
+        let mut command: Command = Command::new(command);
+        command.args(parts);
+        Ok(command)
+      }
+    }
+  }
+
+  /// Ставит результат обработчика непредвиденного поведения,
+  /// либо пустое значение, если обработчика не было
+  fn setUnexpected(value: &mut [Token], i: usize, result: Option<Token>)
+  {
+    match result
+    {
+      None =>
+      {
+        value[i].setData(None);
+        value[i].setDataType( TokenType::None );
+      }
+      Some(result) =>
+      {
+        value[i].setData    ( result.getData() );
+        value[i].setDataType( *result.getDataType() );
+      }
+    }
+  }
+
   // ===============================================================================================
   
-  /// Запускает что-то и возвращает строковый output работы
+  /// Получает переменную окружения по имени; None, если её нет
+  fn env(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let name: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    value[i] = environment::getEnv(&name);
+  }
+
+  /// Ставит переменную окружения, либо удаляет её, если значения нет
+  fn setEnv(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let name: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    let variable: Option<String> = Function::getText(structure, parameters, 1);
+    Function::setBool(value, i, environment::setEnv(&name, variable));
+  }
+
+  /// Меняет текущий каталог процесса; Путь считается от текущего каталога, как в cd
+  fn chdir(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let path: String = Function::getText(structure, parameters, 0).unwrap_or_default();
+    Function::fileOperation(structure, value, i, &path, || environment::chdir( Path::new(&path) ));
+  }
+
+  // ===============================================================================================
+
+  /// Запускает что-то и возвращает строковый output работы;
+  /// Если запустить не получилось, то это непредвиденное поведение `exec`
   fn exec(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
   {
     match parameters.getExpression(structure,0)
     { None => {} Some(p0) =>
     {
       let data: String = p0.getData().toString().unwrap_or_default();
-      let mut parts: SplitWhitespace<'_> = data.split_whitespace();
-
-      let command: &str      = parts.next().expect("No command found in parameters"); // todo: no errors
-      let    args: Vec<&str> = parts.collect();
-
-      let output: Output =
-        Command::new(command)
-          .args(&args)
-          .output()
-          .expect("Failed to execute process"); // todo: no errors
-
-      let outputString: String = String::from_utf8_lossy(&output.stdout).to_string();
-      match !outputString.is_empty()
-      { false => {} true =>
-      { // result
-        value[i].setData( outputString.trim_end().to_string() );
-        value[i].setDataType( TokenType::String );
-      }}
+      let mut attempt: usize = 0;
+      loop
+      {
+        let output: Result<Output, String> = Function::command(&data)
+          .and_then(|mut command: Command| command.output().map_err(|error| error.to_string()));
+        match output
+        {
+          Ok(output) =>
+          {
+            let outputString: String = String::from_utf8_lossy(&output.stdout).to_string();
+            match !outputString.is_empty()
+            { false => {} true =>
+            { // result
+              value[i].setData( outputString.trim_end().to_string() );
+              value[i].setDataType( TokenType::String );
+            }}
+            break;
+          }
+          Err(error) =>
+          {
+            let values: Vec<Token> = vec![
+              Token::new( TokenType::String, data.clone() ),
+              Token::new( TokenType::String, error )
+            ];
+            match unexpected(structure, "exec", values, attempt)
+            {
+              Unexpected::Retry => attempt += 1,
+              Unexpected::Substitute(result) => { Function::setUnexpected(value, i, Some(result)); break; }
+              Unexpected::Nothing => { Function::setUnexpected(value, i, None); break; }
+            }
+          }
+        }
+      }
       //
     }}
     //
@@ -316,7 +999,8 @@ impl Function
   
   // ===============================================================================================
   
-  /// Запускает что-то и возвращает кодовый результат работы
+  /// Запускает что-то и возвращает кодовый результат работы;
+  /// Если запустить не получилось, то это непредвиденное поведение `exec`
   /// todo: Возможно изменение: Следует ли оставлять вывод stdout & stderr ?
   ///       -> Возможно следует сделать отдельные методы для подобных операций.
   fn execs(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
@@ -325,23 +1009,396 @@ impl Function
     { None => {} Some(p0) =>
     {
       let data: String = p0.getData().toString().unwrap_or_default();
-      let mut parts: SplitWhitespace<'_> = data.split_whitespace();
-
-      let command: &str      = parts.next().expect("No command found in expression"); // todo: no errors
-      let    args: Vec<&str> = parts.collect();
-
-      let status: ExitStatus =
-        Command::new(command)
-          .args(&args)
-          .stdout(std::process::Stdio::null())
-          .stderr(std::process::Stdio::null())
-          .status()
-          .expect("Failed to execute process"); // todo: no errors
-      value[i].setData( status.code().unwrap_or(-1).to_string() );
-      value[i].setDataType( TokenType::String );
+      let mut attempt: usize = 0;
+      loop
+      {
+        let status: Result<ExitStatus, String> = Function::command(&data)
+          .and_then(|mut command: Command|
+            command
+              .stdout(std::process::Stdio::null())
+              .stderr(std::process::Stdio::null())
+              .status()
+              .map_err(|error| error.to_string())
+          );
+        match status
+        {
+          Ok(status) =>
+          {
+            value[i].setData( status.code().unwrap_or(-1).to_string() );
+            value[i].setDataType( TokenType::String );
+            break;
+          }
+          Err(error) =>
+          {
+            let values: Vec<Token> = vec![
+              Token::new( TokenType::String, data.clone() ),
+              Token::new( TokenType::String, error )
+            ];
+            match unexpected(structure, "exec", values, attempt)
+            {
+              Unexpected::Retry => attempt += 1,
+              Unexpected::Substitute(result) => { Function::setUnexpected(value, i, Some(result)); break; }
+              Unexpected::Nothing => { Function::setUnexpected(value, i, None); break; }
+            }
+          }
+        }
+      }
     }}
   }
 
+  /// Получает настройки процесса из именованных параметров `name = value`;
+  /// Остальные параметры возвращаются по порядку
+  fn getProcessOptions(structure: &Structure, parameters: &Parameters)
+    -> (ProcessOptions, Option< Arc<RwLock<Structure>> >, Vec<usize>)
+  {
+    let mut options: ProcessOptions = ProcessOptions::default();
+    let mut onLine: Option< Arc<RwLock<Structure>> > = None;
+    let mut positional: Vec<usize> = Vec::new();
+    for (index, parameter) in parameters.getAll().map(Vec::as_slice).unwrap_or_default().iter().enumerate()
+    {
+      let tokens: &[Token] = parameter.tokens.as_deref().unwrap_or_default();
+      let (name, mut expression): (String, Vec<Token>) = match tokens
+      {
+        [name, equals, rest @ ..] if *name.getDataType() == TokenType::Word && *equals.getDataType() == TokenType::Equals =>
+          (name.getData().toString().unwrap_or_default(), rest.to_vec()),
+        _ =>
+        {
+          positional.push(index);
+          continue;
+        }
+      };
+      match name.as_str()
+      {
+        "onLine" =>
+        { // Имя метода не вычисляется, иначе метод будет запущен здесь же
+          onLine = match expression.as_slice()
+          {
+            [method] => structure.getStructureByName( &method.getData().toString().unwrap_or_default() )
+              .filter(|methodLink: &Arc<RwLock<Structure>>| methodLink.read().unwrap().dataType == StructureType::Method),
+            _ => None
+          };
+        }
+        "env" =>
+        {
+          let env: Token = structure.expression(&mut expression);
+          let variables: Vec<Token> = env.getList().cloned().unwrap_or_else(|| vec![ env ]);
+          options.env.extend( variables.iter()
+            .filter_map(|variable: &Token| variable.getData().toString())
+            .filter_map(|variable: String|
+              variable.split_once('=').map(|(name, value): (&str, &str)| (name.to_string(), value.to_string()))
+            )
+          );
+        }
+        "cwd" =>
+        {
+          let cwd: Option<String> = structure.expression(&mut expression).getData().toString();
+          options.cwd = cwd.map(|cwd: String| resolvePath(&cwd));
+        }
+        "input" => options.input = structure.expression(&mut expression).getData().toString(),
+        "timeout" =>
+        {
+          let timeout: Option<u64> = structure.expression(&mut expression)
+            .getData().toString()
+            .and_then(|timeout: String| timeout.parse::<u64>().ok());
+          options.timeout = timeout.map(Duration::from_millis);
+        }
+        _ => {}
+      }
+    }
+    (options, onLine, positional)
+  }
+
+  /// Запускает процесс; Каждый параметр после команды это один аргумент без разбора на слова,
+  /// а список передаёт свои элементы. Возвращает запись с полями code, stdout, stderr и signal.
+  /// Если процесс не удалось запустить, то это непредвиденное поведение `exec`
+  fn process(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let (options, onLine, positional): (ProcessOptions, Option< Arc<RwLock<Structure>> >, Vec<usize>) =
+      Function::getProcessOptions(structure, parameters);
+    let command: String = match positional.first()
+    {
+      None => return Function::setUnexpected(value, i, None),
+      Some(&index) => Function::getText(structure, parameters, index).unwrap_or_default()
+    };
+    // Каждый параметр это один аргумент, а список - несколько
+    let mut args: Vec<String> = Vec::new();
+    for &index in positional.iter().skip(1)
+    {
+      match Function::getListParameter(structure, parameters, index)
+      { None => {} Some(arg) =>
+      {
+        match arg.getList()
+        {
+          None => args.extend( arg.getData().toString() ),
+          Some(elements) => args.extend(
+            elements.iter().filter_map(|element: &Token| element.getData().toString())
+          )
+        }
+      }}
+    }
+
+    // Строки stdout передаются методу по мере появления
+    let mut callLine = |line: String|
+    {
+      match &onLine
+      { None => {} Some(methodLink) =>
+      {
+        methodLink.read().unwrap()
+          .setParameters( vec![ Token::new(TokenType::String, line) ] );
+        readLines(methodLink.clone());
+      }}
+    };
+
+    let mut attempt: usize = 0;
+    loop
+    {
+      let handler: Option<&mut dyn FnMut(String)> = match onLine.is_some()
+      {
+        true  => Some(&mut callLine),
+        false => None
+      };
+      match runProcess(&command, &args, options.clone(), handler)
+      {
+        Ok(output) =>
+        {
+          value[i] = output.toRecord();
+          break;
+        }
+        Err(error) =>
+        {
+          let values: Vec<Token> = vec![
+            Token::new( TokenType::String, command.clone() ),
+            Token::new( TokenType::String, error )
+          ];
+          match unexpected(structure, "exec", values, attempt)
+          {
+            Unexpected::Retry => attempt += 1,
+            Unexpected::Substitute(result) => { Function::setUnexpected(value, i, Some(result)); break; }
+            Unexpected::Nothing => { Function::setUnexpected(value, i, None); break; }
+          }
+        }
+      }
+    }
+  }
+
+  // ===============================================================================================
+
+  /// Получает структуру-метод по имени в параметре
+  fn getMethod(structure: &Structure, parameters: &Parameters, index: usize) -> Option< Arc<RwLock<Structure>> >
+  {
+    // Имя метода не вычисляется, иначе метод будет запущен здесь же
+    let methodName: String = match parameters.get(index)?.tokens.as_deref()
+    {
+      Some([methodToken]) => methodToken.getData().toString().unwrap_or_default(),
+      _ => String::new()
+    };
+    structure.getStructureByName(&methodName)
+      .filter(|methodLink: &Arc<RwLock<Structure>>| methodLink.read().unwrap().dataType == StructureType::Method)
+  }
+
+  /// Вычисляет параметры для метода, начиная с указанного
+  fn getMethodParameters(structure: &Structure, parameters: &Parameters, start: usize) -> Vec<Token>
+  {
+    (start..parameters.getAll().map_or(0, Vec::len))
+      .filter_map(|index: usize| parameters.getExpression(structure, index))
+      .collect()
+  }
+
+  /// Запускает структуру-метод в новом потоке и возвращает номер потока;
+  /// Первый параметр это имя метода, остальные - его параметры
+  fn spawn(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    match Function::getMethod(structure, parameters, 0)
+    {
+      None => Function::setUnexpected(value, i, None),
+      Some(methodLink) =>
+      {
+        let methodParameters: Vec<Token> = Function::getMethodParameters(structure, parameters, 1);
+        let id: usize = spawnThread(&methodLink, methodParameters);
+        value[i].setDataType( TokenType::UInt );
+        value[i].setData( id.to_string() );
+      }
+    }
+  }
+
+  /// Получает номер потока из первого параметра
+  fn getThreadId(structure: &Structure, parameters: &Parameters) -> usize
+  {
+    match parameters.getExpression(structure, 0)
+    {
+      None => 0,
+      Some(p0) => p0.getData().toString().unwrap_or_default().parse::<usize>().unwrap_or_default()
+    }
+  }
+
+  /// Ждёт завершения потока и возвращает результат его метода;
+  /// Если поток упал, то это непредвиденное поведение `thread`;
+  /// Если первый параметр - список, то соединяет его элементы в строку
+  fn join(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let p0: Option<Token> = Function::getListParameter(structure, parameters, 0);
+    match p0.as_ref().and_then(|p0: &Token| p0.getList())
+    { None => {} Some(elements) =>
+    {
+      return Function::joinText(structure, parameters, elements, value, i);
+    }}
+    let id: usize = match p0
+    {
+      None => 0,
+      Some(p0) => p0.getData().toString().unwrap_or_default().parse::<usize>().unwrap_or_default()
+    };
+    match joinThread(id)
+    {
+      None => Function::setUnexpected(value, i, None),
+      Some(Ok(result)) => Function::setUnexpected(value, i, result),
+      Some(Err(message)) =>
+      {
+        let values: Vec<Token> = vec![
+          Token::new( TokenType::UInt, id.to_string() ),
+          Token::new( TokenType::String, message )
+        ];
+        match unexpected(structure, "thread", values, 0)
+        {
+          Unexpected::Substitute(result) => Function::setUnexpected(value, i, Some(result)),
+          Unexpected::Retry | Unexpected::Nothing => Function::setUnexpected(value, i, None)
+        }
+      }
+    }
+  }
+
+  /// Проверяет, работает ли ещё поток
+  fn alive(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let id: usize = Function::getThreadId(structure, parameters);
+    value[i].setDataType( TokenType::Bool );
+    value[i].setData( isThreadAlive(id).to_string() );
+  }
+
+  // ===============================================================================================
+
+  /// Создаёт канал и возвращает его номер;
+  /// Первый параметр это тип значений канала, второй - ёмкость, без неё канал неограниченный
+  fn channel(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    // Тип не вычисляется, т.к. это имя типа, а не структуры
+    let dataType: StructureType = match parameters.get(0)
+    {
+      None => StructureType::Any,
+      Some(line) => match line.tokens.as_deref()
+      {
+        Some([typeToken]) => typeToken.getStructureTypeSimple(),
+        _ => StructureType::Any
+      }
+    };
+    let capacity: Option<usize> = parameters.getExpression(structure, 1)
+      .and_then(|p1: Token| p1.getData().toString()?.parse::<usize>().ok());
+    let id: usize = newChannel(dataType, capacity);
+    value[i].setDataType( TokenType::UInt );
+    value[i].setData( id.to_string() );
+  }
+
+  /// Получает номер канала или таймера из параметра
+  fn getId(structure: &Structure, parameters: &Parameters, index: usize) -> usize
+  {
+    match parameters.getExpression(structure, index)
+    {
+      None => 0,
+      Some(p) => p.getData().toString().unwrap_or_default().parse::<usize>().unwrap_or_default()
+    }
+  }
+
+  /// Отправляет значение в канал; Возвращает false, если канал закрыт
+  fn send(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let id: usize = Function::getId(structure, parameters, 0);
+    let sent: bool = match parameters.getExpression(structure, 1)
+    {
+      None => false,
+      Some(p1) => sendChannel(id, p1)
+    };
+    value[i].setDataType( TokenType::Bool );
+    value[i].setData( sent.to_string() );
+  }
+
+  /// Ждёт значение из канала; Второй параметр это наибольшее время ожидания в миллисекундах
+  fn recv(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let id: usize = Function::getId(structure, parameters, 0);
+    let timeout: Option<Duration> = parameters.getExpression(structure, 1)
+      .and_then(|p1: Token| p1.getData().toString()?.parse::<u64>().ok())
+      .map(Duration::from_millis);
+    Function::setUnexpected(value, i, recvChannel(id, timeout));
+  }
+
+  /// Получает значение из канала без ожидания
+  fn tryRecv(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let id: usize = Function::getId(structure, parameters, 0);
+    Function::setUnexpected(value, i, tryRecvChannel(id));
+  }
+
+  /// Ждёт значение в любом из каналов и возвращает номер канала, в котором оно есть
+  fn select(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let ids: Vec<usize> = (0..parameters.getAll().map_or(0, Vec::len))
+      .map(|index: usize| Function::getId(structure, parameters, index))
+      .collect();
+    match selectChannels(&ids)
+    {
+      None => Function::setUnexpected(value, i, None),
+      Some(id) =>
+      {
+        value[i].setDataType( TokenType::UInt );
+        value[i].setData( id.to_string() );
+      }
+    }
+  }
+
+  // ===============================================================================================
+
+  /// Создаёт таймер, который вызовет метод через ms миллисекунд, и возвращает номер таймера;
+  /// Первый параметр это время, второй - имя метода, остальные - его параметры
+  fn timer(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize, repeat: bool)
+  {
+    let delay: u64 = parameters.getExpression(structure, 0)
+      .and_then(|p0: Token| p0.getData().toString()?.parse::<u64>().ok())
+      .unwrap_or_default();
+    match Function::getMethod(structure, parameters, 1)
+    {
+      None => Function::setUnexpected(value, i, None),
+      Some(methodLink) =>
+      {
+        let methodParameters: Vec<Token> = Function::getMethodParameters(structure, parameters, 2);
+        let id: usize = newTimer(methodLink, methodParameters, Duration::from_millis(delay), repeat);
+        value[i].setDataType( TokenType::UInt );
+        value[i].setData( id.to_string() );
+      }
+    }
+  }
+
+  /// Отменяет таймер; Возвращает false, если он уже отработал
+  fn cancel(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let id: usize = Function::getId(structure, parameters, 0);
+    value[i].setDataType( TokenType::Bool );
+    value[i].setData( cancelTimer(id).to_string() );
+  }
+
+  /// Возвращает опоздание последнего срабатывания таймера в микросекундах
+  fn drift(structure: &Structure, parameters: &Parameters, value: &mut Vec<Token>, i: usize)
+  {
+    let id: usize = Function::getId(structure, parameters, 0);
+    match getTimerDrift(id)
+    {
+      None => Function::setUnexpected(value, i, None),
+      Some(drift) =>
+      {
+        value[i].setDataType( TokenType::UInt );
+        value[i].setData( drift.as_micros().to_string() );
+      }
+    }
+  }
+
   // ===============================================================================================
   
   /// todo desc
@@ -433,11 +1490,7 @@ impl Function
         // todo Не уверен что это лучший вариант
         let index: usize = 
         {
-          let structuresLink: RwLockReadGuard<Option< Vec< Arc<RwLock<Structure>> > >> = 
-            structure.structures.read().unwrap();
-          let structures: &Vec< Arc<RwLock<Structure>> > = structuresLink
-            .as_ref()
-            .expect("structures should be Some after pushStructure"); // todo TokenType::None
+          let structures: RwLockReadGuard<Structures> = structure.structures.read().unwrap();
           structures.iter()
             .position(|s| Arc::ptr_eq(s, &tempStructureLink))
             .expect("newly added structure not found") // todo TokenType::None
@@ -524,12 +1577,27 @@ impl Structure
             match parameters.getExpression(self,0)
             { None => {} Some(p0) =>
             {
-              value[i].setDataType( TokenType::Char );
-              value[i].setData(
-                (p0.getData().toString().unwrap_or_default()
-                  .parse::<u8>().unwrap() as char
-                ).to_string()
-              );
+              let data: String = p0.getData().toString().unwrap_or_default();
+              match data.parse::<u8>()
+              {
+                Ok(code) =>
+                {
+                  value[i].setDataType( TokenType::Char );
+                  value[i].setData( (code as char).to_string() );
+                }
+                Err(error) =>
+                { // Это непредвиденное поведение `parse`
+                  let values: Vec<Token> = vec![
+                    Token::new( TokenType::String, data ),
+                    Token::new( TokenType::String, error.to_string() )
+                  ];
+                  match unexpected(self, "parse", values, 0)
+                  {
+                    Unexpected::Substitute(result) => Function::setUnexpected(value, i, Some(result)),
+                    Unexpected::Retry | Unexpected::Nothing => Function::setUnexpected(value, i, None)
+                  }
+                }
+              }
               //
             }}
             //
@@ -542,10 +1610,19 @@ impl Structure
       { // Вариант в котором это обращение к стандартной или custom функции;
         // todo: проверка на нижний регистр
 
+        // Структуры пользователя закрывают базовые методы с тем же именем,
+        // поэтому сначала ищем их, как и любое другое имя
+        let isMethod: bool = self.getStructureByName(&structureName).is_some();
+
         // Далее идут базовые методы;
         // Эти методы ожидают аргументов
         'basicMethods:
         { // Это позволит выйти, если мы ожидаем не стандартные варианты
+          match isMethod
+          { false => {} true =>
+          {
+            break 'basicMethods;
+          }}
           match structureName.as_str()
           { // Проверяем на сходство стандартных функций
 
@@ -556,12 +1633,90 @@ impl Structure
             "stype" => Function::stype(self, &parameters, value, i),
             "mut" => Function::_mut(self, &parameters, value, i),
             "randUInt" => Function::randUInt(self, &parameters, value, i),
+            "randInt" => Function::randInt(self, &parameters, value, i),
+            "randFloat" => Function::randFloat(self, &parameters, value, i),
+            "randBool" => Function::randBool(self, &parameters, value, i),
+            "choice" => Function::choice(self, &parameters, value, i),
+            "shuffle" => Function::shuffle(self, &parameters, value, i),
+            "seed" => Function::seed(self, &parameters, value, i),
+            "readFile" => Function::fileCall(self, &parameters, value, i, files::readFile),
+            "readBytes" => Function::fileCall(self, &parameters, value, i, files::readBytes),
+            "writeFile" => Function::writeFile(self, &parameters, value, i, false),
+            "appendFile" => Function::writeFile(self, &parameters, value, i, true),
+            "exists" => Function::fileCheck(self, &parameters, value, i, Path::exists),
+            "isDir" => Function::fileCheck(self, &parameters, value, i, Path::is_dir),
+            "listDir" => Function::fileCall(self, &parameters, value, i, files::listDir),
+            "mkdir" => Function::fileCall(self, &parameters, value, i, files::mkdir),
+            "remove" => Function::fileCall(self, &parameters, value, i, files::remove),
+            "rename" => Function::fileCall2(self, &parameters, value, i, files::rename),
+            "copy" => Function::fileCall2(self, &parameters, value, i, files::copy),
+            "fileSize" => Function::fileCall(self, &parameters, value, i, files::fileSize),
+            "modifiedTime" => Function::fileCall(self, &parameters, value, i, files::modifiedTime),
             "len" => Function::len(self, &parameters, value, i),
+            "split" => Function::split(self, &parameters, value, i),
+            "replace" => Function::replace(self, &parameters, value, i),
+            "trim" => Function::trim(self, &parameters, value, i),
+            "startsWith" => Function::startsWith(self, &parameters, value, i),
+            "endsWith" => Function::endsWith(self, &parameters, value, i),
+            "find" => Function::find(self, &parameters, value, i),
+            "slice" => Function::slice(self, &parameters, value, i),
+            "upper" => Function::upper(self, &parameters, value, i),
+            "lower" => Function::lower(self, &parameters, value, i),
+            "repeat" => Function::repeat(self, &parameters, value, i),
+            "parseInt" => Function::parseInt(self, &parameters, value, i),
+            "parseFloat" => Function::parseFloat(self, &parameters, value, i),
+            "chars" => Function::chars(self, &parameters, value, i),
+            "abs" => Function::math(self, &parameters, value, i, Value::abs),
+            "floor" => Function::math(self, &parameters, value, i, |x| x.roundWith(f64::floor)),
+            "ceil" => Function::math(self, &parameters, value, i, |x| x.roundWith(f64::ceil)),
+            "round" => Function::math(self, &parameters, value, i, |x| x.roundWith(f64::round)),
+            "sqrt" => Function::math(self, &parameters, value, i, |x| x.floatMap(f64::sqrt)),
+            "exp" => Function::math(self, &parameters, value, i, |x| x.floatMap(f64::exp)),
+            "ln" => Function::math(self, &parameters, value, i, |x| x.floatMap(f64::ln)),
+            "log10" => Function::math(self, &parameters, value, i, |x| x.floatMap(f64::log10)),
+            "sin" => Function::math(self, &parameters, value, i, |x| x.floatMap(f64::sin)),
+            "cos" => Function::math(self, &parameters, value, i, |x| x.floatMap(f64::cos)),
+            "tan" => Function::math(self, &parameters, value, i, |x| x.floatMap(f64::tan)),
+            "asin" => Function::math(self, &parameters, value, i, |x| x.floatMap(f64::asin)),
+            "acos" => Function::math(self, &parameters, value, i, |x| x.floatMap(f64::acos)),
+            "atan" => Function::math(self, &parameters, value, i, |x| x.floatMap(f64::atan)),
+            "atan2" => Function::math2(self, &parameters, value, i, f64::atan2),
+            "hypot" => Function::math2(self, &parameters, value, i, f64::hypot),
+            "pow" => Function::pow(self, &parameters, value, i),
+            "min" => Function::extremum(self, &parameters, value, i, Ordering::Less),
+            "max" => Function::extremum(self, &parameters, value, i, Ordering::Greater),
+            "clamp" => Function::clamp(self, &parameters, value, i),
+            "isNan" => Function::isFloatKind(self, &parameters, value, i, f64::is_nan),
+            "isInf" => Function::isFloatKind(self, &parameters, value, i, f64::is_infinite),
+            "pi" => value[i] = getValueToken( Value::Float(std::f64::consts::PI) ),
+            "e" => value[i] = getValueToken( Value::Float(std::f64::consts::E) ),
             "input" => Function::input(self, &parameters, value, i),
             "exec" => Function::exec(self, &parameters, value, i),
             "execs" => Function::execs(self, &parameters, value, i),
+            "process" => Function::process(self, &parameters, value, i),
+            "env" => Function::env(self, &parameters, value, i),
+            "setEnv" => Function::setEnv(self, &parameters, value, i),
+            "envAll" => value[i] = environment::envAll(),
+            "cwd" => value[i] = environment::cwd(),
+            "chdir" => Function::chdir(self, &parameters, value, i),
+            "pid" => value[i] = environment::pid(),
+            "scriptPath" => value[i] = environment::scriptPath(),
+            "hostname" => value[i] = environment::hostname(),
+            "platform" => value[i] = environment::platform(),
             "importNative" => Function::importNative(self, &parameters, value, i),
             "Usize" => Function::Usize(self, &parameters, value, i),
+            "spawn" => Function::spawn(self, &parameters, value, i),
+            "join" => Function::join(self, &parameters, value, i),
+            "alive" => Function::alive(self, &parameters, value, i),
+            "channel" => Function::channel(self, &parameters, value, i),
+            "send" => Function::send(self, &parameters, value, i),
+            "recv" => Function::recv(self, &parameters, value, i),
+            "tryRecv" => Function::tryRecv(self, &parameters, value, i),
+            "select" => Function::select(self, &parameters, value, i),
+            "after" => Function::timer(self, &parameters, value, i, false),
+            "every" => Function::timer(self, &parameters, value, i, true),
+            "cancel" => Function::cancel(self, &parameters, value, i),
+            "drift" => Function::drift(self, &parameters, value, i),
             _ => { break 'basicMethods; } // Выходим, ожидается нестандартный метод
           }
           return;
@@ -572,26 +1727,21 @@ impl Structure
         // Передаём параметры, они также могут быть None
 //        println!("? {} - parameters: {:?}",structureName,parameters.get(0).unwrap().tokens);
 //\        println!("  > A1 {:?}",parameters.getAllExpressions(self).unwrap_or_default());
-        self.procedureCall(&structureName, parameters);
+        let result: Option<Token> = self.procedureCall(&structureName, parameters);
         // После чего решаем какой результат оставить
-        match self.getStructureByName(&structureName)
-        { None => {} Some(structureLink) =>
-        { // По результату структуры, определяем пустой он или нет
-          match
-            &structureLink.read().unwrap()
-              .result
-          {
-            Some(result) =>
-            { // Результат не пустой, значит оставляем его
-              value[i].setData    ( result.getData() );
-              value[i].setDataType( *result.getDataType() );
-            }
-            None =>
-            { // Если результата структуры не было,
-              // значит это была действительно процедура
-              value[i].setData(None);
-              value[i].setDataType( TokenType::None );
-            }
+        match isMethod
+        { false => {} true => match result
+        {
+          Some(result) =>
+          { // Результат не пустой, значит оставляем его
+            value[i].setData    ( result.getData() );
+            value[i].setDataType( *result.getDataType() );
+          }
+          None =>
+          { // Если результата структуры не было,
+            // значит это была действительно процедура
+            value[i].setData(None);
+            value[i].setDataType( TokenType::None );
           }
         }}
         // -----------------------------------------------------------------------------------------
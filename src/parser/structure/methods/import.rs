@@ -0,0 +1,258 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard};
+use crate::parser::runtime::{runtime, Runtime};
+use crate::parser::parser::readLines;
+use crate::parser::structure::methods::parameters::Parameters;
+use crate::parser::structure::structure::{Structure, StructureMut};
+use crate::parser::structure::structureType::StructureType;
+use crate::tokenizer::tokenizer::readTokens;
+use crate::tokenizer::types::line::Line;
+use crate::tokenizer::types::token::Token;
+use crate::tokenizer::types::tokenType::TokenType;
+#[cfg(not(target_family = "wasm"))]
+use crate::logger::logger::log;
+// =================================================================================================
+
+// Импорт других .rt файлов;
+// Файл читается и выполняется только один раз в собственную namespace структуру,
+// после чего она кешируется по каноническому пути и может быть:
+//   - привязана к имени: `lib = import("lib.rt")`, тогда `lib.method()`
+//   - вставлена в текущую структуру: `import("lib.rt")`, тогда `method()`
+// Относительный путь ищется от файла, в коде которого написан import,
+// в том числе когда это метод модуля, вызванный позже из другого файла.
+
+// =================================================================================================
+
+/// Импорты одной среды выполнения
+#[derive(Default)]
+pub(crate) struct ImportState
+{
+  /// Уже выполненные файлы по каноническому пути
+  cache: Mutex< HashMap< PathBuf, Arc<RwLock<Structure>> > >
+}
+
+impl ImportState
+{
+  /// Забывает все выполненные файлы
+  pub(crate) fn clear(&self) -> ()
+  {
+    self.cache.lock().unwrap().clear();
+  }
+}
+
+thread_local!
+{
+  /// Файлы, которые выполняются прямо сейчас в текущем потоке; Нужны для поиска циклов импорта
+  static Loading: RefCell< Vec<PathBuf> > = const { RefCell::new(Vec::new()) };
+}
+
+// =================================================================================================
+
+/// Ключевое слово импорта
+const importKeyword: &str = "import";
+
+/// Проверяет, что токен является ключевым словом import
+pub(crate) fn isImportKeyword(token: &Token) -> bool
+{
+  *token.getDataType() == TokenType::Word &&
+    token.getData().toString().as_deref() == Some(importKeyword)
+}
+
+/// Получает канонический путь запущенного файла;
+/// None, если запущен скрипт из строки
+fn getEntryPath(runtime: &Runtime) -> Option<PathBuf>
+{
+  let filePath: String = runtime.getFilePath();
+  match filePath.ends_with(".rt")
+  {
+    true  => fs::canonicalize(filePath).ok(),
+    false => None
+  }
+}
+
+/// Получает каталог, относительно которого ищутся пути импорта;
+/// Это каталог файла ближайшей namespace структуры выше по родителям,
+/// либо запущенного файла, либо текущий каталог для скрипта из строки
+fn getImportBase(runtime: &Runtime, structureLink: &Arc<RwLock<Structure>>) -> PathBuf
+{
+  let mut importingFile: Option<PathBuf> = None;
+  let mut current: Option< Arc<RwLock<Structure>> > = Some(structureLink.clone());
+  while let Some(structureLink) = current
+  {
+    let structure: RwLockReadGuard<Structure> = structureLink.read().unwrap();
+    match &structure.filePath
+    {
+      Some(filePath) =>
+      {
+        importingFile = Some(filePath.clone());
+        break;
+      }
+      None => current = structure.parent.clone()
+    }
+  }
+  importingFile.or_else(|| getEntryPath(runtime))
+    .and_then(|importingFile: PathBuf| importingFile.parent().map(Path::to_path_buf))
+    .unwrap_or_default()
+}
+
+/// Получает канонический путь файла импорта
+fn resolveImportPath(runtime: &Runtime, path: &str, structureLink: &Arc<RwLock<Structure>>) -> Option<PathBuf>
+{
+  let path: &Path = Path::new(path);
+  let fullPath: PathBuf = match path.is_absolute()
+  {
+    true  => path.to_path_buf(),
+    false => getImportBase(runtime, structureLink).join(path)
+  };
+  fs::canonicalize(fullPath).ok()
+}
+
+// =================================================================================================
+
+/// Выполняет файл в собственную namespace структуру или берёт её из кеша;
+/// Относительный путь ищется от файла, в коде которого находится structureLink;
+/// Возвращает None, если файл не удалось найти, либо был найден цикл импорта
+pub fn importStructure(path: &str, structureLink: &Arc<RwLock<Structure>>) -> Option< Arc<RwLock<Structure>> >
+{
+  let runtime: Arc<Runtime> = runtime();
+  let canonicalPath: PathBuf = match resolveImportPath(&runtime, path, structureLink)
+  {
+    Some(canonicalPath) => canonicalPath,
+    None =>
+    {
+      #[cfg(not(target_family = "wasm"))]
+      log("err", &format!("Import file not found [{}]", path));
+      return None;
+    }
+  };
+
+  // Уже выполнен
+  {
+    let cache: MutexGuard< HashMap< PathBuf, Arc<RwLock<Structure>> > > = runtime.imports.cache.lock().unwrap();
+    match cache.get(&canonicalPath)
+    { None => {} Some(namespaceLink) =>
+    {
+      return Some(namespaceLink.clone());
+    }}
+  }
+
+  // Цикл импорта: файл уже выполняется выше по стеку, либо это запущенный файл
+  match Loading.with(|loading: &RefCell< Vec<PathBuf> >| loading.borrow().contains(&canonicalPath)) ||
+        getEntryPath(&runtime).as_ref() == Some(&canonicalPath)
+  {
+    false => {}
+    true =>
+    {
+      // Путь как в import(), чтобы сообщение не зависело от того, где лежат файлы
+      #[cfg(not(target_family = "wasm"))]
+      log("err", &format!("Import cycle [{}]", path));
+      return None;
+    }
+  }
+
+  let buffer: Vec<u8> = fs::read(&canonicalPath).ok()?;
+  let lines: Vec< Arc<RwLock<Line>> > = readTokens(buffer, false);
+
+  let mut namespace: Structure = Structure::new(
+    Some( canonicalPath.display().to_string() ),
+    StructureMut::Constant,
+    StructureType::List,
+    Some(lines),
+    None // namespace изолирован от импортирующей стороны
+  );
+  namespace.filePath = Some(canonicalPath.clone());
+  let namespaceLink: Arc<RwLock<Structure>> = Arc::new(RwLock::new(namespace));
+
+  // Выполняем файл; Блокировки не держатся, т.к. внутри могут быть свои импорты
+  Loading.with(|loading: &RefCell< Vec<PathBuf> >| loading.borrow_mut().push(canonicalPath.clone()));
+  readLines(namespaceLink.clone());
+  Loading.with(|loading: &RefCell< Vec<PathBuf> >| loading.borrow_mut().pop());
+
+  runtime.imports.cache.lock().unwrap().insert(canonicalPath, namespaceLink.clone());
+  Some(namespaceLink)
+}
+
+/// Вставляет все объявления namespace структуры в текущую структуру
+fn mergeStructure(structure: &Structure, namespaceLink: &Arc<RwLock<Structure>>)
+{
+  let children: Vec< Arc<RwLock<Structure>> > =
+    namespaceLink.read().unwrap()
+      .structures.read().unwrap()
+      .to_vec();
+  for child in children
+  {
+    structure.pushStructure(child);
+  }
+}
+
+/// Создаёт структуру с именем, которая разделяет объявления namespace структуры;
+/// Так `name.method()` обращается к тем же структурам, что и в кеше
+fn bindStructure(name: String, namespaceLink: &Arc<RwLock<Structure>>, parentLink: &Arc<RwLock<Structure>>)
+  -> Arc<RwLock<Structure>>
+{
+  let mut structure: Structure = Structure::new(
+    Some(name),
+    StructureMut::Constant,
+    StructureType::List,
+    None,
+    Some(parentLink.clone())
+  );
+  structure.structures = namespaceLink.read().unwrap().structures.clone();
+  Arc::new(RwLock::new(structure))
+}
+
+// =================================================================================================
+
+/// Ищет импорт в линии и выполняет его:
+///   - `import("path")` вставляет объявления в текущую структуру
+///   - `name = import("path")` привязывает их к имени
+///
+/// Возвращает true, если линия была импортом
+pub fn searchImport(lineTokens: &[Token], parentLink: Arc<RwLock<Structure>>) -> bool
+{
+  let (name, callIndex): (Option<String>, usize) = match lineTokens.len()
+  {
+    2 => (None, 0),
+    4 if *lineTokens[0].getDataType() == TokenType::Word &&
+         *lineTokens[1].getDataType() == TokenType::Equals =>
+      (lineTokens[0].getData().toString(), 2),
+    _ => return false
+  };
+  match isImportKeyword(&lineTokens[callIndex]) &&
+        *lineTokens[callIndex+1].getDataType() == TokenType::CircleBracketBegin
+  {
+    false => return false,
+    true => {}
+  }
+
+  // Получаем путь из параметров
+  let path: String =
+  {
+    let parameters: Parameters = Parameters::new( lineTokens[callIndex+1].lines.clone() );
+    match parameters.getExpression(&parentLink.read().unwrap(), 0)
+    {
+      None => return true,
+      Some(path) => path.getData().toString().unwrap_or_default()
+    }
+  };
+
+  match importStructure(&path, &parentLink)
+  { None => {} Some(namespaceLink) =>
+  {
+    match name
+    {
+      None => mergeStructure(&parentLink.read().unwrap(), &namespaceLink),
+      Some(name) =>
+      {
+        let structureLink: Arc<RwLock<Structure>> = bindStructure(name, &namespaceLink, &parentLink);
+        parentLink.read().unwrap().pushStructure(structureLink);
+      }
+    }
+  }}
+  true
+}
+
+// =================================================================================================
@@ -1,3 +1,11 @@
 mod function;
 mod procedure;
-pub(super) mod parameters;
\ No newline at end of file
+pub(super) mod parameters;
+pub(crate) mod import;
+pub(crate) mod thread;
+pub(crate) mod channel;
+pub(crate) mod timer;
+pub(crate) mod random;
+mod files;
+mod process;
+mod environment;
\ No newline at end of file
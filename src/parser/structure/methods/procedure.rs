@@ -1,19 +1,18 @@
 use std::process::Command;
 use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
-use std::thread::sleep;
 use std::time::Duration;
-use crate::{_exit, _exitCode};
-use crate::parser::parser::{readLines, searchStructure};
+use crate::parser::runtime::{runtime, Runtime};
+use crate::parser::parser::searchStructure;
 use crate::parser::structure::structure::Structure;
+use crate::parser::structure::structures::Structures;
 use crate::tokenizer::types::line::Line;
 #[cfg(not(target_family = "wasm"))]
-use std::io;
-#[cfg(not(target_family = "wasm"))]
-use std::io::Write;
-use std::ops::DerefMut;
-#[cfg(not(target_family = "wasm"))]
-use crate::logger::logger::formatPrint;
+use crate::logger::logger::{formatPrintError, formatString};
 use crate::parser::structure::methods::parameters::{Parameters};
+use crate::parser::unexpected::requestRetry;
+use crate::parser::structure::methods::channel::{closeChannel, sendChannel};
+use crate::parser::structure::methods::timer::waitEvents;
+use crate::parser::structure::methods::thread::callMethod;
 use crate::tokenizer::types::token::Token;
 // =================================================================================================
 
@@ -31,15 +30,22 @@ impl Procedure
     match parameters.getAllExpressions(structure)
     { None => {} Some(parameters) =>
     {
+      let runtime: Arc<Runtime> = runtime();
+      // После exit() значения вызовов уже не досчитаны, их не выводим
+      match runtime.isExit()
+      { false => {} true =>
+      {
+        return;
+      }}
       for p in parameters.iter()
       {
-        formatPrint( p.getData().toString().unwrap_or_default().as_str() );
+        let mut text: String = formatString( p.getData().toString().unwrap_or_default().as_str() );
         match newline
         {
           false => {},
-          true => formatPrint("\n")
+          true => text.push('\n')
         }
-        io::stdout().flush().unwrap();
+        runtime.write(&text);
       }
     }}
   }
@@ -99,7 +105,8 @@ impl Procedure
   
   // ===============================================================================================
   
-  /// Ожидает определённое количество ms
+  /// Ожидает определённое количество ms;
+  /// В основном потоке во время ожидания срабатывают таймеры
   fn sleep(structure: &Structure, parameters: &Parameters)
   {
     match parameters.getExpression(structure, 0)
@@ -112,7 +119,7 @@ impl Procedure
       match valueNumber > 0
       {
         false => {}
-        true => { sleep(Duration::from_millis(valueNumber)); }
+        true => { waitEvents(Duration::from_millis(valueNumber)); }
       }
       //
     } }
@@ -125,17 +132,86 @@ impl Procedure
   fn exit(structure: &Structure, parameters: &Parameters)
   {
     match parameters.getExpression(structure,0)
-    { None => {} Some(p0) => unsafe
+    { None => {} Some(p0) =>
     {
-      _exit = true;
-      _exitCode =
+      runtime().exit(
         p0
           .getData().toString().unwrap_or_default()
-          .parse::<i32>().unwrap_or(1);
+          .parse::<i32>().unwrap_or(1)
+      );
     }}
   }
   
   // ===============================================================================================
+
+  /// Проверяет условие, которое должно быть верным;
+  /// Если это не так, то выводит сообщение с местом проверки в stderr,
+  /// а программа продолжает работу, но завершится с кодом 1.
+  fn assert(structure: &Structure, parameters: &Parameters)
+  {
+    let condition: bool = match parameters.getExpression(structure, 0)
+    {
+      None => false,
+      Some(p0) => matches!(p0.getData().toString().as_deref(), Some("1") | Some("true"))
+    };
+    match condition
+    { true => {} false =>
+    {
+      let message: String = match parameters.getExpression(structure, 1)
+      {
+        None => String::new(),
+        Some(p1) => p1.getData().toString().unwrap_or_default()
+      };
+      let runtime: Arc<Runtime> = runtime();
+      let source: String = runtime.getSource(structure);
+      #[cfg(not(target_family = "wasm"))]
+      formatPrintError(&format!(
+        "   \\fg(#e91a34)\\b-\\cb\\cfg \\fg(#f0f8ff)\\bAssertion failed [{}] {}\\c\n",
+        source,
+        message
+      ));
+      runtime.setExitCode(1);
+    }}
+  }
+
+  // ===============================================================================================
+
+  /// Просит повторить операцию, которая вызвала текущий обработчик `! kind`
+  fn retry()
+  {
+    requestRetry();
+  }
+
+  // ===============================================================================================
+
+  /// Получает номер канала из первого параметра
+  fn getChannelId(structure: &Structure, parameters: &Parameters) -> usize
+  {
+    match parameters.getExpression(structure, 0)
+    {
+      None => 0,
+      Some(p0) => p0.getData().toString().unwrap_or_default().parse::<usize>().unwrap_or_default()
+    }
+  }
+
+  /// Отправляет значение в канал, не проверяя результат
+  fn send(structure: &Structure, parameters: &Parameters)
+  {
+    let id: usize = Procedure::getChannelId(structure, parameters);
+    match parameters.getExpression(structure, 1)
+    { None => {} Some(p1) =>
+    {
+      sendChannel(id, p1);
+    }}
+  }
+
+  /// Закрывает канал
+  fn close(structure: &Structure, parameters: &Parameters)
+  {
+    closeChannel( Procedure::getChannelId(structure, parameters) );
+  }
+
+  // ===============================================================================================
 }
 
 // =================================================================================================
@@ -154,7 +230,8 @@ impl Structure
   /// 
   /// todo Кстати было замечено что 2 и последующие параметры могут обрабатывать не верно, а 1 норм.
   ///   Пример был когда у 2 параметра None - то его не видно, а 1 был виден, при проверках type/stype.
-  pub fn procedureCall(&self, structureName: &str, parameters: Parameters) -> ()
+  /// Возвращает результат нестандартного метода, если он был
+  pub fn procedureCall(&self, structureName: &str, parameters: Parameters) -> Option<Token>
   {
     if structureName.starts_with(|c: char| c.is_lowercase()) // todo if -> match
     { // Если название в нижнем регистре - то это точно процедура
@@ -166,6 +243,10 @@ impl Structure
         "go" => Procedure::go(self),
         "sleep" => Procedure::sleep(self, &parameters),
         "exit" => Procedure::exit(self, &parameters),
+        "retry" => Procedure::retry(),
+        "assert" => Procedure::assert(self, &parameters),
+        "send" => Procedure::send(self, &parameters),
+        "close" => Procedure::close(self, &parameters),
         // -----------------------------------------------------------------------------------------
         _ => 
         { // Если не найдено совпадений среди стандартных процедур,
@@ -176,61 +257,15 @@ impl Structure
             Some(calledStructureLink) => 
             {
               // 1. Вычисляем значения переданных аргументов в контексте вызывающей стороны;
-              // Они здесь точно есть, но в Some мы оборачиваем чтобы не делать clone ниже при take.
-              let mut parametersValues: Vec<Option<Token>> = parameters
+              let parametersValues: Vec<Token> = parameters
                 .getAllExpressions(self)
-                .unwrap_or_default()
-                .into_iter()
-                .map(Some)
-                .collect();
-
-              // 2. Присваиваем значения параметрам (дочерним структурам) вызываемой функции
-              // todo Они же потом не удаляются? Вообще по логике должна быть копия структуры,
-              //  если он используется как метод? и там создание этого?
-              {
-                let calledStructure: RwLockReadGuard<Structure> = calledStructureLink.read().unwrap();
-                
-                let mut calledStructureStructuresLink: RwLockWriteGuard<Option< Vec< Arc<RwLock<Structure>> > >> = 
-                  calledStructure.structures.write().unwrap();
-                
-                if let Some(calledStructureStructures) = calledStructureStructuresLink.deref_mut()
-                {
-                  for (idx, calledStructureStructureLink) in calledStructureStructures.iter_mut().enumerate() 
-                  {
-                    if idx < parametersValues.len() 
-                    { // Проходит по количеству параметров, потому что первые структуры - это параметры.
-                      let mut calledStructureStructure: RwLockWriteGuard<Structure> = 
-                        calledStructureStructureLink.write().unwrap();
-
-                      // Забираем токен один раз
-                      let mut token: Token = parametersValues[idx].take().unwrap(); // Здесь токен еще точно есть
-                      
-                      // Нормализируем под тип параметра
-                      // todo:
-                      //  Кстати не должен ли getAllExpressions сам делать приведение?
-                      //  Много таких мест в коде с params.
-                      Structure::normalizeToken(
-                        &mut token, 
-                        calledStructureStructure.dataType.clone()
-                      );
-                      
-                      // Устанавливаем lines параметра как линию с одним токеном – переданным значением
-                      calledStructureStructure.lines = Some(vec![
-                        Arc::new(RwLock::new(Line {
-                          tokens: Some(vec![token]),
-                          indent: None,
-                          lines: None,
-                          parent: None,
-                        }))
-                      ]);
-                    }
-                  }
-                  //
-                }
-              }
-
-              // 3. Запускаем исполнение тела функции
-              readLines(calledStructureLink);
+                .unwrap_or_default();
+
+              // 2. Читаем тело метода с этими значениями параметров;
+              //    Метод, который уже читает другой вызов (рекурсия или другой поток), запускается в своём кадре,
+              //    иначе вызовы делили бы параметры, результат и указатель линий
+              let result: Option<Token> = callMethod(&calledStructureLink, parametersValues);
+              return result;
             }
           }
         }
@@ -238,6 +273,47 @@ impl Structure
       }
       // Всё успешно, это была стандартная процедура
     } // Если название структуры не в нижнем регистре
+    None
+  }
+
+  // ===============================================================================================
+
+  /// Присваивает значения параметрам структуры-метода;
+  /// Параметры - это первые вложенные структуры, по порядку объявления
+  pub fn setParameters(&self, values: Vec<Token>) -> ()
+  {
+    let mut values: Vec<Option<Token>> = values.into_iter().map(Some).collect();
+    let structures: RwLockReadGuard<Structures> = self.structures.read().unwrap();
+
+    for (idx, structureLink) in structures.iter().enumerate() 
+    {
+      if idx < values.len() 
+      { // Проходит по количеству параметров, потому что первые структуры - это параметры.
+        let mut structure: RwLockWriteGuard<Structure> = structureLink.write().unwrap();
+
+        // Забираем токен один раз
+        let mut token: Token = values[idx].take().unwrap(); // Здесь токен еще точно есть
+        
+        // Нормализируем под тип параметра
+        // todo:
+        //  Кстати не должен ли getAllExpressions сам делать приведение?
+        //  Много таких мест в коде с params.
+        Structure::normalizeToken(
+          &mut token, 
+          structure.dataType.clone()
+        );
+        
+        // Устанавливаем lines параметра как линию с одним токеном – переданным значением
+        structure.lines = Some(vec![
+          Arc::new(RwLock::new(Line {
+            tokens: Some(vec![token]),
+            indent: None,
+            lines: None,
+            parent: None,
+          }))
+        ]);
+      }
+    }
   }
 
   // ===============================================================================================
@@ -0,0 +1,266 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStderr, ChildStdout, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use crate::parser::budget::waitSlice;
+use crate::tokenizer::types::token::Token;
+use crate::tokenizer::types::tokenType::TokenType;
+// =================================================================================================
+
+// Запуск процессов;
+// process(command, args...) запускает программу с аргументами как есть, без разбора строки на слова,
+// ждёт её завершения и возвращает запись с полями code, stdout, stderr и signal.
+// Каждый параметр после команды это один аргумент, а список передаёт все свои элементы.
+//
+// Настройки передаются именованными параметрами:
+//   - cwd = "path"           каталог запуска;
+//   - env = "NAME=value"     дополнительная переменная окружения, либо их список; можно повторять;
+//   - input = "text"         текст, который получит stdin;
+//   - timeout = ms           через сколько миллисекунд процесс будет остановлен;
+//   - onLine = method        метод, который получает строки stdout по мере их появления.
+//
+// code - код завершения, либо None, если процесс остановлен сигналом;
+// signal - номер сигнала, который остановил процесс, либо None.
+// Если процесс не удалось запустить, то это непредвиденное поведение `exec`.
+// exit() и время бюджета (--timeout) останавливают процесс так же, как timeout.
+
+// =================================================================================================
+
+/// Настройки запуска процесса
+#[derive(Clone, Default)]
+pub(crate) struct ProcessOptions
+{
+  /// Каталог запуска; None - текущий каталог
+  pub cwd: Option<PathBuf>,
+  /// Дополнительные переменные окружения
+  pub env: Vec<(String, String)>,
+  /// Текст для stdin; None - stdin закрыт
+  pub input: Option<String>,
+  /// Время на выполнение; None - без ограничения
+  pub timeout: Option<Duration>
+}
+
+/// Итог выполнения процесса
+pub(crate) struct ProcessOutput
+{
+  status: ExitStatus,
+  stdout: String,
+  stderr: String
+}
+
+impl ProcessOutput
+{
+  /// Получает запись с полями code, stdout, stderr и signal
+  pub(crate) fn toRecord(self) -> Token
+  {
+    let code: Token = match self.status.code()
+    {
+      None => Token::newEmpty(TokenType::None),
+      Some(code) => Token::new(TokenType::Int, code.to_string())
+    };
+    Token::newRecord(vec![
+      (String::from("code"),   code),
+      (String::from("stdout"), Token::new(TokenType::String, self.stdout)),
+      (String::from("stderr"), Token::new(TokenType::String, self.stderr)),
+      (String::from("signal"), getSignal(&self.status))
+    ])
+  }
+}
+
+/// Получает номер сигнала, который остановил процесс
+#[cfg(unix)]
+fn getSignal(status: &ExitStatus) -> Token
+{
+  use std::os::unix::process::ExitStatusExt;
+  match status.signal()
+  {
+    None => Token::newEmpty(TokenType::None),
+    Some(signal) => Token::new(TokenType::UInt, signal.to_string())
+  }
+}
+
+/// Получает номер сигнала, который остановил процесс; Вне unix сигналов нет
+#[cfg(not(unix))]
+fn getSignal(_status: &ExitStatus) -> Token
+{
+  Token::newEmpty(TokenType::None)
+}
+
+// =================================================================================================
+
+/// Останавливает процесс, если он не завершился за отведённое время;
+/// Отправка в канал или его закрытие отменяет остановку
+fn watchTimeout(child: Arc< Mutex<Child> >, timeout: Duration) -> Sender<()>
+{
+  let (sender, receiver): (Sender<()>, Receiver<()>) = mpsc::channel();
+  thread::spawn(move ||
+  {
+    match receiver.recv_timeout(timeout)
+    {
+      Err(RecvTimeoutError::Timeout) => { let _ = child.lock().unwrap().kill(); }
+      _ => {}
+    }
+  });
+  sender
+}
+
+/// Читает поток вывода целиком в отдельном потоке
+fn readAll(mut stream: impl Read + Send + 'static) -> JoinHandle<String>
+{
+  thread::spawn(move ||
+  {
+    let mut bytes: Vec<u8> = Vec::new();
+    let _ = stream.read_to_end(&mut bytes);
+    String::from_utf8_lossy(&bytes).to_string()
+  })
+}
+
+/// Читает поток вывода по строкам в отдельном потоке и передаёт их в канал
+fn readChunks<R: Read + Send + 'static>(stream: R) -> Receiver< Vec<u8> >
+{
+  let (sender, receiver): (Sender< Vec<u8> >, Receiver< Vec<u8> >) = mpsc::channel();
+  thread::spawn(move ||
+  {
+    let mut reader: BufReader<R> = BufReader::new(stream);
+    let mut line: Vec<u8> = Vec::new();
+    while reader.read_until(b'\n', &mut line).unwrap_or(0) != 0
+    {
+      match sender.send( std::mem::take(&mut line) )
+      {
+        Ok(_)  => {}
+        Err(_) => break
+      }
+    }
+  });
+  receiver
+}
+
+/// Останавливает процесс, если программа завершается или вышло время бюджета;
+/// Возвращает сколько можно ждать до следующей проверки, None - если процесс остановлен
+fn waitOrKill(child: &Mutex<Child>) -> Option<Duration>
+{
+  let wait: Option<Duration> = waitSlice("process", None);
+  match wait
+  { Some(_) => {} None =>
+  {
+    let _ = child.lock().unwrap().kill();
+  }}
+  wait
+}
+
+/// Читает stdout; Если есть onLine, то каждая строка передаётся ему по мере появления.
+/// Если процесс остановлен из-за завершения программы, то вывод его дочерних процессов не ждём
+fn readStdout(stdout: ChildStdout, child: &Mutex<Child>, mut onLine: Option<&mut dyn FnMut(String)>) -> String
+{
+  let mut output: String = String::new();
+  let chunks: Receiver< Vec<u8> > = readChunks(stdout);
+  while let Some(wait) = waitOrKill(child)
+  {
+    match chunks.recv_timeout(wait)
+    {
+      Ok(line) =>
+      {
+        let text: String = String::from_utf8_lossy(&line).to_string();
+        output.push_str(&text);
+        match &mut onLine
+        { None => {} Some(onLine) =>
+        {
+          onLine( text.trim_end_matches(['\n', '\r']).to_string() );
+        }}
+      }
+      Err(RecvTimeoutError::Timeout) => {}
+      Err(RecvTimeoutError::Disconnected) => break
+    }
+  }
+  output
+}
+
+/// Получает stderr, прочитанный в отдельном потоке;
+/// Если программа завершается, то вывод его дочерних процессов не ждём
+fn joinStderr(stderr: JoinHandle<String>, child: &Mutex<Child>) -> String
+{
+  while !stderr.is_finished()
+  {
+    match waitOrKill(child)
+    {
+      Some(wait) => thread::sleep(wait),
+      None => return String::new()
+    }
+  }
+  stderr.join().unwrap_or_default()
+}
+
+/// Ждёт завершения процесса, не занимая его надолго, чтобы его можно было остановить по времени;
+/// exit() и время бюджета тоже останавливают процесс
+fn waitChild(child: &Mutex<Child>) -> std::io::Result<ExitStatus>
+{
+  loop
+  {
+    match child.lock().unwrap().try_wait()?
+    {
+      Some(status) => return Ok(status),
+      None => {}
+    }
+    thread::sleep( waitOrKill(child).map_or(Duration::from_millis(1), |wait: Duration| wait.min(Duration::from_millis(1))) );
+  }
+}
+
+/// Запускает процесс и ждёт его завершения
+pub(crate) fn runProcess(
+  command: &str,
+  args: &[String],
+  options: ProcessOptions,
+  onLine: Option<&mut dyn FnMut(String)>
+) -> Result<ProcessOutput, String>
+{
+  let mut builder: Command = Command::new(command);
+  builder
+    .args(args)
+    .envs(options.env)
+    .stdin( match options.input { None => Stdio::null(), Some(_) => Stdio::piped() } )
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+  match options.cwd
+  { None => {} Some(cwd) =>
+  {
+    builder.current_dir(cwd);
+  }}
+
+  let mut child: Child = builder.spawn().map_err(|error| error.to_string())?;
+  match (child.stdin.take(), options.input)
+  {
+    (Some(mut stdin), Some(input)) =>
+    { // Запись идёт отдельно, иначе большой ввод и вывод могут ждать друг друга
+      thread::spawn(move || { let _ = stdin.write_all(input.as_bytes()); });
+    }
+    _ => {}
+  }
+  let stdout: Option<ChildStdout> = child.stdout.take();
+  let stderr: Option<JoinHandle<String>> = child.stderr.take().map(|stderr: ChildStderr| readAll(stderr));
+
+  let child: Arc< Mutex<Child> > = Arc::new(Mutex::new(child));
+  let watcher: Option< Sender<()> > = options.timeout
+    .map(|timeout: Duration| watchTimeout(child.clone(), timeout));
+
+  let stdout: String = stdout
+    .map(|stdout: ChildStdout| readStdout(stdout, &child, onLine))
+    .unwrap_or_default();
+  let status: std::io::Result<ExitStatus> = waitChild(&child);
+  match watcher
+  { None => {} Some(watcher) =>
+  {
+    let _ = watcher.send(());
+  }}
+
+  Ok(ProcessOutput
+  {
+    status: status.map_err(|error| error.to_string())?,
+    stdout,
+    stderr: stderr.map(|stderr: JoinHandle<String>| joinStderr(stderr, &child)).unwrap_or_default()
+  })
+}
+
+// =================================================================================================
@@ -0,0 +1,82 @@
+use std::sync::Mutex;
+#[cfg(not(target_family = "wasm"))]
+use std::sync::MutexGuard;
+#[cfg(not(target_family = "wasm"))]
+use rand::{rngs::StdRng, SeedableRng};
+use crate::parser::runtime::{withRuntime, Runtime};
+// =================================================================================================
+
+// Случайные числа;
+// У каждой среды выполнения свой генератор: randUInt, randInt, randFloat, randBool,
+// choice(list) и shuffle(list) берут значения из него.
+//
+// seed(n) перезапускает генератор с зерном n, после чего значения повторяются от запуска к запуску.
+// Зерно можно задать и до запуска: `rts run --seed 42 file.rt` или RTS::setSeed;
+// Без зерна генератор получает случайное зерно от системы при первом обращении.
+// Потоки одной среды берут значения из общего генератора, поэтому их порядок зависит от потоков.
+
+// =================================================================================================
+
+/// Генератор случайных чисел одной среды выполнения
+#[derive(Default)]
+pub(crate) struct RandomState
+{
+  /// Зерно из настроек среды; None - случайное зерно
+  seed: Mutex< Option<u64> >,
+  /// Генератор; Создаётся при первом обращении
+  #[cfg(not(target_family = "wasm"))]
+  rng: Mutex< Option<StdRng> >
+}
+
+impl RandomState
+{
+  /// Забывает генератор; Следующее обращение начнёт его заново с зерна из настроек
+  pub(crate) fn clear(&self) -> ()
+  {
+    #[cfg(not(target_family = "wasm"))]
+    {
+      *self.rng.lock().unwrap() = None;
+    }
+  }
+}
+
+// =================================================================================================
+
+impl Runtime
+{
+  /// Ставит зерно генератора случайных чисел; None - случайное зерно от системы
+  pub fn setSeed(&self, seed: Option<u64>) -> ()
+  {
+    *self.random.seed.lock().unwrap() = seed;
+    self.random.clear();
+  }
+}
+
+/// Перезапускает генератор текущей среды с зерном; Вызывается из seed(n)
+#[cfg(not(target_family = "wasm"))]
+pub(crate) fn reseed(seed: u64) -> ()
+{
+  withRuntime(|runtime: &Runtime|
+    *runtime.random.rng.lock().unwrap() = Some( StdRng::seed_from_u64(seed) )
+  );
+}
+
+/// Выполняет действие с генератором текущей среды
+#[cfg(not(target_family = "wasm"))]
+pub(crate) fn withRng<R>(action: impl FnOnce(&mut StdRng) -> R) -> R
+{
+  withRuntime(|runtime: &Runtime|
+  {
+    let mut rng: MutexGuard< Option<StdRng> > = runtime.random.rng.lock().unwrap();
+    let seed: Option<u64> = *runtime.random.seed.lock().unwrap();
+    action( rng.get_or_insert_with(||
+      match seed
+      {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng()
+      }
+    ))
+  })
+}
+
+// =================================================================================================
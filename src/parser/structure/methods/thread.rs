@@ -0,0 +1,328 @@
+use std::collections::{HashMap, HashSet};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard};
+use std::thread::{self, JoinHandle};
+use crate::parser::parser::readLines;
+use crate::parser::budget::waitSlice;
+use crate::parser::runtime::{enterRuntime, readStackSize, runtime, withRuntime, Runtime, RuntimeGuard};
+use crate::parser::structure::structure::Structure;
+use crate::tokenizer::types::token::Token;
+// =================================================================================================
+
+// Потоки;
+// spawn(method, args...) запускает структуру-метод в новом потоке ОС и возвращает номер потока,
+// join(handle) ждёт его завершения и возвращает результат, alive(handle) проверяет работу.
+//
+// Каждый поток работает в своей копии структуры-метода (кадре вызова),
+// поэтому параметры, результат и указатель линий у разных потоков не пересекаются.
+// Обычный вызов метода тоже получает кадр, если этот метод уже читает другой вызов
+// (рекурсия или другой поток), см. claimStructure.
+// Глобальный exit() останавливает все потоки, т.к. все они читают линии через readLines.
+// Поток работает в той же среде выполнения (Runtime), что и поток, который его запустил.
+//
+// Паника потока не завершает процесс: она перехватывается и уходит в join
+// как непредвиденное поведение `thread`. Блокировки структур, которые поток держал в момент паники,
+// освобождаются от отравления, чтобы остальные потоки продолжили с ними работать.
+
+// =================================================================================================
+
+/// Итог работы потока: результат метода, либо описание паники
+type ThreadResult = Result< Option<Token>, String >;
+
+/// Поток и его итог, если к нему уже был join
+struct ThreadEntry
+{
+  handle: Option< JoinHandle<ThreadResult> >,
+  result: Option<ThreadResult>
+}
+
+/// Потоки одной среды выполнения
+#[derive(Default)]
+pub(crate) struct ThreadState
+{
+  /// Запущенные потоки по их номеру
+  threads: Mutex< HashMap<usize, ThreadEntry> >,
+  /// Структуры-методы, которые сейчас читаются без кадра, по адресу
+  reading: Mutex< HashSet<usize> >
+}
+
+impl ThreadState
+{
+  /// Забывает все потоки; Работающие потоки не останавливаются
+  pub(crate) fn clear(&self) -> ()
+  {
+    self.threads.lock().unwrap().clear();
+    self.reading.lock().unwrap().clear();
+  }
+}
+
+/// Занимает структуру-метод, чтобы вызов читал её саму, без кадра;
+/// false - её уже читает другой вызов (рекурсия или другой поток), тогда вызову нужен свой кадр,
+/// иначе вызовы делили бы параметры, результат и указатель линий
+pub(crate) fn claimStructure(structureLink: &Arc<RwLock<Structure>>) -> bool
+{
+  let address: usize = Arc::as_ptr(structureLink) as usize;
+  withRuntime(|runtime: &Runtime| runtime.threads.reading.lock().unwrap().insert(address))
+}
+
+/// Освобождает структуру-метод после claimStructure
+pub(crate) fn releaseStructure(structureLink: &Arc<RwLock<Structure>>) -> ()
+{
+  let address: usize = Arc::as_ptr(structureLink) as usize;
+  withRuntime(|runtime: &Runtime| runtime.threads.reading.lock().unwrap().remove(&address));
+}
+
+// =================================================================================================
+
+/// Создаёт отдельный кадр вызова структуры-метода;
+/// Вложенные структуры (параметры и прошлые объявления) копируются
+pub(crate) fn newFrame(method: &Structure) -> Structure
+{
+  let mut frame: Structure = Structure::new(
+    method.name.clone(),
+    method.mutable.clone(),
+    method.dataType.clone(),
+    method.lines.clone(),
+    method.parent.clone()
+  );
+  frame.result = method.result.clone();
+  for childLink in method.structures.read().unwrap().iter()
+  {
+    let child: Structure = childLink.read().unwrap().clone();
+    frame.pushStructure( Arc::new(RwLock::new(child)) );
+  }
+  frame
+}
+
+/// Читает структуру-метод со значениями параметров и возвращает её результат;
+/// Метод, который уже читает другой вызов (рекурсия, поток или таймер), читается в своём кадре
+pub(crate) fn callMethod(methodLink: &Arc<RwLock<Structure>>, parameters: Vec<Token>) -> Option<Token>
+{
+  let claimed: bool = claimStructure(methodLink);
+  let frameLink: Arc<RwLock<Structure>> = match claimed
+  {
+    true  => methodLink.clone(),
+    false => Arc::new(RwLock::new( newFrame(&methodLink.read().unwrap()) ))
+  };
+  // todo Они же потом не удаляются? Вообще по логике должна быть копия структуры,
+  //  если он используется как метод? и там создание этого?
+  frameLink.read().unwrap()
+    .setParameters(parameters);
+  readLines(frameLink.clone());
+  let result: Option<Token> = frameLink.read().unwrap().result.clone();
+  match claimed
+  { false => {} true =>
+  {
+    releaseStructure(methodLink);
+  }}
+  result
+}
+
+/// Снимает отравление с блокировок структуры, её вложенных структур и её родителей;
+/// Вызывается после паники потока, который мог держать эти блокировки на запись
+fn recoverLocks(structureLink: &Arc<RwLock<Structure>>, visited: &mut HashSet<usize>) -> ()
+{
+  match visited.insert(Arc::as_ptr(structureLink) as usize)
+  {
+    false => return,
+    true  => {}
+  }
+  structureLink.clear_poison();
+  let (children, parent): (Vec< Arc<RwLock<Structure>> >, Option< Arc<RwLock<Structure>> >) =
+  {
+    let structure: RwLockReadGuard<Structure> = structureLink.read().unwrap_or_else(PoisonError::into_inner);
+    structure.structures.clear_poison();
+    structure.reactions.clear_poison();
+    let children: Vec< Arc<RwLock<Structure>> > =
+      structure.structures.read().unwrap_or_else(PoisonError::into_inner).iter().cloned().collect();
+    (children, structure.parent.clone())
+  };
+  for childLink in &children
+  {
+    recoverLocks(childLink, visited);
+  }
+  match &parent
+  { None => {} Some(parentLink) =>
+  {
+    recoverLocks(parentLink, visited);
+  }}
+}
+
+/// Получает описание паники потока
+fn getPanicMessage(payload: Box<dyn std::any::Any + Send>) -> String
+{
+  match payload.downcast::<String>()
+  {
+    Ok(message) => *message,
+    Err(payload) => match payload.downcast::<&str>()
+    {
+      Ok(message) => message.to_string(),
+      Err(_) => String::from("Thread panicked")
+    }
+  }
+}
+
+// =================================================================================================
+
+/// Запускает структуру-метод в новом потоке со значениями параметров;
+/// Возвращает номер потока, либо 0, если система не дала поток
+pub fn spawnThread(methodLink: &Arc<RwLock<Structure>>, parameters: Vec<Token>) -> usize
+{
+  let frameLink: Arc<RwLock<Structure>> =
+  {
+    let method: RwLockReadGuard<Structure> = methodLink.read().unwrap();
+    let frame: Structure = newFrame(&method);
+    frame.setParameters(parameters);
+    Arc::new(RwLock::new(frame))
+  };
+
+  let runtime: Arc<Runtime> = runtime();
+  let threadRuntime: Arc<Runtime> = runtime.clone();
+  let spawned: std::io::Result< JoinHandle<ThreadResult> > = thread::Builder::new().stack_size(readStackSize).spawn(move ||
+  {
+    let _guard: RuntimeGuard = enterRuntime(threadRuntime.clone());
+    // Паника потока не должна останавливать другие потоки, она уходит в join
+    catch_unwind(AssertUnwindSafe(||
+    {
+      readLines(frameLink.clone());
+      frameLink.read().unwrap().result.clone()
+    }))
+    .map_err(|payload: Box<dyn std::any::Any + Send>|
+    {
+      let mut visited: HashSet<usize> = HashSet::new();
+      recoverLocks(&frameLink, &mut visited);
+      recoverLocks(&threadRuntime.getMainStructure(), &mut visited);
+      getPanicMessage(payload)
+    })
+  });
+  // Если система не дала поток, то номера нет; join(0) вернёт None
+  let handle: JoinHandle<ThreadResult> = match spawned
+  {
+    Ok(handle) => handle,
+    Err(_) => return 0
+  };
+
+  let mut threads: MutexGuard< HashMap<usize, ThreadEntry> > = runtime.threads.threads.lock().unwrap();
+  let id: usize = threads.len()+1;
+  threads.insert(id, ThreadEntry { handle: Some(handle), result: None });
+  id
+}
+
+/// Ждёт завершения потока и возвращает его итог;
+/// Повторный join вернёт тот же итог, None - если такого потока нет, либо программа завершается
+pub fn joinThread(id: usize) -> Option<ThreadResult>
+{
+  let runtime: Arc<Runtime> = runtime();
+  // Блокировку не держим, пока ждём поток, т.к. он тоже может запускать потоки
+  let handle: Option< JoinHandle<ThreadResult> > =
+  {
+    let mut threads: MutexGuard< HashMap<usize, ThreadEntry> > = runtime.threads.threads.lock().unwrap();
+    threads.get_mut(&id)?.handle.take()
+  };
+  match handle
+  { None => {} Some(handle) =>
+  {
+    // Ждём шагами, чтобы exit() и время бюджета прерывали ожидание;
+    // Тогда поток остаётся в списке, и join к нему можно повторить
+    while !handle.is_finished()
+    {
+      match waitSlice("join", None)
+      {
+        Some(wait) => thread::sleep(wait),
+        None =>
+        {
+          runtime.threads.threads.lock().unwrap()
+            .get_mut(&id)?.handle = Some(handle);
+          return None;
+        }
+      }
+    }
+    let result: ThreadResult = handle.join()
+      .unwrap_or_else(|payload| Err(getPanicMessage(payload)));
+    runtime.threads.threads.lock().unwrap()
+      .get_mut(&id)?.result = Some(result);
+  }}
+  let result: Option<ThreadResult> = runtime.threads.threads.lock().unwrap()
+    .get(&id)?.result.clone();
+  result
+}
+
+/// Проверяет, работает ли ещё поток
+pub fn isThreadAlive(id: usize) -> bool
+{
+  withRuntime(|runtime: &Runtime|
+    match runtime.threads.threads.lock().unwrap().get(&id)
+    {
+      None => false,
+      Some(entry) => match &entry.handle
+      {
+        None => false,
+        Some(handle) => !handle.is_finished()
+      }
+    }
+  )
+}
+
+/// Проверяет, остались ли работающие потоки
+pub fn hasAliveThreads() -> bool
+{
+  withRuntime(|runtime: &Runtime|
+    runtime.threads.threads.lock().unwrap().values()
+      .any(|entry: &ThreadEntry| entry.handle.as_ref().is_some_and(|handle: &JoinHandle<ThreadResult>| !handle.is_finished()))
+  )
+}
+
+// =================================================================================================
+
+// =================================================================================================
+
+#[cfg(test)]
+mod tests
+{
+  use std::collections::HashSet;
+  use std::panic::{catch_unwind, AssertUnwindSafe};
+  use std::sync::{Arc, RwLock, RwLockWriteGuard};
+  use std::thread;
+  use crate::parser::structure::methods::thread::recoverLocks;
+  use crate::parser::structure::structure::{Structure, StructureMut};
+  use crate::parser::structure::structureType::StructureType;
+  // ===============================================================================================
+
+  /// Вспомогательная функция:
+  /// Создаёт пустую структуру с родителем
+  fn newStructure(name: &str, parent: Option< Arc<RwLock<Structure>> >) -> Arc<RwLock<Structure>>
+  {
+    Arc::new(RwLock::new(Structure::new(
+      Some(String::from(name)),
+      StructureMut::Variable,
+      StructureType::None,
+      None,
+      parent
+    )))
+  }
+
+  /// Проверяет, что после паники потока, который держал структуры на запись,
+  /// эти структуры снова можно читать и изменять
+  #[test]
+  fn panicLocks() -> ()
+  {
+    let mainLink: Arc<RwLock<Structure>> = newStructure("main", None);
+    let childLink: Arc<RwLock<Structure>> = newStructure("child", Some(mainLink.clone()));
+    mainLink.read().unwrap().pushStructure(childLink.clone());
+    let frameLink: Arc<RwLock<Structure>> = newStructure("frame", Some(mainLink.clone()));
+
+    let poisoned: Arc<RwLock<Structure>> = childLink.clone();
+    let _ = thread::spawn(move ||
+      catch_unwind(AssertUnwindSafe(||
+      {
+        let _structure: RwLockWriteGuard<Structure> = poisoned.write().unwrap();
+        panic!("thread failed");
+      }))
+    ).join();
+    assert!(childLink.is_poisoned());
+
+    recoverLocks(&frameLink, &mut HashSet::new());
+    assert!(!childLink.is_poisoned());
+    assert!(childLink.write().is_ok());
+  }
+}
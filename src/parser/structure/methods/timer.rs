@@ -0,0 +1,325 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::parser::budget::{checkTime, waitSlice};
+use crate::parser::runtime::{runtime, withRuntime, Runtime};
+use crate::parser::structure::structure::Structure;
+use crate::parser::structure::methods::thread::{callMethod, hasAliveThreads};
+use crate::tokenizer::types::token::Token;
+// =================================================================================================
+
+// Таймеры и цикл событий;
+// after(ms, method, args...) вызывает метод один раз через ms миллисекунд,
+// every(ms, method, args...) вызывает его каждые ms миллисекунд, cancel(timer) отменяет таймер.
+//
+// Методы таймеров выполняются только в основном потоке (в котором запущен Runtime):
+//   - пока основной поток ждёт в sleep();
+//   - после чтения всего файла, пока остаются таймеры или работающие потоки.
+// Метод, который ждёт в sleep() и сам вызывается таймером, читается в отдельном кадре.
+//
+// Время считается по монотонным часам (Instant). Повторяющийся таймер планируется
+// от прошлого запланированного времени, а не от фактического, поэтому задержки не накапливаются;
+// Пропущенные из-за долгого метода срабатывания не догоняются.
+// Для каждого таймера считается drift - опоздание срабатывания относительно плана,
+// и jitter - разброс drift между соседними срабатываниями.
+// Замеры хранятся для ожидающих таймеров и для finishedStatsLimit последних отработавших.
+
+// =================================================================================================
+
+/// Наименьший шаг ожидания цикла событий, когда остались только потоки
+const threadPollInterval: Duration = Duration::from_millis(1);
+/// Сколько замеров отработавших и отменённых таймеров хранится для drift() и debug отчёта
+const finishedStatsLimit: usize = 64;
+
+/// Таймер
+struct Timer
+{
+  /// Структура-метод, которую вызывает таймер
+  method: Arc<RwLock<Structure>>,
+  /// Значения параметров метода
+  parameters: Vec<Token>,
+  /// Интервал повтора; None для однократного таймера
+  interval: Option<Duration>,
+  /// Запланированное время срабатывания
+  deadline: Instant
+}
+
+/// Замеры точности срабатываний таймера
+#[derive(Clone, Default)]
+struct TimerStats
+{
+  /// Количество срабатываний
+  fired: usize,
+  /// Опоздание последнего срабатывания
+  lastDrift: Duration,
+  /// Наибольшее опоздание
+  maxDrift: Duration,
+  /// Наибольший разброс опоздания между соседними срабатываниями
+  maxJitter: Duration
+}
+
+/// Таймеры одной среды выполнения
+#[derive(Default)]
+pub(crate) struct TimerState
+{
+  /// Все таймеры по номеру; Отменённые и отработавшие таймеры отсюда удаляются
+  timers: Mutex< BTreeMap<usize, Timer> >,
+  /// Замеры таймеров по номеру: ожидающих и последних отработавших
+  stats: Mutex< BTreeMap<usize, TimerStats> >,
+  /// Отработавшие и отменённые таймеры в порядке завершения; Их замеры удаляются сверх finishedStatsLimit
+  finished: Mutex< VecDeque<usize> >,
+  /// Повторяющиеся таймеры, метод которых сейчас выполняется
+  firing: Mutex< BTreeSet<usize> >,
+  /// Номер последнего созданного таймера; Номера не используются повторно
+  lastId: AtomicUsize
+}
+
+impl TimerState
+{
+  /// Удаляет все таймеры и замеры
+  pub(crate) fn clear(&self) -> ()
+  {
+    self.timers.lock().unwrap().clear();
+    self.stats.lock().unwrap().clear();
+    self.finished.lock().unwrap().clear();
+    self.firing.lock().unwrap().clear();
+  }
+}
+
+// =================================================================================================
+
+/// Создаёт таймер и возвращает его номер
+pub fn newTimer(
+  method:     Arc<RwLock<Structure>>,
+  parameters: Vec<Token>,
+  delay:      Duration,
+  repeat:     bool
+) -> usize
+{
+  let runtime: Arc<Runtime> = runtime();
+  let id: usize = runtime.timers.lastId.fetch_add(1, Ordering::SeqCst)+1;
+  runtime.timers.stats.lock().unwrap().insert(id, TimerStats::default());
+  runtime.timers.timers.lock().unwrap().insert(id, Timer {
+    method,
+    parameters,
+    interval: match repeat
+    {
+      // Нулевой интервал занял бы весь цикл событий
+      true  => Some( delay.max(Duration::from_millis(1)) ),
+      false => None
+    },
+    deadline: Instant::now()+delay
+  });
+  id
+}
+
+/// Запоминает, что таймер больше не сработает;
+/// Замеры самых старых отработавших таймеров сверх finishedStatsLimit удаляются
+fn finishTimer(runtime: &Runtime, id: usize) -> ()
+{
+  let mut finished: MutexGuard< VecDeque<usize> > = runtime.timers.finished.lock().unwrap();
+  finished.push_back(id);
+  while finished.len() > finishedStatsLimit
+  {
+    match finished.pop_front()
+    { None => {} Some(oldId) =>
+    {
+      runtime.timers.stats.lock().unwrap().remove(&oldId);
+    }}
+  }
+}
+
+/// Отменяет таймер; Возвращает false, если таймер уже отработал или был отменён
+pub fn cancelTimer(id: usize) -> bool
+{
+  withRuntime(|runtime: &Runtime|
+  {
+    let waiting: bool = runtime.timers.timers.lock().unwrap().remove(&id).is_some();
+    let firing:  bool = runtime.timers.firing.lock().unwrap().remove(&id);
+    // Повторяющийся таймер, который сейчас выполняется, завершится сам после своего метода
+    match waiting
+    {
+      false => {}
+      true  => finishTimer(runtime, id)
+    }
+    waiting || firing
+  })
+}
+
+/// Получает опоздание последнего срабатывания таймера
+pub fn getTimerDrift(id: usize) -> Option<Duration>
+{
+  withRuntime(|runtime: &Runtime|
+    runtime.timers.stats.lock().unwrap().get(&id).map(|stats: &TimerStats| stats.lastDrift)
+  )
+}
+
+// =================================================================================================
+
+/// Получает время срабатывания ближайшего таймера
+fn nextDeadline(runtime: &Runtime) -> Option<Instant>
+{
+  runtime.timers.timers.lock().unwrap().values()
+    .map(|timer: &Timer| timer.deadline)
+    .min()
+}
+
+/// Вызывает метод таймера и записывает точность срабатывания
+fn fireTimer(runtime: &Runtime, id: usize, now: Instant) -> ()
+{
+  // Таймер забирается из списка на время вызова, т.к. метод может создавать и отменять таймеры
+  let timer: Timer = match runtime.timers.timers.lock().unwrap().remove(&id)
+  {
+    None => return,
+    Some(timer) => timer
+  };
+
+  let drift: Duration = now.saturating_duration_since(timer.deadline);
+  match runtime.timers.stats.lock().unwrap().get_mut(&id)
+  { None => {} Some(stats) =>
+  {
+    match stats.fired > 0
+    {
+      false => {}
+      true  =>
+      {
+        let jitter: Duration = match drift > stats.lastDrift
+        {
+          true  => drift-stats.lastDrift,
+          false => stats.lastDrift-drift
+        };
+        stats.maxJitter = stats.maxJitter.max(jitter);
+      }
+    }
+    stats.fired += 1;
+    stats.lastDrift = drift;
+    stats.maxDrift = stats.maxDrift.max(drift);
+  }}
+
+  match timer.interval.is_some()
+  {
+    true  => { runtime.timers.firing.lock().unwrap().insert(id); }
+    false => {}
+  }
+  // Метод может сейчас ждать в sleep(), тогда таймер читает его в своём кадре
+  callMethod(&timer.method, timer.parameters.clone());
+
+  match timer.interval
+  { None => finishTimer(runtime, id), Some(interval) =>
+  {
+    let mut deadline: Instant = timer.deadline+interval;
+    let now: Instant = Instant::now();
+    while deadline <= now
+    { // Пропущенные срабатывания не догоняем
+      deadline += interval;
+    }
+    // Метод мог отменить свой же таймер, тогда повтора нет
+    match runtime.timers.firing.lock().unwrap().remove(&id)
+    {
+      false => finishTimer(runtime, id),
+      true  => { runtime.timers.timers.lock().unwrap().insert(id, Timer { deadline, ..timer }); }
+    }
+  }}
+}
+
+/// Вызывает все таймеры, время которых уже пришло
+fn fireDueTimers(runtime: &Runtime) -> ()
+{
+  let now: Instant = Instant::now();
+  let due: Vec<usize> = runtime.timers.timers.lock().unwrap().iter()
+    .filter(|(_, timer)| timer.deadline <= now)
+    .map(|(id, _)| *id)
+    .collect();
+  for id in due
+  {
+    match runtime.isExit()
+    {
+      true  => return,
+      false => fireTimer(runtime, id, now)
+    }
+  }
+}
+
+// =================================================================================================
+
+/// Ждёт указанное время; В основном потоке вызывает таймеры во время ожидания
+pub fn waitEvents(duration: Duration) -> ()
+{
+  let runtime: Arc<Runtime> = runtime();
+  let end: Instant = Instant::now()+duration;
+  match runtime.isOwnerThread()
+  {
+    false =>
+    { // Вне основного потока таймеров нет, но exit() и время бюджета прерывают ожидание
+      while let Some(wait) = waitSlice("sleep", Some( end.saturating_duration_since(Instant::now()) ))
+      {
+        match wait.is_zero()
+        {
+          true  => break,
+          false => thread::sleep(wait)
+        }
+      }
+    }
+    true  =>
+      while !runtime.isExit()
+      {
+        fireDueTimers(&runtime);
+        let now: Instant = Instant::now();
+        match now >= end
+        {
+          true  => break,
+          false =>
+          {
+            let wait: Duration = nextDeadline(&runtime).map_or(end, |deadline: Instant| deadline.min(end)).saturating_duration_since(now);
+            thread::sleep( checkTime("sleep").map_or(wait, |left: Duration| wait.min(left)) );
+          }
+        }
+      }
+  }
+}
+
+/// Цикл событий после чтения файла;
+/// Работает, пока есть таймеры или работающие потоки
+pub fn runEventLoop() -> ()
+{
+  let runtime: Arc<Runtime> = runtime();
+  while !runtime.isExit()
+  {
+    fireDueTimers(&runtime);
+    let now: Instant = Instant::now();
+    let wait: Duration = match (nextDeadline(&runtime), hasAliveThreads())
+    {
+      (None, false) => break,
+      (None, true)  => threadPollInterval,
+      (Some(deadline), alive) =>
+      {
+        let wait: Duration = deadline.saturating_duration_since(now);
+        match alive
+        {
+          true  => wait.min(threadPollInterval),
+          false => wait
+        }
+      }
+    };
+    // Ожидание тоже ограничено временем бюджета
+    thread::sleep( checkTime("event loop").map_or(wait, |left: Duration| wait.min(left)) );
+  }
+}
+
+/// Выводит замеры точности всех сработавших таймеров
+pub fn timerReport() -> Vec<String>
+{
+  withRuntime(|runtime: &Runtime|
+    runtime.timers.stats.lock().unwrap().iter()
+      .filter(|(_, stats)| stats.fired > 0)
+      .map(|(id, stats)| format!(
+        "Timer [{}] fired [{}] max drift [{:?}] max jitter [{:?}]",
+        id, stats.fired, stats.maxDrift, stats.maxJitter
+      ))
+      .collect()
+  )
+}
+
+// =================================================================================================
@@ -1,5 +1,7 @@
 pub mod structure;
-mod tokenValue;
+pub mod structures;
+pub(crate) mod tokenValue;
+pub(crate) mod ir;
 pub mod structureType;
-mod methods;
+pub(crate) mod methods;
 pub mod ffi;
\ No newline at end of file
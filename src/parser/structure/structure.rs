@@ -1,10 +1,15 @@
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use crate::parser::bytes::Bytes;
 use crate::parser::structure::ffi::workerManager::callExternal;
 use crate::parser::structure::methods::parameters::{Parameters};
-use crate::parser::structure::structureType::{StructureType};
+use crate::parser::structure::structureType::{promoteStructureType, Overflow, StructureType};
+use crate::parser::structure::structures::{internPath, Name, Structures};
+use crate::parser::unexpected::{unexpected, Unexpected};
+use crate::parser::reactive::{propagate, Reaction};
 use crate::parser::structure::tokenValue::calculate::calculate;
-use crate::tokenizer::tokenizer::readTokens;
+use crate::parser::structure::tokenValue::precedence::{evaluateOperands, isExpressionOperator, separateExponentSigns};
+use crate::parser::structure::ir::{getFormatTemplate, getLineIr, Ir};
 use crate::tokenizer::types::line::Line;
 use crate::tokenizer::types::token::{Token};
 use crate::tokenizer::types::tokenType::{TokenType};
@@ -44,6 +49,94 @@ impl ToString for StructureMut
   }
 }
 
+/// Получает операцию составного оператора и её значение по умолчанию;
+/// `a += b` это `a = a + b`, а двойные операторы без правой части
+/// повторяют свою операцию с шагом: `a++` это `a + 1`, `a^^` это `a ^ 2`;
+/// `//` - это деление с округлением вниз: `a//` это `a // 2`
+fn getCompoundOperation(op: &TokenType) -> Option<( TokenType, Option<Token> )>
+{
+  let one = || Some( Token::new(TokenType::UInt, String::from("1")) );
+  let two = || Some( Token::new(TokenType::UInt, String::from("2")) );
+  match *op
+  {
+    TokenType::PlusEquals     => Some((TokenType::Plus, None)),
+    TokenType::MinusEquals    => Some((TokenType::Minus, None)),
+    TokenType::MultiplyEquals => Some((TokenType::Multiply, None)),
+    TokenType::DivideEquals   => Some((TokenType::Divide, None)),
+    TokenType::ModuloEquals   => Some((TokenType::Modulo, None)),
+    TokenType::ExponentEquals => Some((TokenType::Exponent, None)),
+    TokenType::UnaryPlus      => Some((TokenType::Plus, one())),
+    TokenType::UnaryMinus     => Some((TokenType::Minus, one())),
+    TokenType::UnaryMultiply  => Some((TokenType::Multiply, two())),
+    TokenType::UnaryDivide    => Some((TokenType::UnaryDivide, two())),
+    TokenType::UnaryModulo    => Some((TokenType::Modulo, two())),
+    TokenType::UnaryExponent  => Some((TokenType::Exponent, two())),
+    _ => None
+  }
+}
+
+/// Получает линии структуры для её значения;
+/// Список раскладывается по одному элементу на линию, как argv
+pub(crate) fn valueLines(tokens: Option< Vec<Token> >) -> Vec< Arc<RwLock<Line>> >
+{
+  let line = |tokens: Option< Vec<Token> >| -> Arc<RwLock<Line>>
+  {
+    Arc::new(RwLock::new(
+      Line
+      {
+        tokens,
+        indent: None,
+        lines:  None,
+        parent: None
+      }
+    ))
+  };
+  match tokens.as_deref()
+  {
+    Some([value]) if value.getList().is_some() =>
+      value.getList().unwrap().iter()
+        .map(|element: &Token| line( Some(vec![ element.clone() ]) ))
+        .collect(),
+    Some([value]) if value.getRecord().is_some() =>
+      value.getRecord().unwrap().into_iter()
+        .map(|(_, field): (String, &Token)| line( Some(vec![ field.clone() ]) ))
+        .collect(),
+    _ => vec![ line(tokens) ]
+  }
+}
+
+/// Добавляет структуре вложенные структуры для полей записи;
+/// После этого поля доступны по имени как `value.field`
+pub(crate) fn pushFields(structureLink: &Arc<RwLock<Structure>>, tokens: Option<&Vec<Token>>) -> ()
+{
+  let fields: Vec<(String, Token)> = match tokens.map(Vec::as_slice)
+  {
+    Some([value]) => match value.getRecord()
+    {
+      None => return,
+      Some(fields) => fields.into_iter()
+        .map(|(name, field): (String, &Token)| (name, field.clone()))
+        .collect()
+    },
+    _ => return
+  };
+
+  let structure: RwLockReadGuard<Structure> = structureLink.read().unwrap();
+  for (name, mut field) in fields
+  {
+    let dataType: StructureType = field.getStructureType();
+    structure.pushStructure( Arc::new(RwLock::new(
+      Structure::new(
+        Some(name),
+        StructureMut::Constant,
+        dataType,
+        Some( valueLines( Some(vec![ field ]) ) ),
+        Some( structureLink.clone() )
+      )
+    )));
+  }
+}
+
 // =================================================================================================
 
 /// Свободная структура данных
@@ -60,6 +153,9 @@ pub struct Structure
   /// Тип данных
   pub dataType: StructureType,
 
+  /// Поведение числа, которое не помещается в dataType
+  pub overflow: Overflow,
+
   /// Ссылки на вложенные линии
   pub lines: Option< Vec< Arc<RwLock<Line>> > >,
 
@@ -73,15 +169,22 @@ pub struct Structure
   pub result: Option<Token>,
 
   /// Ссылки на вложенные структуры
-  pub structures: Arc<RwLock< // Нужно, чтобы не мутировать методы и иметь доступ
-    Option< // Может не быть
-      Vec< Arc<RwLock<Structure>> > // Гибкий список вложенных структур
-    >
-  >>,
+  pub structures: Arc<RwLock<Structures>>, // Нужно, чтобы не мутировать методы и иметь доступ
 
   /// Ссылка на родителя
   pub parent: Option< Arc<RwLock<Structure>> >,
 
+  /// Реакции, которые запускаются при изменении структуры через structureOp
+  pub(crate) reactions: Arc<RwLock< Vec< Arc<Reaction> > >>,
+
+  /// Временная структура блока (условие, ветка match, реакция);
+  /// Запись из блока меняет структуры окружения, а не объявляет свои
+  pub(crate) block: bool,
+
+  /// Канонический путь файла, код которого выполнен в эту namespace структуру;
+  /// Импорты из её структур ищутся относительно этого файла
+  pub(crate) filePath: Option<PathBuf>,
+
   /// todo Комментарий + возможно не нужно т.к. можно лучше
   pub lineIndex: usize,
 }
@@ -102,15 +205,33 @@ impl Structure
       name,
       mutable,
       dataType,
+      overflow: Overflow::Saturate,
       lines,
       parameters: Parameters::new(None),
       result: None,
-      structures: Arc::new(RwLock::new(None)),
+      structures: Arc::new(RwLock::new(Structures::default())),
       parent,
+      reactions: Arc::new(RwLock::new(Vec::new())),
+      block: false,
+      filePath: None,
       lineIndex: 0
     }
   }
 
+  /// Создаёт временную структуру блока, которая читает и пишет структуры окружения
+  pub(crate) fn newBlock(name: &str, lines: Option< Vec< Arc<RwLock<Line>> > >, parent: Arc<RwLock<Structure>>) -> Self
+  {
+    let mut block: Structure = Structure::new(
+      Some(String::from(name)),
+      StructureMut::Constant,
+      StructureType::Method,
+      lines,
+      Some(parent)
+    );
+    block.block = true;
+    block
+  }
+
   // ===============================================================================================
   
   pub fn parseLink(linkName: &str) -> Vec<String> 
@@ -128,76 +249,72 @@ impl Structure
   ///   2. "child" в дочерних структурах "parent"
   ///   3. "grandchild" в дочерних структурах "child"
   /// 
-  /// todo Не смотрит выше self. Должен ли?
+  /// Если в self ничего не найдено, то поиск продолжается в родительских структурах,
+  /// чтобы вложенные блоки видели объявления окружения.
   pub fn getStructureByName(&self, name: &str) -> Option<Arc<RwLock<Structure>>> 
   {
-    // "a.b.c" -> ["a", "b", "c"]
-    let segments: Vec<String> = Self::parseLink(name);
-    
-    // Если имя пустое - нечего искать
-    match segments.is_empty() 
-    {
-      false => (),
-      true => return None
-    }
-
-    // Начинаем с корневого уровня (None)
-    let mut currentStructure: Option< Arc<RwLock<Structure>> > = None;
+    self.getStructureByPath( &internPath(name) )
+  }
 
-    // Пошагово проходим по каждому сегменту имени
-    for segment in segments.iter() 
+  /// Ищет структуру по уже разобранной ссылке, поднимаясь к родителям
+  pub fn getStructureByPath(&self, path: &[Name]) -> Option<Arc<RwLock<Structure>>> 
+  {
+    match self.getOwnStructureByPath(path)
     {
-      // Определяем список структур для поиска на текущем уровне:
-      // если currentStructure = None, это означает корневой уровень self.structures
-      // иначе — получаем дочерние структуры текущей найденной структуры
-      let childrenLink: Arc<RwLock< Option<Vec< Arc<RwLock<Structure>> >> >> = match &currentStructure 
+      Some(structureLink) => Some(structureLink),
+      None => match &self.parent
       {
-        None => self.structures.clone(), // Корневые структуры
-        Some(structureRef) => {
-          let structureGuard: RwLockReadGuard<Structure> = structureRef.read().unwrap();
-          structureGuard.structures.clone() // Дочерние структуры текущей
-        }
-      };
-      let childrenOption: RwLockReadGuard< Option<Vec< Arc<RwLock<Structure>> >> > = childrenLink.read().unwrap();
-
-      // Флаг найденной структуры
-      let mut found: bool = false;
-      // Следующая структура, если сегмент найден
-      let mut nextStructure: Option< Arc<RwLock<Structure>> > = None;
+        None => None,
+        Some(parentLink) => parentLink.read().unwrap().getStructureByPath(path)
+      }
+    }
+  }
 
-      // Обрабатываем наличие дочерних структур
-      match childrenOption.as_deref()
-      { None => {} Some(children) => 
-      {
-        for child in children 
-        {
-          let childGuard: RwLockReadGuard<Structure> = child.read().unwrap();
-          match &childGuard.name 
-          {
-            Some(childName) if childName == segment => 
-            {
-              found = true;
-              nextStructure = Some(child.clone());
-              break;
-            }
-            _ => ()
-          }
-        }
-      }}
+  /// Ищет структуру, которую может изменить запись в self:
+  /// в самой self и в окружении блоков, но не выше ближайшего метода.
+  /// Поэтому запись в методе объявляет локальную структуру, а не меняет внешнюю
+  pub fn getWritableStructureByName(&self, name: &str) -> Option<Arc<RwLock<Structure>>> 
+  {
+    self.getWritableStructureByPath( &internPath(name) )
+  }
 
-      // Если не найдено соответствие текущему сегменту - путь невалиден
-      match found
+  /// Ищет структуру для записи по уже разобранной ссылке, поднимаясь только из блоков
+  pub fn getWritableStructureByPath(&self, path: &[Name]) -> Option<Arc<RwLock<Structure>>> 
+  {
+    match self.getOwnStructureByPath(path)
+    {
+      Some(structureLink) => Some(structureLink),
+      None => match (self.block, &self.parent)
       {
-        true => (),
-        false => return None
+        (true, Some(parentLink)) => parentLink.read().unwrap().getWritableStructureByPath(path),
+        _ => None
       }
-
-      // Переходим на следующий уровень структуры
-      currentStructure = nextStructure;
     }
+  }
+
+  /// Ищет структуру по имени только внутри self, не поднимаясь к родителям
+  pub fn getOwnStructureByName(&self, name: &str) -> Option<Arc<RwLock<Structure>>> 
+  {
+    self.getOwnStructureByPath( &internPath(name) )
+  }
 
-    // После успешного прохождения всех сегментов возвращаем найденную структуру
-    currentStructure
+  /// Ищет структуру по уже разобранной ссылке только внутри self
+  pub fn getOwnStructureByPath(&self, path: &[Name]) -> Option<Arc<RwLock<Structure>>> 
+  {
+    // Первое имя ищем в корневых структурах
+    let (first, rest): (&Name, &[Name]) = path.split_first()?;
+    let mut currentStructure: Arc<RwLock<Structure>> =
+      self.structures.read().unwrap()
+        .get(*first)?.clone();
+
+    // Следующие имена ищем в дочерних структурах найденной
+    for name in rest
+    {
+      let children: Arc<RwLock<Structures>> = currentStructure.read().unwrap().structures.clone();
+      currentStructure = children.read().unwrap()
+        .get(*name)?.clone();
+    }
+    Some(currentStructure)
   }
 
   /// Добавляет новую вложенную структуру в текущую структуру;
@@ -205,16 +322,8 @@ impl Structure
   /// Нет &mut self - что хорошо.
   pub fn pushStructure(&self, structureLink: Arc<RwLock<Structure>>) -> ()
   {
-    let mut children: RwLockWriteGuard<Option< Vec< Arc<RwLock<Structure>> > >> =
-      self.structures.write().unwrap();
-    
-    if let Some(childrenVec) = children.as_mut() 
-    { // Если уже есть структуры, то просто push делаем
-      childrenVec.push(structureLink);
-    } else 
-    { // Если не было ещё структур, то создаём новый вектор
-      *children = Some(vec![structureLink]);
-    }
+    self.structures.write().unwrap()
+      .push(structureLink);
   }
 
   // ===============================================================================================
@@ -225,128 +334,120 @@ impl Structure
   /// чтобы было видно возможные объявления в ней
   pub fn structureOp(&self, structureLink: Arc<RwLock<Structure>>, op: TokenType, leftPartMutable: StructureMut, rightPart: Vec<Token>) -> ()
   {
-    match op
-    { // Принимаем только математические операции
-      TokenType::Equals |
-      TokenType::PlusEquals |
-      TokenType::MinusEquals |
-      TokenType::MultiplyEquals |
-      TokenType::DivideEquals => {},
-      _ => return
-    }
-
-    match op == TokenType::Equals 
+    // Вычисляем новое значение структуры
+    let rightPartValue: Token = match op
     {
-      true => 
+      TokenType::Equals =>
       { // Приравнивание правой части выражения к левой части выражения
 
         // todo должен быть вариант с вложением ?
         // Если нет вложений
-
-        let mut rightPartValue: Token = self.expression(&mut rightPart.clone());
-
-        let mut structure: RwLockWriteGuard<Structure> = structureLink.write().unwrap();
-
-        // Изменяем тип структуры если он не был указан
-        match
-          structure.dataType == StructureType::None ||
-          leftPartMutable == StructureMut::Dynamic // Dynamic может изменить dataType просто так
-        {
-          true =>
+        self.expression(&mut rightPart.clone())
+      }
+      op => match getCompoundOperation(&op)
+      {
+        None => return,
+        Some((operation, step)) =>
+        { // Составные операторы: a += 1, a++ и т.д.
+          let mut leftValue: Token =
           {
-            match leftPartMutable != StructureMut::Variable
-            { false => {} true =>
-            { // Будет присвоено только Final | Dynamic
-              structure.dataType = rightPartValue.getStructureType();
-            }}
-          }
-          false =>
-          { // Требуется выполнить преобразование в указанный тип данных
-            Structure::normalizeToken(&mut rightPartValue, structure.dataType.clone())
+            let structure: RwLockReadGuard<Structure> = structureLink.read().unwrap();
+            match structure.lines.as_ref().and_then(|lines: &Vec< Arc<RwLock<Line>> >| lines.first())
+            {
+              None => Token::newEmpty(TokenType::None),
+              Some(line) =>
+                self.expression(
+                  &mut line.read().unwrap()
+                    .tokens.clone()
+                    .unwrap_or_default()
+                )
+            }
+          };
+          // Считаем со знаком, чтобы 0 - 1 не остановилось на 0;
+          // Итог всё равно приводится к типу структуры ниже
+          match *leftValue.getDataType()
+          {
+            TokenType::UInt   => leftValue.setDataType(TokenType::Int),
+            TokenType::UFloat => leftValue.setDataType(TokenType::Float),
+            _ => {}
           }
+          let rightValue: Token = match (rightPart.is_empty(), step)
+          {
+            (true, Some(step)) => step,
+            _ => self.expression(&mut rightPart.clone())
+          };
+          calculate(&operation, &leftValue, &rightValue)
         }
+      }
+    };
+    self.setStructureValue(structureLink, leftPartMutable, rightPartValue);
+  }
+
+  /// Записывает новое значение в структуру с учётом её типа и изменяемости;
+  /// Запускает реакции, зависимые от этой структуры
+  pub fn setStructureValue(&self, structureLink: Arc<RwLock<Structure>>, leftPartMutable: StructureMut, mut rightPartValue: Token) -> ()
+  {
+    // Тип берётся до записи в структуру, т.к. обработчик overflow может её читать
+    let (dataType, overflow, structureName): (StructureType, Overflow, String) =
+    {
+      let structure: RwLockReadGuard<Structure> = structureLink.read().unwrap();
+      (structure.dataType.clone(), structure.overflow, structure.name.clone().unwrap_or_default())
+    };
+    // Изменяем тип структуры если он не был указан;
+    // Dynamic может изменить dataType, но только расширяя его
+    let newType: Option<StructureType> = match dataType == StructureType::None
+    {
+      true  => Some( rightPartValue.getStructureType() ),
+      false => match leftPartMutable == StructureMut::Dynamic
+      {
+        true  => Some( promoteStructureType(&dataType, &mut rightPartValue) ),
+        false => None
+      }
+    };
+    match newType
+    { Some(_) => {} None =>
+    { // Требуется выполнить преобразование в указанный тип данных
+      self.normalizeOverflow(&structureName, &mut rightPartValue, dataType, overflow);
+    }}
 
-        match leftPartMutable == StructureMut::Final
-        { false => {} true =>
-        { // Изменяем mutable если это был Final
-          structure.mutable = StructureMut::Constant;
+    let record: Option< Vec<Token> > = rightPartValue.getRecord()
+      .map(|_| vec![ rightPartValue.clone() ]);
+    {
+      let mut structure: RwLockWriteGuard<Structure> = structureLink.write().unwrap();
+      match newType
+      { None => {} Some(newType) =>
+      {
+        match leftPartMutable == StructureMut::Variable
+        { true => {} false =>
+        { // Будет присвоено только Final | Dynamic
+          structure.dataType = newType;
         }}
+      }}
 
-        // Приравниваем новое значение структуре
-        structure.lines =
-          Some(vec![
-            Arc::new(RwLock::new(
-              Line
-              {
-                tokens: Some(vec![ rightPartValue ]),
-                indent: None,
-                lines:  None,
-                parent: None
-              }
-            ))
-          ]);
-      }  
-      false =>
-      { // Иные операторы, например += -= *= /=
-        // получаем левую и правую часть
-        // todo сейчас тут много ошибок
-        let leftValue: Token = 
-        {
-          let structure: RwLockReadGuard<Structure> = structureLink.read().unwrap();
-          match &structure.lines
-          {
-            None => Token::newEmpty(TokenType::None),
-            Some(lines) =>
-              match lines.len() > 0
-              {
-                false => Token::newEmpty(TokenType::None),
-                true =>
-                  self.expression(
-                    &mut lines[0].read().unwrap()
-                      .tokens.clone()
-                      .unwrap_or_default() // todo плохо
-                )
-              }
-          }
-          //
-        };
-        let rightPart: Token = self.expression(&mut rightPart.clone()); // todo: возможно не надо клонировать токены, но скорее надо
-        
-        // Далее обрабатываем саму операцию
-        let mut structure: RwLockWriteGuard<Structure> = structureLink.write().unwrap();
-        match op 
-        { // Определяем тип операции
-          TokenType::PlusEquals => 
-          { 
-            structure.lines = 
-              Some(vec![
-                Arc::new(RwLock::new( 
-                  Line {
-                    tokens: Some(vec![ calculate(&TokenType::Plus, &leftValue, &rightPart) ]),
-                    // todo Здесь должны быть преобразования типа у структуры
-                    //  Сейчас если станет Int, то у структуры не поменяется U8 на I8.
-                    //  + Здесь должна быть normalizeToken когда не Dynamic
-                    indent: None,
-                    lines:  None,
-                    parent: None
-                  }
-                ))
-              ]);
-          }
-          _ => {} // todo: Дописать другие варианты; а также добавит для них отдельные тесты
-        }
-        //if op == TokenType::PlusEquals     { structure.value = calculate(&TokenType::Plus,     &leftValue, &rightValue); } else 
-        //if op == TokenType::MinusEquals    { structure.value = calculate(&TokenType::Minus,    &leftValue, &rightValue); } else 
-        //if op == TokenType::MultiplyEquals { structure.value = calculate(&TokenType::Multiply, &leftValue, &rightValue); } else 
-        //if op == TokenType::DivideEquals   { structure.value = calculate(&TokenType::Divide,   &leftValue, &rightValue); }
-      }
+      match leftPartMutable == StructureMut::Final
+      { false => {} true =>
+      { // Изменяем mutable если это был Final
+        structure.mutable = StructureMut::Constant;
+      }}
+
+      // Приравниваем новое значение структуре
+      structure.lines = Some( valueLines( Some(vec![ rightPartValue ]) ) );
+      match record.is_some()
+      { false => {} true =>
+      { // Поля прошлой записи заменяются полями новой
+        structure.structures = Arc::new(RwLock::new(Structures::default()));
+      }}
     }
+    pushFields(&structureLink, record.as_ref());
+
+    // Структура изменилась, запускаем зависимые от неё реакции
+    propagate(&structureLink);
   }
 
   // ===============================================================================================
 
   /// Вычисляем значение для struct имени типа TokenType::Word
-  fn replaceStructureByName(&self, value: &mut Vec<Token>, index: usize) -> ()
+  pub(crate) fn replaceStructureByName(&self, value: &mut Vec<Token>, index: usize) -> ()
   {
     fn setNone(value: &mut Vec<Token>, index: usize) 
     { // Возвращаем пустое значение
@@ -370,43 +471,28 @@ impl Structure
             { None => {} Some(lines) =>
             {
               let structureLinesLen: usize = lines.len();
-              match structureLinesLen
+              // Список остаётся списком при любом количестве элементов
+              match (structureLinesLen, structure.dataType == StructureType::List)
               {
-                1 =>
+                (1, false) =>
                 { // Структура с одним вложением
-                  let tokens: &mut Vec<Token> =
-                    &mut lines[0]
-                      .read().unwrap()
-                      .tokens.clone().unwrap_or_default(); // todo плохо
+                  let ir: Arc<Ir> = getLineIr(&lines[0], &lines[0].read().unwrap());
                   let _ = drop(structure);
-                  let result: Token = self.expression(tokens);
+                  let result: Token = self.evaluate(&ir);
                   value[index].setData    ( result.getData().clone() );
                   value[index].setDataType( *result.getDataType() );
                 }
-                structureLinesLen if structureLinesLen > 1 =>
+                (0, false) => { setNone(value, index); } // В структуре не было вложений
+                _ =>
                 { // Это структура с вложением
                   let mut linesResult: Vec<Token> = Vec::new();
                   for line in lines
                   {
-                    let tokens: &mut Vec<Token> =
-                      &mut line.read().unwrap()
-                        .tokens.clone().unwrap_or_default(); // todo плохо
-                    linesResult.push( self.expression(tokens) );
+                    let ir: Arc<Ir> = getLineIr(line, &line.read().unwrap());
+                    linesResult.push( self.evaluate(&ir) );
                   }
-                  value[index] = Token::newNesting(
-                    vec![
-                      Line
-                      {
-                        tokens: Some(linesResult),
-                        indent: None,
-                        lines: None,
-                        parent: None
-                      }
-                    ]
-                  );
-                  value[index].setDataType( TokenType::Link ); // todo: Речь не о Link, а об Array?
+                  value[index] = Token::newList(linesResult);
                 }
-                _ => { setNone(value, index); } // В структуре не было вложений
               }
               //
             }}
@@ -528,13 +614,9 @@ impl Structure
                               match lines.get(0)
                               { None => {} Some(line) =>
                               { // По сути это просто 0 линия через expression
-                                let mut lineTokens: Vec<Token> =
-                                  {
-                                    line.read().unwrap()
-                                      .tokens.clone().unwrap_or_default() // todo плохо
-                                  };
+                                let ir: Arc<Ir> = getLineIr(line, &line.read().unwrap());
                                 let _ = drop(childStructure);
-                                return self.expression(&mut lineTokens);
+                                return self.evaluate(&ir);
                                 //
                               }}
                               //
@@ -694,13 +776,9 @@ impl Structure
                     true =>
                     { // Если это просто одиночное значение, то просто выдаём его
                       // По сути это просто 0 линия через expression
-                      let mut lineTokens: Vec<Token> =
-                      {
-                        lines[0].read().unwrap()
-                          .tokens.clone().unwrap_or_default() // todo плохо
-                      };
+                      let ir: Arc<Ir> = getLineIr(&lines[0], &lines[0].read().unwrap());
                       let _ = drop(structure);
-                      return self.expression(&mut lineTokens);
+                      return self.evaluate(&ir);
                     }
                     false => match parameters
                     { // Здесь могут быть параметры функции или Some(vec![]) для процедуры;
@@ -762,71 +840,11 @@ impl Structure
   /// Принимает formatQuote типы и получает возможное значение обычной строки;
   /// В основном всё сводится к получению токенов в {} через Token::readTokens(),
   /// после чего результат проходит через expression и мы получаем обычную строку на выходе.
+  /// Разобранное тело строки берётся из кэша, см. ir::getFormatTemplate
   fn formatQuote(&self, tokenData: String) -> String 
   {
-    let mut result:           String    = String::new(); // Cтрока которая будет получена в конце
-    let mut expressionBuffer: String    = String::new(); // Буфер для выражения между {}
-    let mut expressionRead:   bool      = false;         // Флаг чтения в буфер выражения
-
-    let chars:       Vec<char> = tokenData.chars().collect(); // Всех символы в строке
-    let charsLength: usize     = chars.len();                 // Количество всех символов в строке
-
-    let mut i:      usize = 0; // Указатель на текущий символ
-    let mut c:      char;      // Текущий символ
-
-    while i < charsLength 
-    { // Читаем символы
-      c = chars[i];
-      match c 
-      {
-        '{' =>
-        { // Начинаем чтение выражения
-          expressionRead = true;
-        }
-        '}' =>
-        { // Заканчиваем чтение выражения
-          expressionRead = false;
-          expressionBuffer += "\n"; // Это нужно чтобы успешно завершить чтение линии Tokenizer::readTokens()
-
-          let mut expressionBufferTokens: Vec<Token> = 
-          {
-            readTokens(
-              expressionBuffer.as_bytes().to_vec(), 
-              false
-            )[0] // Получаем результат выражения в виде ссылки на буферную линию
-              .read().unwrap() // Читаем ссылку и
-              .tokens.clone()  // получаем все токены линии
-              .unwrap_or_default() // todo плохо
-          };
-          // Отправляем все токены линии как выражение
-          match self.expression(&mut expressionBufferTokens).getData().toString() 
-          { None => {} Some(expressionData) =>
-          { // Записываем результат посчитанный между {}
-            result += &expressionData;
-          }}
-          // Обнуляем буфер, вдруг далее ещё есть выражения между {}
-          expressionBuffer = String::new();
-        }
-        _ => 
-        { // Запись символов кроме {}
-          match expressionRead 
-          {
-            true => 
-            { // Если флаг чтения активен, то записываем символы выражения
-              expressionBuffer.push(c);
-            }  
-            false => 
-            { // Если флаг чтения не активен, то это просто символы
-              result.push(c);
-            }
-          }
-        }
-      }
-      // Продолжаем чтение символов строки
-      i += 1;
-    }
-    // Отдаём новую строку
-    result
+    // Тело строки разбирается один раз, далее вычисляются только выражения
+    self.evaluateFormat( &getFormatTemplate(&tokenData) )
   }
   
   // ===============================================================================================
@@ -898,6 +916,12 @@ impl Structure
     // Если это выражение не из одного токена,
     // то следует проверять каждый токен в цикле и
     // производить соответствующие операции
+    match separateExponentSigns(value)
+    { None => {} Some(separated) =>
+    {
+      *value = separated;
+      valueLength = value.len();
+    }}
     let mut i: usize = 0; // указатель на текущий токен
 
     while i < valueLength 
@@ -912,17 +936,17 @@ impl Structure
         }
         TokenType::FormattedRawString | TokenType::FormattedString | TokenType::FormattedChar =>
         { // Если это форматные варианты Char, String, RawString;
-          match value[0].getData().toString() 
+          match value[i].getData().toString() 
           { None => {} Some(valueData) =>
           { // Получаем data этого токена и сразу вычисляем его значение
-            value[0].setData( self.formatQuote(valueData) );
+            value[i].setData( self.formatQuote(valueData) );
             // Получаем новый тип без formatted
-            match *value[0].getDataType()
+            match *value[i].getDataType()
             {
-              TokenType::FormattedRawString => { value[0].setDataType(TokenType::RawString); }
-              TokenType::FormattedString    => { value[0].setDataType(TokenType::String); }
-              TokenType::FormattedChar      => { value[0].setDataType(TokenType::Char); }
-              _ => { value[0].setDataType(TokenType::None); }
+              TokenType::FormattedRawString => { value[i].setDataType(TokenType::RawString); }
+              TokenType::FormattedString    => { value[i].setDataType(TokenType::String); }
+              TokenType::FormattedChar      => { value[i].setDataType(TokenType::Char); }
+              _ => { value[i].setDataType(TokenType::None); }
             }
           }}
         }
@@ -960,18 +984,40 @@ impl Structure
                     let parameters: Parameters = Parameters::new(Some(bracketLines.to_vec()));
                     let mut parametersTokens: Vec<Token> = parameters.getAllExpressions(self).unwrap();
 
-                    // Вызов через worker
-                    match callExternal(&libraryPath, &methodName, &mut parametersTokens, StructureType::None) // todo Заменить string на abi-ffi
+                    // Вызов через worker;
+                    // Если вызов не получился, то это непредвиденное поведение `ffi`
+                    let mut attempt: usize = 0;
+                    loop
                     {
-                      Ok(_result) => {
-                        // todo Обработка result
-                      }
-                      Err(_) => {
-                        value[i].setDataType(TokenType::None);
-                        value[i].setData(None);
-                        break 'none;
+                      match callExternal(&libraryPath, &methodName, &mut parametersTokens, StructureType::None) // todo Заменить string на abi-ffi
+                      {
+                        Ok(_result) => {
+                          // todo Обработка result
+                          break;
+                        }
+                        Err(error) => {
+                          let values: Vec<Token> = vec![
+                            Token::new( TokenType::String, libraryPath.clone() ),
+                            Token::new( TokenType::String, methodName.clone() ),
+                            Token::new( TokenType::String, error )
+                          ];
+                          match unexpected(self, "ffi", values, attempt)
+                          {
+                            Unexpected::Retry => { attempt += 1; }
+                            Unexpected::Substitute(result) => {
+                              value[i].setDataType( *result.getDataType() );
+                              value[i].setData( result.getData() );
+                              break 'none;
+                            }
+                            Unexpected::Nothing => {
+                              value[i].setDataType(TokenType::None);
+                              value[i].setData(None);
+                              break 'none;
+                            }
+                          }
+                        }
+                        //
                       }
-                      //
                     }
                   }
                   //
@@ -986,57 +1032,6 @@ impl Structure
           }
           //
         } 
-        TokenType::Minus =>
-        { // это выражение в круглых скобках, но перед ними отрицание -
-          match
-            i+1 < valueLength &&
-            *value[i+1].getDataType() == TokenType::CircleBracketBegin
-          { false => {} true =>
-          { // считаем выражение внутри скобок
-            value[i+1] =
-            {
-              match &value[i+1].lines
-              {
-                None => Token::newEmpty(TokenType::None),
-                Some(lines) => match lines[0].tokens.clone() // todo Может быть не 0
-                {
-                  Some(mut tokenTokens) =>
-                  { // если получилось то оставляем его
-                    self.expression(&mut tokenTokens)
-                  }
-                  None =>
-                  { // если не получилось, то просто None
-                    Token::newEmpty(TokenType::None)
-                  }
-                }
-                //
-              }
-            };
-            // Меняем отрицание
-            let tokenData: String = value[i+1].getData().toString().unwrap_or_default();
-            match tokenData.starts_with(|c: char| c == '-')
-            {
-              true =>
-              { // Если это было отрицательное выражение, то делаем его положительным
-                value[i+1].setData(
-                  tokenData.chars().skip(1).collect::<String>()
-                );
-                value[i].setDataType(TokenType::Plus);
-              }
-              false =>
-              { // Если это не было отрицательным выражением, то делаем его отрицательным
-                //value[i+1].setData(
-                //  format!("-{}", tokenData)
-                //);
-                value[i+1].setData(
-                  format!("{}", tokenData)
-                );
-              }
-            }
-
-            i += 1; // Мы уже посчитали скобку
-          }}
-        }
         TokenType::CircleBracketBegin =>
         { // Это просто выражение в круглых скобках
           value[i] =
@@ -1058,74 +1053,15 @@ impl Structure
             }
           };
         }
+        // Операторы вычисляются после того, как получены все операнды
+        dataType if isExpressionOperator(&dataType) => {}
         _ =>
         { // Это либо метод, либо просто слово-структура
           match i+1 < valueLength && *value[i+1].getDataType() == TokenType::CircleBracketBegin
           {
             true =>
             { // Запускает метод; но он может быть либо обычный, либо из ссылки;
-              let structureName:String = value[i].getData().toString().unwrap_or_default();
-              let mut runBasicMethod: bool = true;
-              match self.getStructureByName(&structureName)
-              {
-                None => {} // Если структуры не было, то пропускаем;
-                Some(structureLink) =>
-                { // Мы должны проверить, что структура имеет только одно вложение;
-                  let structure: RwLockReadGuard<Structure> = structureLink.read().unwrap();
-                  match &structure.lines
-                  {
-                    None => {} // Если линий нет, то пропускаем
-                    Some(lines) =>
-                    {
-                      match lines.len() == 1
-                      {
-                        false => {} // Если вложений больше 1, то пропускаем;
-                        true =>
-                        {
-                          let line: RwLockReadGuard<Line> = lines[0].read().unwrap();
-                          match &line.tokens
-                          { None => {} Some(tokens) =>
-                          {
-                            match tokens.len() == 1
-                            {
-                              false => {} // Если больше одного токена, то пропускаем;
-                              true =>
-                              {
-                                // todo: Вообще должна быть проверка на TokenType::Link
-                                match *tokens[0].getDataType() == TokenType::Word
-                                {
-                                  false => {} // Если этот один токен не был ссылкой, то пропускаем;
-                                  true =>
-                                  {
-                                    self.linkExpression(
-                                      None,
-                                      &mut [
-                                        tokens[0].getData().toString().unwrap_or_default()
-                                      ].to_vec(),
-                                      Some(vec![]) // todo: Передать параметры функции
-                                    );
-                                    runBasicMethod = false; // Запуск метода по ссылке
-                                  }
-                                }
-                                //
-                              }
-                            }
-                            //
-                          }}
-                          //
-                        }
-                      }
-                      //
-                    }
-                  }
-                  //
-                }
-              }
-              match runBasicMethod
-              { false => {} true =>
-              { // Запуск обычного метода
-                self.functionCall(value, &mut valueLength, i);
-              }}
+              self.callExpression(value, &mut valueLength, i);
             }
             false => match *value[i].getDataType()
             { // Вычисляем значение для struct имени только при типе TokenType::Word
@@ -1141,100 +1077,83 @@ impl Structure
       i += 1;
     }
 
-    // Далее идут варианты математических и логических операций
-    // Проверка на логические операции 1
-    // todo Работало за другие операторы (преждевременно + или -)
-    //self.expressionOp(value, &mut valueLength,
-    //  &[TokenType::Equals, TokenType::NotEquals,
-    //    TokenType::GreaterThan, TokenType::LessThan,
-    //    TokenType::GreaterThanOrEquals, TokenType::LessThanOrEquals]
-    //);
-
-    // Проверка на логические операции 2
-    // todo Работало за другие операторы (преждевременно + или -)
-    //self.expressionOp(value, &mut valueLength,
-    //  &[TokenType::Inclusion, TokenType::Joint]
-    //);
-
-    // Проверка * и /
-    // todo Работало за другие операторы (преждевременно + или -)
-    //self.expressionOp(value, &mut valueLength, &[TokenType::Multiply, TokenType::Divide]);
-
-    // Проверка + и -
-    self.expressionOp(value, &mut valueLength, &[TokenType::Plus, TokenType::Minus]);
-
-    // Конец чтения выражения
-    match valueLength != 0
-    {
-      true =>
-      { // В том случае, если мы имеем всё ещё значение,
-        // значит просто вернём 0 элемент, чтобы избавиться от него
-        value[0].clone()
-      }
-      false =>
-      { // Если всё пусто, значит пусто
-        Token::newEmpty(TokenType::None)
-      }
-    }
+    // Далее идут математические и логические операции с учётом порядка операций
+    evaluateOperands(
+      value[..valueLength].iter()
+        .map(|token: &Token| (token.clone(), None))
+        .collect()
+    )
   }
 
-  /// Получает значение операции по левому и правому выражению; Это зависимость для expression.
-  /// Кроме того, может обрабатывать отрицание при использовании TokenType::Minus
-  fn expressionOp(&self, value: &mut Vec<Token>, valueLength: &mut usize, operations: &[TokenType])
+  /// Запускает метод по токену value[i] со скобкой параметров value[i+1];
+  /// Метод может быть либо обычный, либо из ссылки.
+  /// Обычный метод забирает скобку из value, а метод из ссылки оставляет её
+  pub(crate) fn callExpression(&self, value: &mut Vec<Token>, valueLength: &mut usize, i: usize) -> ()
   {
-    let mut i: usize = 0;
-    let mut token: Token;
-    let mut tokenType: &TokenType;
-
-    while i < *valueLength
-    { // Если остался только 1 токен — дальше нечего делать
-      match *valueLength == 1
-      { false => {} true =>
-      {
-        break;
-      }}
-      // Если i == 0, не можем начать, потому что нужен оператор
-      match i == 0
-      { false => {} true =>
-      {
-        i += 1; 
-        continue;
-      }}
-
-      // true - если будет входящий в operations операция
-      token = value[i].clone();
-      tokenType = token.getDataType();
-      match i+1 < *valueLength && operations.contains(tokenType)
-      {
-        // Вычисление заданной операции между двумя операндами.
-        true =>
+    let structureName:String = value[i].getData().toString().unwrap_or_default();
+    let mut runBasicMethod: bool = true;
+    match self.getStructureByName(&structureName)
+    {
+      None => {} // Если структуры не было, то пропускаем;
+      Some(structureLink) =>
+      { // Мы должны проверить, что структура имеет только одно вложение;
+        let structure: RwLockReadGuard<Structure> = structureLink.read().unwrap();
+        match &structure.lines
         {
-          value[i-1] = calculate(tokenType, &value[i-1], &value[i+1]);
-
-          value.remove(i); // remove op
-          value.remove(i); // remove right value
-          *valueLength -= 2;
-          continue;
+          None => {} // Если линий нет, то пропускаем
+          Some(lines) =>
+          {
+            match lines.len() == 1
+            {
+              false => {} // Если вложений больше 1, то пропускаем;
+              true =>
+              {
+                let line: RwLockReadGuard<Line> = lines[0].read().unwrap();
+                match &line.tokens
+                { None => {} Some(tokens) =>
+                {
+                  match tokens.len() == 1
+                  {
+                    false => {} // Если больше одного токена, то пропускаем;
+                    true =>
+                    {
+                      // todo: Вообще должна быть проверка на TokenType::Link
+                      match *tokens[0].getDataType() == TokenType::Word
+                      {
+                        false => {} // Если этот один токен не был ссылкой, то пропускаем;
+                        true =>
+                        {
+                          self.linkExpression(
+                            None,
+                            &mut [
+                              tokens[0].getData().toString().unwrap_or_default()
+                            ].to_vec(),
+                            Some(vec![]) // todo: Передать параметры функции
+                          );
+                          runBasicMethod = false; // Запуск метода по ссылке
+                        }
+                      }
+                      //
+                    }
+                  }
+                  //
+                }}
+                //
+              }
+            }
+            //
+          }
         }
-        // Подразумевается, что нет оператора - поэтому два операнда,
-        // поэтому мы можем проверить:
-        // value -value2
-        // Потому что минус входит в число и мы можем просто проверить 2 токена.
-        false => match matches!(*tokenType, TokenType::Int | TokenType::Float)
-        { false => {} true =>
-        {
-          value[i-1] = calculate(&TokenType::Plus, &value[i-1], &value[i]);
-
-          value.remove(i); // remove UInt
-          *valueLength -= 1;
-          continue;
-        }}
+        //
       }
-
-      i += 1;
     }
+    match runBasicMethod
+    { false => {} true =>
+    { // Запуск обычного метода
+      self.functionCall(value, valueLength, i);
+    }}
   }
-  
+
   // ===============================================================================================
 }
 
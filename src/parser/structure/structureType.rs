@@ -1,6 +1,9 @@
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{FromPrimitive, ToPrimitive};
 use serde::{Deserialize, Serialize};
 use crate::parser::bytes::Bytes;
 use crate::parser::structure::structure::Structure;
+use crate::parser::unexpected::{unexpected, Unexpected};
 use crate::tokenizer::types::token::Token;
 use crate::tokenizer::types::tokenType::TokenType;
 // =================================================================================================
@@ -96,6 +99,189 @@ impl ToString for StructureType
 
 // =================================================================================================
 
+/// Поведение числа, которое не помещается в StructureType структуры;
+/// Указывается после типа: `counter: U8 wrap = 0`
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum Overflow
+{
+  /// Число сводится к ближайшему пределу типа
+  #[default]
+  Saturate,
+  /// Целое переносится по модулю размера типа, как в дополнительном коде;
+  /// Дробные числа при этом сводятся к пределу
+  Wrap,
+  /// Число сводится к пределу, а выход за пределы является непредвиденным поведением `overflow`
+  Strict
+}
+
+impl Overflow
+{
+  /// Получает политику переполнения по её имени в объявлении структуры
+  pub fn fromToken(token: &Token) -> Option<Overflow>
+  {
+    match *token.getDataType() == TokenType::Word
+    {
+      false => None,
+      true  => match token.getData().toString()?.as_str()
+      {
+        "saturate" => Some(Overflow::Saturate),
+        "wrap"     => Some(Overflow::Wrap),
+        "strict"   => Some(Overflow::Strict),
+        _ => None
+      }
+    }
+  }
+}
+
+/// Получает границы целочисленного StructureType и его размер в битах
+fn getIntegerBounds(structureType: &StructureType) -> Option<(i128, i128, u32)>
+{
+  match structureType
+  {
+    StructureType::U8    => Some((0, u8::MAX as i128,    u8::BITS)),
+    StructureType::U16   => Some((0, u16::MAX as i128,   u16::BITS)),
+    StructureType::U32   => Some((0, u32::MAX as i128,   u32::BITS)),
+    StructureType::U64   => Some((0, u64::MAX as i128,   u64::BITS)),
+    StructureType::Usize => Some((0, usize::MAX as i128, usize::BITS)),
+    StructureType::I8    => Some((i8::MIN as i128,    i8::MAX as i128,    i8::BITS)),
+    StructureType::I16   => Some((i16::MIN as i128,   i16::MAX as i128,   i16::BITS)),
+    StructureType::I32   => Some((i32::MIN as i128,   i32::MAX as i128,   i32::BITS)),
+    StructureType::I64   => Some((i64::MIN as i128,   i64::MAX as i128,   i64::BITS)),
+    StructureType::Isize => Some((isize::MIN as i128, isize::MAX as i128, isize::BITS)),
+    _ => None
+  }
+}
+
+/// Получает целочисленный тип без ограничения размера: UInt или Int;
+/// Возвращает true для беззнакового UInt
+fn getUnboundedInteger(structureType: &StructureType) -> Option<bool>
+{
+  match structureType
+  {
+    StructureType::Custom(name) if name == "UInt" => Some(true),
+    StructureType::Custom(name) if name == "Int"  => Some(false),
+    _ => None
+  }
+}
+
+/// Получает дробный тип без ограничения размера: UFloat или Float;
+/// Возвращает true для беззнакового UFloat
+fn getUnboundedFloat(structureType: &StructureType) -> Option<bool>
+{
+  match structureType
+  {
+    StructureType::Custom(name) if name == "UFloat" => Some(true),
+    StructureType::Custom(name) if name == "Float"  => Some(false),
+    _ => None
+  }
+}
+
+/// Записывает целое в токен; Тип токена следует знаку числа
+fn setIntegerData(token: &mut Token, data: String, negative: bool) -> ()
+{
+  token.setDataType( match negative { true => TokenType::Int, false => TokenType::UInt } );
+  token.setData( Bytes::from(data) );
+}
+
+/// Записывает дробное число в токен дробного типа;
+/// F32 сводится к своим пределам, а UFloat не бывает отрицательным.
+/// Возвращает true, если число не помещалось в тип
+fn fitFloat(token: &mut Token, value: f64, structureType: &StructureType, unboundedFloat: Option<bool>) -> bool
+{
+  let (result, data, overflowed): (f64, String, bool) = match (structureType, unboundedFloat)
+  {
+    (StructureType::F32, _) =>
+    { // Потеря точности F32 не считается выходом за пределы
+      let clamped: f64 = value.clamp(f32::MIN as f64, f32::MAX as f64);
+      (clamped, (clamped as f32).to_string(), clamped != value)
+    }
+    (_, Some(true)) if value < 0.0 => (0.0, 0.0_f64.to_string(), true),
+    _ => (value, value.to_string(), false)
+  };
+  token.setDataType( match result < 0.0 { true => TokenType::Float, false => TokenType::UFloat } );
+  token.setData( Bytes::from(data) );
+  overflowed
+}
+
+/// Приводит целое к границам типа по политике переполнения;
+/// Возвращает новое значение и был ли выход за границы
+fn fitInteger(value: BigInt, (min, max, bits): (i128, i128, u32), overflow: Overflow) -> (i128, bool)
+{
+  match value.to_i128()
+  {
+    Some(value) if value >= min && value <= max => return (value, false),
+    _ => {}
+  }
+  let result: i128 = match overflow
+  {
+    Overflow::Wrap =>
+    { // Остаток по модулю 2^bits, а для знаковых - сдвиг в отрицательную половину
+      let modulus: BigInt = BigInt::from(1) << bits;
+      let mut wrapped: BigInt = ((value % &modulus) + &modulus) % &modulus;
+      match wrapped > BigInt::from(max)
+      {
+        false => {}
+        true  => wrapped -= &modulus
+      }
+      wrapped.to_i128().unwrap_or_default()
+    }
+    Overflow::Saturate | Overflow::Strict => match value.sign()
+    {
+      Sign::Minus => min,
+      _ => max
+    }
+  };
+  (result, true)
+}
+
+/// Порядок расширения целых типов;
+/// Знаковый тип того же размера идёт раньше большего беззнакового
+const integerPromotion: &[StructureType] = &[
+  StructureType::U8,  StructureType::I8,
+  StructureType::U16, StructureType::I16,
+  StructureType::U32, StructureType::I32,
+  StructureType::U64, StructureType::I64
+];
+
+/// Получает тип Dynamic структуры для нового значения;
+/// Тип остаётся прежним, пока значение в нём помещается, а иначе только расширяется:
+/// U8 становится I8 для отрицательных чисел, U16 для чисел больше 255 и т.д.
+pub fn promoteStructureType(structureType: &StructureType, token: &mut Token) -> StructureType
+{
+  let valueType: StructureType = token.getStructureType();
+  let isFloat = |structureType: &StructureType| matches!(structureType, StructureType::F32 | StructureType::F64);
+  match (getIntegerBounds(structureType), getIntegerBounds(&valueType))
+  {
+    (Some((min, max, _)), Some(_)) =>
+    {
+      let value: Option<i128> = token.getData().toString()
+        .and_then(|data: String| data.parse::<i128>().ok());
+      match value
+      {
+        Some(value) if value >= min && value <= max => structureType.clone(),
+        Some(value) =>
+        {
+          let signed: bool = min < 0;
+          integerPromotion.iter()
+            .skip_while(|candidate: &&StructureType| *candidate != structureType)
+            .filter(|candidate: &&StructureType| !signed || getIntegerBounds(candidate).is_some_and(|(min, _, _)| min < 0))
+            .find(|candidate: &&StructureType|
+              getIntegerBounds(candidate).is_some_and(|(min, max, _)| value >= min && value <= max)
+            )
+            .cloned()
+            .unwrap_or(valueType)
+        }
+        None => valueType // Больше 128 бит - целое без ограничения размера
+      }
+    }
+    // Целое помещается в дробный тип
+    (None, Some(_)) if isFloat(structureType) => structureType.clone(),
+    // F32 расширяется до F64, но F64 не сужается до F32
+    (None, None) if *structureType == StructureType::F64 && isFloat(&valueType) => StructureType::F64,
+    _ => valueType
+  }
+}
+
 impl Structure
 {
   /// Приводит данные токена в рамки требуемого StructureType,
@@ -107,7 +293,19 @@ impl Structure
   ///  чтобы можно было проверить и оптимизировать эту работу.
   pub fn normalizeToken(token: &mut Token, structureType: StructureType) 
   {
-    let dataType: &TokenType = token.getDataType();
+    Structure::normalizeTokenWith(token, structureType, Overflow::Saturate);
+  }
+
+  /// Приводит данные токена в рамки требуемого StructureType по политике переполнения;
+  /// Возвращает true, если число не помещалось в тип
+  pub fn normalizeTokenWith(token: &mut Token, structureType: StructureType, overflow: Overflow) -> bool
+  {
+    let dataType: TokenType = *token.getDataType();
+    match token.getList().is_some() || token.getRecord().is_some()
+    { false => {} true =>
+    { // Список и запись хранят значения в элементах, а не в данных
+      return false;
+    }}
 
     // Получаем строку из данных
     let tokenData: String = match token.getData().toString()
@@ -116,199 +314,166 @@ impl Structure
       None =>
       { // Нет данных
         token.setDefaultValue(structureType);
-        return;
+        return false;
       }
     };
 
     // Обработка типов
+    let unboundedInteger: Option<bool> = getUnboundedInteger(&structureType);
+    let unboundedFloat:   Option<bool> = getUnboundedFloat(&structureType);
     match structureType 
     {
       StructureType::U8 | StructureType::U16 | StructureType::U32 | StructureType::U64 | StructureType::Usize |
       StructureType::I8 | StructureType::I16 | StructureType::I32 | StructureType::I64 | StructureType::Isize |
-      StructureType::F32 | StructureType::F64 => 
+      StructureType::F32 | StructureType::F64 => {}
+      _ if unboundedInteger.is_some() || unboundedFloat.is_some() => {}
+      _ => {
+        // todo
+        // Другие типы — ничего не делаем
+        return false;
+      }
+    }
+
+    match dataType
+    {
+      TokenType::UInt | TokenType::Int => match (getIntegerBounds(&structureType), unboundedInteger)
       {
-        match dataType
-        {
-          TokenType::UInt => 
+        (Some(bounds), _) =>
+        { // Целое любой длины в целочисленный тип
+          let value: BigInt = match tokenData.parse::<i128>()
           {
-            if let Ok(mut value) = tokenData.parse::<u128>() 
+            Ok(value) if value >= bounds.0 && value <= bounds.1 => return false,
+            Ok(value) => BigInt::from(value),
+            Err(_) => match tokenData.parse::<BigInt>()
             {
-              match structureType 
-              {
-                StructureType::U8 => value = value.clamp(0, u8::MAX as u128),
-                StructureType::U16 => value = value.clamp(0, u16::MAX as u128),
-                StructureType::U32 => value = value.clamp(0, u32::MAX as u128),
-                StructureType::U64 => value = value.clamp(0, u64::MAX as u128),
-                StructureType::Usize => value = value.clamp(0, usize::MAX as u128),
-                StructureType::I8 => value = value.clamp(0, i8::MAX as u128),
-                StructureType::I16 => value = value.clamp(0, i16::MAX as u128),
-                StructureType::I32 => value = value.clamp(0, i32::MAX as u128),
-                StructureType::I64 => value = value.clamp(0, i64::MAX as u128),
-                StructureType::Isize => value = value.clamp(0, isize::MAX as u128),
-                StructureType::F32 => {
-                  let floatValue: f64 = (value as f64).clamp(f32::MIN as f64, f32::MAX as f64);
-                  token.setData( Bytes::from((floatValue as f32).to_string()) );
-                  return;
-                }
-                StructureType::F64 => {
-                  let floatValue: f64 = value as f64;
-                  token.setData( Bytes::from(floatValue.to_string()) );
-                  return;
-                }
-                _ => {}
+              Ok(value) => value,
+              Err(_) =>
+              { // Не распарсилось — базовое значение
+                token.setDefaultValue(structureType);
+                return false;
               }
-              token.setData( Bytes::from(value.to_string()) );
-            } else 
-            { // Не распарсилось — базовое значение
-              token.setDefaultValue(structureType);
             }
-          }
-          TokenType::Int => 
+          };
+          let (result, overflowed): (i128, bool) = fitInteger(value, bounds, overflow);
+          setIntegerData(token, result.to_string(), result < 0);
+          overflowed
+        }
+        (None, Some(unsigned)) =>
+        { // Целое в целое без ограничения размера; UInt не бывает отрицательным
+          match unsigned && dataType == TokenType::Int && tokenData.starts_with('-')
           {
-            if let Ok(mut value) = tokenData.parse::<i128>() 
+            false =>
             {
-              match structureType 
-              {
-                StructureType::U8 => {
-                  if value < 0 { value = 0; }
-                  value = value.clamp(0, u8::MAX as i128);
-                }
-                StructureType::U16 => {
-                  if value < 0 { value = 0; }
-                  value = value.clamp(0, u16::MAX as i128);
-                }
-                StructureType::U32 => {
-                  if value < 0 { value = 0; }
-                  value = value.clamp(0, u32::MAX as i128);
-                }
-                StructureType::U64 => {
-                  if value < 0 { value = 0; }
-                  value = value.clamp(0, u64::MAX as i128);
-                }
-                StructureType::Usize => {
-                  if value < 0 { value = 0; }
-                  value = value.clamp(0, usize::MAX as i128);
-                }
-                StructureType::I8 => value = value.clamp(i8::MIN as i128, i8::MAX as i128),
-                StructureType::I16 => value = value.clamp(i16::MIN as i128, i16::MAX as i128),
-                StructureType::I32 => value = value.clamp(i32::MIN as i128, i32::MAX as i128),
-                StructureType::I64 => value = value.clamp(i64::MIN as i128, i64::MAX as i128),
-                StructureType::Isize => value = value.clamp(isize::MIN as i128, isize::MAX as i128),
-                StructureType::F32 => {
-                  let floatValue: f64 = (value as f64).clamp(f32::MIN as f64, f32::MAX as f64);
-                  token.setData( Bytes::from((floatValue as f32).to_string()) );
-                  return;
-                }
-                StructureType::F64 => {
-                  let floatValue: f64 = value as f64;
-                  token.setData( Bytes::from(floatValue.to_string()) );
-                  return;
-                }
-                _ => {}
-              }
-              token.setData( Bytes::from(value.to_string()) );
-            } else 
-            { // Не распарсилось — базовое значение
-              token.setDefaultValue(structureType);
+              setIntegerData(token, tokenData.clone(), tokenData.starts_with('-'));
+              false
+            }
+            true =>
+            {
+              setIntegerData(token, String::from("0"), false);
+              true
             }
           }
-          TokenType::UFloat | TokenType::Float => 
+        }
+        (None, None) =>
+        { // Целое любой длины в дробный тип
+          let value: f64 = tokenData.parse::<f64>().unwrap_or_default();
+          fitFloat(token, value, &structureType, unboundedFloat)
+        }
+      }
+      TokenType::UFloat | TokenType::Float => 
+      {
+        if let Ok(mut value) = tokenData.parse::<f64>() 
+        { // Для UFloat обрезаем отрицательные до 0
+          if dataType == TokenType::UFloat && value < 0.0 {
+            value = 0.0;
+          }
+          match unboundedFloat
+          { // Float и UFloat хранят и бесконечность
+            Some(_) => return fitFloat(token, value, &structureType, unboundedFloat),
+            None => {}
+          }
+          if !value.is_finite() 
           {
-            if let Ok(mut value) = tokenData.parse::<f64>() 
-            { // Для UFloat обрезаем отрицательные до 0
-              if dataType == &TokenType::UFloat && value < 0.0 {
-                value = 0.0;
-              }
-              if !value.is_finite() 
-              {
-                // Бесконечность или NaN — базовое значение
-                token.setDefaultValue(structureType);
-                return;
-              }
-              match structureType 
+            // Бесконечность или NaN — базовое значение
+            token.setDefaultValue(structureType);
+            return false;
+          }
+          match (getIntegerBounds(&structureType), unboundedInteger)
+          {
+            (Some(bounds), _) =>
+            { // Приведение к целочисленным типам с округлением
+              let value: BigInt = BigInt::from_f64(value.round()).unwrap_or_default();
+              let (result, overflowed): (i128, bool) = fitInteger(value, bounds, overflow);
+              setIntegerData(token, result.to_string(), result < 0);
+              overflowed
+            }
+            (None, Some(unsigned)) =>
+            { // Приведение к целому без ограничения размера с округлением
+              let value: BigInt = BigInt::from_f64(value.round()).unwrap_or_default();
+              match unsigned && value.sign() == Sign::Minus
               {
-                StructureType::F32 => {
-                  if value < f32::MIN as f64 { value = f32::MIN as f64; }
-                  if value > f32::MAX as f64 { value = f32::MAX as f64; }
-                  token.setData( Bytes::from((value as f32).to_string()) );
-                }
-                StructureType::F64 => {
-                  if value < f64::MIN { value = f64::MIN; }
-                  if value > f64::MAX { value = f64::MAX; }
-                  token.setData( Bytes::from(value.to_string()) );
+                false =>
+                {
+                  setIntegerData(token, value.to_string(), value.sign() == Sign::Minus);
+                  false
                 }
-                // Приведение к целочисленным типам
-                target if matches!(target, 
-                  StructureType::U8 | StructureType::U16 | StructureType::U32 | StructureType::U64 | StructureType::Usize |
-                  //
-                  StructureType::I8 | StructureType::I16 | StructureType::I32 | StructureType::I64 | StructureType::Isize) =>
+                true =>
                 {
-                  let integerValue: i128 = if value < 0.0 { 0 } else { value.round() as i128 };
-                  match target 
-                  {
-                    StructureType::U8 => {
-                      let clamped: i128 = integerValue.clamp(0, u8::MAX as i128);
-                      token.setData( Bytes::from((clamped as u8).to_string()) );
-                    }
-                    StructureType::U16 => {
-                      let clamped: i128 = integerValue.clamp(0, u16::MAX as i128);
-                      token.setData( Bytes::from((clamped as u16).to_string()) );
-                    }
-                    StructureType::U32 => {
-                      let clamped: i128 = integerValue.clamp(0, u32::MAX as i128);
-                      token.setData( Bytes::from((clamped as u32).to_string()) );
-                    }
-                    StructureType::U64 => {
-                      let clamped: i128 = integerValue.clamp(0, u64::MAX as i128);
-                      token.setData( Bytes::from((clamped as u64).to_string()) );
-                    }
-                    StructureType::Usize => {
-                      let clamped: i128 = integerValue.clamp(0, usize::MAX as i128);
-                      token.setData( Bytes::from((clamped as usize).to_string()) );
-                    }
-                    StructureType::I8 => {
-                      let clamped: i128 = integerValue.clamp(i8::MIN as i128, i8::MAX as i128);
-                      token.setData( Bytes::from((clamped as i8).to_string()) );
-                    }
-                    StructureType::I16 => {
-                      let clamped: i128 = integerValue.clamp(i16::MIN as i128, i16::MAX as i128);
-                      token.setData( Bytes::from((clamped as i16).to_string()) );
-                    }
-                    StructureType::I32 => {
-                      let clamped: i128 = integerValue.clamp(i32::MIN as i128, i32::MAX as i128);
-                      token.setData( Bytes::from((clamped as i32).to_string()) );
-                    }
-                    StructureType::I64 => {
-                      let clamped: i128 = integerValue.clamp(i64::MIN as i128, i64::MAX as i128);
-                      token.setData( Bytes::from((clamped as i64).to_string()) );
-                    }
-                    StructureType::Isize => {
-                      let clamped: i128 = integerValue.clamp(isize::MIN as i128, isize::MAX as i128);
-                      token.setData( Bytes::from((clamped as isize).to_string()) );
-                    }
-                    _ => {}
-                  }
+                  setIntegerData(token, String::from("0"), false);
+                  true
                 }
-                _ => {}
               }
-            } else 
-            { // Не распарсилось — базовое значение
-              token.setDefaultValue(structureType);
             }
+            (None, None) => fitFloat(token, value, &structureType, unboundedFloat)
           }
-          _ => {
-            // Здесь пытаются прировнять что-то левое
-            token.setDefaultValue(structureType);
-          }
-          //
+        } else 
+        { // Не распарсилось — базовое значение
+          token.setDefaultValue(structureType);
+          false
         }
       }
       _ => {
-        // todo
-        // Другие типы — ничего не делаем
+        // Здесь пытаются прировнять что-то левое
+        token.setDefaultValue(structureType);
+        false
       }
     }
-    //
+  }
+
+  /// Приводит значение к типу структуры по её политике переполнения;
+  /// Для Overflow::Strict выход за пределы - это непредвиденное поведение `overflow`
+  /// со значениями: имя структуры и исходное число; Обработчик может подставить своё значение
+  pub(crate) fn normalizeOverflow(
+    &self,
+    structureName: &str,
+    token: &mut Token,
+    structureType: StructureType,
+    overflow: Overflow
+  ) -> ()
+  {
+    let original: Option<Token> = match overflow == Overflow::Strict
+    {
+      true  => Some( token.clone() ),
+      false => None
+    };
+    match Structure::normalizeTokenWith(token, structureType.clone(), overflow)
+    { false => {} true =>
+    {
+      match original
+      { None => {} Some(original) =>
+      {
+        let values: Vec<Token> = vec![ Token::new(TokenType::String, String::from(structureName)), original ];
+        match unexpected(self, "overflow", values, 0)
+        {
+          Unexpected::Substitute(mut result) =>
+          { // Подставленное значение тоже должно поместиться в тип
+            Structure::normalizeToken(&mut result, structureType);
+            *token = result;
+          }
+          Unexpected::Retry | Unexpected::Nothing => {}
+        }
+      }}
+    }}
   }
 }
 
@@ -339,6 +504,15 @@ impl Token
         self.setDataType(TokenType::Float);
         self.setData("0.0");
       }
+      // Числа без ограничения размера
+      _ if getUnboundedInteger(&structureType).is_some() => {
+        self.setDataType(TokenType::UInt);
+        self.setData("0");
+      }
+      _ if getUnboundedFloat(&structureType).is_some() => {
+        self.setDataType(TokenType::Float);
+        self.setData("0.0");
+      }
       // todo
       // Для остальных типов - ничего
       _ => {
@@ -371,6 +545,12 @@ impl Token
       return structureType;
     };
     
+    match self.getList().is_some() || self.getRecord().is_some()
+    { false => {} true =>
+    { // У списка и записи нет данных, только элементы
+      return StructureType::List;
+    }}
+
     //
     let dataType: &TokenType = self.getDataType();
     
@@ -401,9 +581,12 @@ impl Token
           } else if value <= usize::MAX as u128 {
             StructureType::Usize
           } else {
-            // Выходит за рамки хранения структуры - не обрабатываем
-            StructureType::None
+            // Выходит за рамки ABI типов - остаётся целым без ограничения размера
+            StructureType::Custom( String::from("UInt") )
           }
+        } else if data.parse::<BigUint>().is_ok() {
+          // Больше u128
+          StructureType::Custom( String::from("UInt") )
         } else {
           // Что-то непонятное
           StructureType::None
@@ -424,9 +607,12 @@ impl Token
           } else if value >= isize::MIN as i128 && value <= isize::MAX as i128 {
             StructureType::Isize
           } else {
-            // Выходит за рамки хранения структуры - не обрабатываем
-            StructureType::None
+            // Выходит за рамки ABI типов - остаётся целым без ограничения размера
+            StructureType::Custom( String::from("Int") )
           }
+        } else if data.parse::<BigInt>().is_ok() {
+          // За пределами i128
+          StructureType::Custom( String::from("Int") )
         } else {
           // Что-то непонятное
           StructureType::None
@@ -450,6 +636,11 @@ impl Token
           StructureType::None
         }
       }
+      TokenType::Bool => StructureType::Bool,
+      // Строки и символы не имеют своего StructureType, поэтому они Custom по имени,
+      // так же как и при явном указании `name: String`
+      TokenType::String | TokenType::RawString => StructureType::Custom( String::from("String") ),
+      TokenType::Char => StructureType::Custom( String::from("Char") ),
       // Для остальных типов - возвращаем Custom
       // todo Сейчас могут попасть лишние т.к. они не объявлены выше
       _ => StructureType::None,
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Arc, LazyLock, RwLock, RwLockWriteGuard};
+use crate::parser::structure::structure::Structure;
+// =================================================================================================
+
+// Вложенные структуры;
+// Хранятся в порядке объявления (по нему связываются параметры методов и индексы List),
+// а рядом лежит индекс по интернированному имени, поэтому поиск на каждом уровне - это O(1)
+// без чтения каждой вложенной структуры.
+//
+// Имена интернируются один раз: одинаковые имена получают один и тот же Name.
+// Ссылки вида `a.b.c` разбиваются на Name тоже один раз и дальше берутся из кэша.
+
+// =================================================================================================
+
+/// Интернированное имя структуры
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Name(u32);
+
+/// Номера всех встреченных имён
+static Names: LazyLock< RwLock< HashMap<Arc<str>, Name> > > =
+  LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Разобранные ссылки по их записи
+static Paths: LazyLock< RwLock< HashMap< String, Arc<[Name]> > > > =
+  LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Получает интернированное имя
+pub fn intern(name: &str) -> Name
+{
+  match Names.read().unwrap().get(name)
+  {
+    Some(name) => return *name,
+    None => {}
+  }
+  let mut names: RwLockWriteGuard< HashMap<Arc<str>, Name> > = Names.write().unwrap();
+  let next: Name = Name(names.len() as u32);
+  *names.entry(Arc::from(name)).or_insert(next)
+}
+
+/// Получает ссылку `a.b.c` как последовательность имён
+pub fn internPath(link: &str) -> Arc<[Name]>
+{
+  match Paths.read().unwrap().get(link)
+  {
+    Some(path) => return path.clone(),
+    None => {}
+  }
+  let path: Arc<[Name]> = link.split('.').map(intern).collect();
+  Paths.write().unwrap()
+    .insert(link.to_string(), path.clone());
+  path
+}
+
+// =================================================================================================
+
+/// Вложенные структуры в порядке объявления с индексом по имени
+#[derive(Clone, Default)]
+pub struct Structures
+{
+  /// Структуры в порядке объявления
+  list: Vec< Arc<RwLock<Structure>> >,
+  /// Позиция первой структуры с таким именем
+  index: HashMap<Name, usize>
+}
+
+impl Structures
+{
+  /// Добавляет структуру в конец;
+  /// Если имя уже занято, то по имени находится прошлая структура, как и при поиске по порядку
+  pub fn push(&mut self, structureLink: Arc<RwLock<Structure>>) -> ()
+  {
+    match &structureLink.read().unwrap().name
+    { None => {} Some(name) =>
+    {
+      self.index.entry(intern(name)).or_insert(self.list.len());
+    }}
+    self.list.push(structureLink);
+  }
+
+  /// Получает структуру по имени
+  pub fn get(&self, name: Name) -> Option<&Arc<RwLock<Structure>>>
+  {
+    self.index.get(&name).map(|position: &usize| &self.list[*position])
+  }
+}
+
+impl Deref for Structures
+{
+  type Target = [Arc<RwLock<Structure>>];
+
+  fn deref(&self) -> &Self::Target
+  {
+    &self.list
+  }
+}
+
+// =================================================================================================
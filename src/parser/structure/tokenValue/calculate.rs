@@ -1,3 +1,4 @@
+use num_bigint::{BigInt, BigUint};
 use crate::parser::structure::tokenValue::uf64::*;
 use crate::parser::structure::tokenValue::value::Value;
 use crate::tokenizer::types::token::Token;
@@ -12,20 +13,42 @@ use crate::tokenizer::types::tokenType::TokenType;
 /// Вычисляет по математической операции значение и тип нового токена из двух
 pub fn calculate(op: &TokenType, leftToken: &Token, rightToken: &Token) -> Token 
 {
-  // Получаем значение левой части выражения
-  let leftTokenDataType: TokenType = *leftToken.getDataType();
-  let leftValue: Value = getValue(leftToken.getData().toString().unwrap_or_default(), &leftTokenDataType);
-  // Получаем значение правой части выражения
-  let rightTokenDataType: TokenType = *rightToken.getDataType();
-  let rightValue: Value = getValue(rightToken.getData().toString().unwrap_or_default(), &rightTokenDataType);
+  calculateValues(op, *leftToken.getDataType(), getTokenValue(leftToken), *rightToken.getDataType(), getTokenValue(rightToken))
+}
+
+/// Вычисляет по математической операции значение и тип нового токена
+/// из уже полученных значений и типов левой и правой части
+pub(crate) fn calculateValues(
+  op: &TokenType,
+  leftTokenDataType:  TokenType,
+  leftValue:          Value,
+  rightTokenDataType: TokenType,
+  rightValue:         Value
+) -> Token
+{
   // Получаем значение выражения, а также предварительный тип
   let mut resultType: TokenType = TokenType::UInt;
+  // Строки и символы получают тип по самому результату: "a" * 3, 'a' + 1, 'a' + 'b'
+  let mut textType: Option<TokenType> = None;
+  let mut text = |value: Value| -> String
+  {
+    textType = match value
+    {
+      Value::String(_) => Some(TokenType::String),
+      Value::Char(_)   => Some(TokenType::Char),
+      _ => None
+    };
+    value.to_string()
+  };
   let mut resultValue: String = match *op 
   {
-    TokenType::Plus     => (leftValue + rightValue).to_string(),
-    TokenType::Minus    => (leftValue - rightValue).to_string(),
-    TokenType::Multiply => (leftValue * rightValue).to_string(),
-    TokenType::Divide   => (leftValue / rightValue).to_string(),
+    TokenType::Plus     => text(leftValue + rightValue),
+    TokenType::Minus    => text(leftValue - rightValue),
+    TokenType::Multiply => text(leftValue * rightValue),
+    TokenType::Divide   => text(leftValue / rightValue),
+    TokenType::Modulo   => text(leftValue % rightValue),
+    TokenType::Exponent => text(leftValue.pow(rightValue)),
+    TokenType::UnaryDivide => text(leftValue.integerDivide(rightValue)),
     TokenType::Inclusion => 
     { 
       resultType = TokenType::Bool;
@@ -103,24 +126,12 @@ pub fn calculate(op: &TokenType, leftToken: &Token, rightToken: &Token) -> Token
   // После того как значение было получено,
   // Смотрим какой точно тип выдать новому токену
   // todo: if -> match
-  match resultType != TokenType::Bool 
+  match (resultType != TokenType::Bool, textType)
   {
-    false => {}
-    true => 
+    (false, _) => {}
+    (true, Some(textType)) => resultType = textType,
+    (true, None) => 
     {
-      if leftTokenDataType == TokenType::String || rightTokenDataType == TokenType::String
-      {
-        resultType = TokenType::String;
-      } else
-      if matches!(leftTokenDataType, TokenType::Int | TokenType::UInt) &&
-          rightTokenDataType == TokenType::Char
-      { //
-        resultType = leftTokenDataType;
-      } else
-      if leftTokenDataType == TokenType::Char
-      {
-        resultType = TokenType::Char;
-      } else
       if leftTokenDataType == TokenType::UFloat || rightTokenDataType == TokenType::UFloat
       {
         // Проверяем смену типа
@@ -142,14 +153,22 @@ pub fn calculate(op: &TokenType, leftToken: &Token, rightToken: &Token) -> Token
       } else
       if leftTokenDataType == TokenType::UInt || rightTokenDataType == TokenType::UInt
       {
-        // Проверяем смену типа
-        if let Ok(value) = resultValue.parse::<i64>() 
+        // Проверяем смену типа; Целое может быть любой длины
+        if isInteger(&resultValue)
         {
-          if value < 0 {
+          if resultValue.starts_with('-') {
             resultType = TokenType::Int;
           } else {
             resultType = TokenType::UInt;
           }
+        } else
+        if let Ok(value) = resultValue.parse::<f64>()
+        { // Целые в отрицательной степени
+          if value < 0.0 {
+            resultType = TokenType::Float;
+          } else {
+            resultType = TokenType::UFloat;
+          }
         } else {
           resultType = TokenType::None;
           resultValue = String::new();
@@ -157,6 +176,14 @@ pub fn calculate(op: &TokenType, leftToken: &Token, rightToken: &Token) -> Token
       } else
       if leftTokenDataType == TokenType::Int || rightTokenDataType == TokenType::Int
       {
+        match isInteger(&resultValue)
+        {
+          true  => resultType = TokenType::Int,
+          false => resultType = TokenType::Float // Целые в отрицательной степени
+        }
+      } else
+      if resultValue.starts_with('-') && isInteger(&resultValue)
+      { // Разница кодов символов: 'a' - 'c'
         resultType = TokenType::Int;
       }
       //
@@ -165,6 +192,43 @@ pub fn calculate(op: &TokenType, leftToken: &Token, rightToken: &Token) -> Token
   // return
   Token::new(resultType, resultValue)
 }
+/// Получает токен значения с самым узким подходящим типом:
+/// Неотрицательные числа становятся UInt и UFloat, а NaN и бесконечности остаются Float
+pub(crate) fn getValueToken(value: Value) -> Token
+{
+  let data: String = value.to_string();
+  let dataType: TokenType = match value
+  {
+    Value::None() => return Token::newEmpty(TokenType::None),
+    Value::Int(_) | Value::UInt(_) | Value::BigInt(_) | Value::BigUInt(_) => match data.starts_with('-')
+    {
+      true  => TokenType::Int,
+      false => TokenType::UInt
+    },
+    Value::Float(_) | Value::UFloat(_) => match value.toF64()
+    {
+      Some(x) if x.is_finite() && !x.is_sign_negative() => TokenType::UFloat,
+      _ => TokenType::Float
+    },
+    Value::Char(_)   => TokenType::Char,
+    Value::String(_) => TokenType::String
+  };
+  Token::new(dataType, data)
+}
+
+/// Проверяет, что строка является записью целого числа любой длины
+fn isInteger(value: &str) -> bool
+{
+  let digits: &str = value.strip_prefix('-').unwrap_or(value);
+  digits.len() != 0 && digits.bytes().all(|byte: u8| byte.is_ascii_digit())
+}
+
+/// Получает значение токена для вычислений
+pub(crate) fn getTokenValue(token: &Token) -> Value
+{
+  getValue(token.getData().toString().unwrap_or_default(), token.getDataType())
+}
+
 /// Зависимость для calculate;
 /// Считает значение левой и правой части выражения
 fn getValue(tokenData: String, tokenDataType: &TokenType) -> Value 
@@ -179,12 +243,14 @@ fn getValue(tokenData: String, tokenDataType: &TokenType) -> Value
     {
       tokenData.parse::<i64>()
         .map(Value::Int)
+        .or_else(|_| tokenData.parse::<BigInt>().map(Value::BigInt))
         .unwrap_or(Value::Int(0))
     },
     TokenType::UInt =>
     {
       tokenData.parse::<u64>()
         .map(Value::UInt)
+        .or_else(|_| tokenData.parse::<BigUint>().map(Value::BigUInt))
         .unwrap_or(Value::UInt(0))
     },
     TokenType::Float =>
@@ -200,13 +266,14 @@ fn getValue(tokenData: String, tokenDataType: &TokenType) -> Value
         .map(Value::UFloat)
         .unwrap_or(Value::UFloat(uf64::from(0.0)))
     },
-    TokenType::Char =>
-    { // todo: добавить поддержку операций с TokenType::formattedChar
+    // Форматные строки и символы вычисляются до операций, здесь остаётся только их текст
+    TokenType::Char | TokenType::FormattedChar =>
+    {
       tokenData.parse::<char>()
         .map(|x| Value::Char(x))
         .unwrap_or(Value::Char('\0'))
     },
-    TokenType::String =>
+    TokenType::String | TokenType::RawString | TokenType::FormattedString | TokenType::FormattedRawString =>
     {
       tokenData.parse::<String>()
         .map(|x| Value::String(x))
@@ -214,8 +281,8 @@ fn getValue(tokenData: String, tokenDataType: &TokenType) -> Value
     },
     TokenType::Bool =>
     {
-      match tokenData == "true"
-      {
+      match tokenData == "true" || tokenData == "1"
+      { // Результат логических операций хранится как 1 и 0
         true  => Value::UInt(1),
         false => Value::UInt(0)
       }
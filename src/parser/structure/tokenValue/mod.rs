@@ -1,3 +1,4 @@
-pub(super) mod calculate;
+pub(crate) mod calculate;
+pub(crate) mod precedence;
 pub(super) mod uf64;
-pub(super) mod value;
\ No newline at end of file
+pub(crate) mod value;
\ No newline at end of file
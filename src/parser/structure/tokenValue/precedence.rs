@@ -0,0 +1,218 @@
+use crate::parser::structure::tokenValue::calculate::{calculateValues, getTokenValue};
+use crate::parser::structure::tokenValue::value::Value;
+use crate::tokenizer::types::token::Token;
+use crate::tokenizer::types::tokenType::TokenType;
+// =================================================================================================
+
+// Вычисление выражения, в котором операнды уже получены;
+// Методы, ссылки, скобки и имена к этому моменту заменены значениями,
+// поэтому остаются только операнды и операторы между ними.
+//
+// Порядок операций, от слабого к сильному:
+//   |
+//   &
+//   = != > < >= <=
+//   + -
+//   * / // %
+//   унарные - и !
+//   ^ (справа налево)
+//
+// Минус входит в число при чтении токенов, поэтому `a -1` - это `a + -1`,
+// а у числа перед ^ минус отделяется заранее: `-2^2` = -4, но `(-2)^2` = 4.
+
+// =================================================================================================
+
+/// Операнд выражения и его значение, если оно уже известно
+pub(crate) type Operand = (Token, Option<Value>);
+
+/// Сила унарных операторов; Выше неё только ^
+pub(crate) const unaryPrecedence: u8 = 6;
+
+/// Получает силу бинарного оператора и то, читается ли он справа налево
+pub(crate) fn getPrecedence(tokenType: &TokenType) -> Option<(u8, bool)>
+{
+  match *tokenType
+  {
+    TokenType::Inclusion => Some((1, false)),
+    TokenType::Joint     => Some((2, false)),
+    TokenType::Equals      | TokenType::NotEquals |
+    TokenType::GreaterThan | TokenType::LessThan  |
+    TokenType::GreaterThanOrEquals | TokenType::LessThanOrEquals => Some((3, false)),
+    TokenType::Plus     | TokenType::Minus  => Some((4, false)),
+    TokenType::Multiply | TokenType::Divide | TokenType::UnaryDivide | TokenType::Modulo => Some((5, false)),
+    TokenType::Exponent => Some((7, true)),
+    _ => None
+  }
+}
+
+/// Проверяет, что токен является оператором выражения, а не операндом
+pub(crate) fn isExpressionOperator(tokenType: &TokenType) -> bool
+{
+  *tokenType == TokenType::Not || getPrecedence(tokenType).is_some()
+}
+
+/// Проверяет, что это отрицательное число, которое минус забрал при чтении токенов
+fn isNegativeNumber(token: &Token) -> bool
+{
+  matches!(*token.getDataType(), TokenType::Int | TokenType::Float) &&
+    token.getData().toString().is_some_and(|data: String| data.starts_with('-'))
+}
+
+/// Отделяет минус от чисел перед ^, чтобы -2^2 считалось как -(2^2);
+/// None, если таких чисел нет.
+/// Это нужно делать до вычисления скобок и имён, пока числа из записи ещё отличаются от результатов
+pub(crate) fn separateExponentSigns(tokens: &[Token]) -> Option< Vec<Token> >
+{
+  let isNegativeBase = |i: usize| -> bool
+  {
+    isNegativeNumber(&tokens[i]) &&
+      tokens.get(i+1).is_some_and(|next: &Token| *next.getDataType() == TokenType::Exponent)
+  };
+  match (0..tokens.len()).any(isNegativeBase)
+  { true => {} false =>
+  {
+    return None;
+  }}
+
+  let mut result: Vec<Token> = Vec::with_capacity(tokens.len()+1);
+  for (i, token) in tokens.iter().enumerate()
+  {
+    match isNegativeBase(i)
+    {
+      false => result.push(token.clone()),
+      true  =>
+      {
+        let data: String = token.getData().toString().unwrap_or_default();
+        let absoluteType: TokenType = match *token.getDataType()
+        {
+          TokenType::Int => TokenType::UInt,
+          _ => TokenType::UFloat
+        };
+        result.push( Token::newEmpty(TokenType::Minus) );
+        result.push( Token::new(absoluteType, data[1..].to_string()) );
+      }
+    }
+  }
+  Some(result)
+}
+
+// =================================================================================================
+
+/// Вычисляет операнды и операторы между ними с учётом порядка операций
+pub(crate) fn evaluateOperands(operands: Vec<Operand>) -> Token
+{
+  let mut i: usize = 0;
+  let result: Operand = readExpression(&operands, &mut i, 0);
+  result.0
+}
+
+/// Читает выражение, в котором все бинарные операторы не слабее minPrecedence
+fn readExpression(operands: &[Operand], i: &mut usize, minPrecedence: u8) -> Operand
+{
+  let left: Operand = readUnary(operands, i);
+  climb(operands, i, left, minPrecedence)
+}
+
+/// Присоединяет к левой части операторы не слабее minPrecedence вместе с их правой частью
+fn climb(operands: &[Operand], i: &mut usize, mut left: Operand, minPrecedence: u8) -> Operand
+{
+  while *i < operands.len()
+  {
+    let token: &Token = &operands[*i].0;
+    let (op, precedence, rightAssociative): (TokenType, u8, bool) =
+      match getPrecedence(token.getDataType())
+      {
+        Some((precedence, rightAssociative)) => (*token.getDataType(), precedence, rightAssociative),
+        // value -value2; Число после операнда без оператора между ними
+        None if matches!(*token.getDataType(), TokenType::Int | TokenType::Float) => (TokenType::Plus, 4, false),
+        None => break // Дальше не выражение
+      };
+    match precedence < minPrecedence
+    { false => {} true =>
+    {
+      break;
+    }}
+
+    match op == *token.getDataType()
+    {
+      true  => *i += 1, // Пропускаем оператор
+      false => {}       // Число само является правой частью
+    }
+    match *i < operands.len()
+    { true => {} false =>
+    { // Оператор без правой части ничего не меняет
+      break;
+    }}
+    let right: Operand = readExpression(
+      operands,
+      i,
+      match rightAssociative
+      {
+        true  => precedence,
+        false => precedence+1
+      }
+    );
+    left = operate(&op, left, right);
+  }
+  left
+}
+
+/// Читает операнд вместе с унарными операторами перед ним
+fn readUnary(operands: &[Operand], i: &mut usize) -> Operand
+{
+  match operands.get(*i)
+  {
+    None => (Token::newEmpty(TokenType::None), None),
+    Some(operand) =>
+    {
+      *i += 1;
+      match *operand.0.getDataType()
+      {
+        TokenType::Plus  => readExpression(operands, i, unaryPrecedence),
+        TokenType::Minus => negate( readExpression(operands, i, unaryPrecedence) ),
+        TokenType::Not   => not( readExpression(operands, i, unaryPrecedence) ),
+        dataType if isExpressionOperator(&dataType) => (Token::newEmpty(TokenType::None), None),
+        _ => operand.clone()
+      }
+    }
+  }
+}
+
+// =================================================================================================
+
+/// Вычисляет бинарную операцию
+pub(crate) fn operate(op: &TokenType, left: Operand, right: Operand) -> Operand
+{
+  let (leftToken, leftValue): Operand = left;
+  let (rightToken, rightValue): Operand = right;
+  (
+    calculateValues(
+      op,
+      *leftToken.getDataType(),
+      leftValue.unwrap_or_else(|| getTokenValue(&leftToken)),
+      *rightToken.getDataType(),
+      rightValue.unwrap_or_else(|| getTokenValue(&rightToken))
+    ),
+    None
+  )
+}
+
+/// Меняет знак операнда; 0 - value, где 0 это Int, чтобы UInt стал Int
+pub(crate) fn negate(operand: Operand) -> Operand
+{
+  operate(&TokenType::Minus, (Token::new(TokenType::Int, String::from("0")), Some(Value::Int(0))), operand)
+}
+
+/// Логическое отрицание операнда
+pub(crate) fn not(operand: Operand) -> Operand
+{
+  let value: Value = operand.1.unwrap_or_else(|| getTokenValue(&operand.0));
+  let result: &str = match value.toBool()
+  {
+    true  => "0",
+    false => "1"
+  };
+  (Token::new(TokenType::Bool, String::from(result)), None)
+}
+
+// =================================================================================================
@@ -1,4 +1,7 @@
+use std::cmp::Ordering;
 use std::fmt;
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{FromPrimitive, ToPrimitive, Zero};
 use crate::parser::structure::tokenValue::uf64::uf64;
 // =================================================================================================
 
@@ -8,16 +11,24 @@ use crate::parser::structure::tokenValue::uf64::uf64;
 // А Value - это математика абстрактных данных, т.е. самих токенов.
 // Это все потому, что Token не может знать что есть какой тип - это дело структур;
 // Поэтому тут не должно быть ABI типов - это разные вещи.
+//
+// Целые не ограничены 64 битами: если результат не помещается в i64/u64,
+// то он становится BigInt/BigUInt, а обратно в 64 бита возвращается, как только помещается.
+// Ограничение до конкретного размера происходит только в Structure::normalizeToken и FFI.
 
 // =================================================================================================
 
-#[derive(Clone, PartialEq, PartialOrd)]
+#[derive(Clone)]
 pub enum Value 
 {
   None(),
   
   Int(i64),
   UInt(u64),
+  /// Int, который не помещается в i64
+  BigInt(BigInt),
+  /// UInt, который не помещается в u64
+  BigUInt(BigUint),
   Float(f64),
   UFloat(uf64),
   
@@ -35,12 +46,250 @@ impl Value
       Value::None() => false, // todo непонятно что нужно возвращать здесь
       Value::Int(v) => *v!=0,
       Value::UInt(v) => *v!=0,
+      Value::BigInt(v) => !v.is_zero(),
+      Value::BigUInt(v) => !v.is_zero(),
       Value::Float(v) => *v!=0.0,
       Value::UFloat(v) => *v!=uf64::from(0.0),
       Value::Char(c) => *c!='\0',
       Value::String(s) => !s.is_empty(),
     }
   }
+
+  /// Создаёт целое из BigInt;
+  /// Если число помещается в 64 бита, то это будет Int или UInt
+  pub fn fromBigInt(value: BigInt, unsigned: bool) -> Value
+  {
+    match unsigned && value >= BigInt::zero()
+    {
+      true => match value.to_u64()
+      {
+        Some(value) => Value::UInt(value),
+        None => Value::BigUInt( value.magnitude().clone() )
+      }
+      false => match value.to_i64()
+      {
+        Some(value) => Value::Int(value),
+        None => Value::BigInt(value)
+      }
+    }
+  }
+
+  /// Создаёт целое из i128;
+  /// Если число не помещается в 64 бита, то это будет BigInt или BigUInt
+  fn fromI128(value: i128, unsigned: bool) -> Value
+  {
+    match (unsigned && value >= 0, u64::try_from(value), i64::try_from(value))
+    {
+      (true, Ok(value), _) => Value::UInt(value),
+      (false, _, Ok(value)) => Value::Int(value),
+      _ => Value::fromBigInt(BigInt::from(value), unsigned)
+    }
+  }
+
+  /// Получает целое значение как i128, если это Int или UInt
+  fn toI128(&self) -> Option<i128>
+  {
+    match self
+    {
+      Value::Int(v) => Some(*v as i128),
+      Value::UInt(v) => Some(*v as i128),
+      _ => None
+    }
+  }
+
+  /// Получает целое значение как BigInt
+  pub fn toBigInt(&self) -> Option<BigInt>
+  {
+    match self
+    {
+      Value::Int(v) => Some( BigInt::from(*v) ),
+      Value::UInt(v) => Some( BigInt::from(*v) ),
+      Value::BigInt(v) => Some( v.clone() ),
+      Value::BigUInt(v) => Some( BigInt::from(v.clone()) ),
+      _ => None
+    }
+  }
+
+  /// Получает числовое значение как f64
+  pub fn toF64(&self) -> Option<f64>
+  {
+    match self
+    {
+      Value::Int(v) => Some(*v as f64),
+      Value::UInt(v) => Some(*v as f64),
+      Value::BigInt(v) => v.to_f64(),
+      Value::BigUInt(v) => v.to_f64(),
+      Value::Float(v) => Some(*v),
+      Value::UFloat(v) => Some( f64::from(*v) ),
+      _ => None
+    }
+  }
+
+  /// Проверяет, что это целое
+  fn isInteger(&self) -> bool
+  {
+    matches!(self, Value::Int(_) | Value::UInt(_) | Value::BigInt(_) | Value::BigUInt(_))
+  }
+
+  /// Проверяет, что это целое без знака
+  fn isUnsigned(&self) -> bool
+  {
+    matches!(self, Value::UInt(_) | Value::BigUInt(_))
+  }
+
+  /// Проверяет, что это большое целое
+  fn isBig(&self) -> bool
+  {
+    matches!(self, Value::BigInt(_) | Value::BigUInt(_))
+  }
+
+  /// Порядок видов значений для сравнения разных не числовых значений
+  fn rank(&self) -> u8
+  {
+    match self
+    {
+      Value::None() => 0,
+      Value::Int(_) | Value::UInt(_) | Value::BigInt(_) | Value::BigUInt(_) |
+      Value::Float(_) | Value::UFloat(_) => 1,
+      Value::Char(_) => 2,
+      Value::String(_) => 3,
+    }
+  }
+}
+
+/// Выполняет операцию над двумя целыми;
+/// Сначала в i128, а если результат туда не помещается, то в BigInt.
+/// Результат без знака, только если оба целых без знака
+fn integerOperation(
+  x: &Value,
+  y: &Value,
+  small: fn(i128, i128) -> Option<i128>,
+  big: fn(BigInt, BigInt) -> BigInt
+) -> Value
+{
+  let unsigned: bool = x.isUnsigned() && y.isUnsigned();
+  match (x.toI128(), y.toI128())
+  {
+    (Some(a), Some(b)) => match small(a, b)
+    {
+      Some(result) => return Value::fromI128(result, unsigned),
+      None => {}
+    }
+    _ => {}
+  }
+  match (x.toBigInt(), y.toBigInt())
+  {
+    (Some(a), Some(b)) => Value::fromBigInt(big(a, b), unsigned),
+    _ => Value::None()
+  }
+}
+
+/// Выполняет операцию над числами через f64;
+/// Результат UFloat, только если оба числа без знака
+fn floatOperation(x: &Value, y: &Value, operation: fn(f64, f64) -> f64) -> Value
+{
+  let result: f64 = operation(x.toF64().unwrap_or_default(), y.toF64().unwrap_or_default());
+  let unsigned = |value: &Value| value.isUnsigned() || matches!(value, Value::UFloat(_));
+  match unsigned(x) && unsigned(y)
+  {
+    true  => Value::UFloat( uf64::from(result) ),
+    false => Value::Float(result)
+  }
+}
+
+/// Наибольшая длина строки в байтах, которую может дать повторение;
+/// Длиннее - это уже не строка, а ошибка в программе, которая заняла бы всю память
+pub(crate) const maxRepeatLength: usize = 1 << 28;
+
+/// Повторяет строку целое количество раз; Отрицательное количество даёт пустую строку,
+/// а если результат длиннее maxRepeatLength, то None
+pub(crate) fn repeatString(text: &str, count: &Value) -> Value
+{
+  let count: Option<usize> = count.toBigInt()
+    .and_then(|count: BigInt| match count.sign() == Sign::Minus
+    {
+      true  => Some(0),
+      false => count.to_usize()
+    });
+  match count.filter(|count: &usize| text.len().checked_mul(*count).is_some_and(|length: usize| length <= maxRepeatLength))
+  {
+    None => Value::None(),
+    Some(count) => Value::String( text.repeat(count) )
+  }
+}
+
+/// Остаток от деления с округлением частного вниз: знак остатка как у делителя
+fn floorRem(a: i128, b: i128) -> Option<i128>
+{
+  a.checked_rem(b).map(|r: i128| match r != 0 && (r < 0) != (b < 0)
+  {
+    true  => r + b,
+    false => r
+  })
+}
+
+/// Частное с округлением вниз, парное к floorRem: a = floorDiv(a, b) * b + floorRem(a, b)
+fn floorDiv(a: i128, b: i128) -> Option<i128>
+{
+  a.checked_div(b).map(|q: i128| match a % b != 0 && (a < 0) != (b < 0)
+  {
+    true  => q - 1,
+    false => q
+  })
+}
+
+/// Прибавляет к символу смещение кода; Если такого символа нет, то None
+fn charOffset(x: char, offset: BigInt) -> Value
+{
+  match (BigInt::from(x as u32) + offset).to_u32().and_then(std::char::from_u32)
+  {
+    Some(resultChar) => Value::Char(resultChar),
+    None => Value::None()
+  }
+}
+
+// =================================================================================================
+
+// Числа сравниваются по значению независимо от вида: Int(1) == UInt(1) == Float(1.0);
+// Целые сравниваются точно, а если одно из чисел дробное - то через f64.
+// Строки и символы сравниваются лексикографически по кодам символов: "apple" < "banana", 'a' < "b"
+impl PartialEq for Value
+{
+  fn eq(&self, other: &Self) -> bool
+  {
+    self.partial_cmp(other) == Some(Ordering::Equal)
+  }
+}
+
+impl PartialOrd for Value
+{
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering>
+  {
+    match (self, other)
+    {
+      (Value::Int(x), Value::Int(y))       => x.partial_cmp(y),
+      (Value::UInt(x), Value::UInt(y))     => x.partial_cmp(y),
+      (Value::Float(x), Value::Float(y))   => x.partial_cmp(y),
+      (Value::UFloat(x), Value::UFloat(y)) => x.partial_cmp(y),
+      (Value::Char(x), Value::Char(y))     => x.partial_cmp(y),
+      (Value::String(x), Value::String(y)) => x.partial_cmp(y),
+      // Символ сравнивается со строкой как строка из одного символа
+      (Value::Char(x), Value::String(y))   => x.to_string().as_str().partial_cmp(y.as_str()),
+      (Value::String(x), Value::Char(y))   => x.as_str().partial_cmp(y.to_string().as_str()),
+      (Value::None(), Value::None())       => Some(Ordering::Equal),
+      (x, y) if x.isInteger() && y.isInteger() =>
+        match (x.toI128(), y.toI128())
+        {
+          (Some(x), Some(y)) => x.partial_cmp(&y),
+          _ => x.toBigInt().partial_cmp(&y.toBigInt())
+        }
+      (x, y) => match (x.toF64(), y.toF64())
+      {
+        (Some(x), Some(y)) => x.partial_cmp(&y),
+        _ => self.rank().partial_cmp(&other.rank())
+      }
+    }
+  }
 }
 
 impl fmt::Display for Value 
@@ -52,6 +301,8 @@ impl fmt::Display for Value
       Value::None() => write!(f, "None"), // todo непонятно что нужно возвращать здесь
       Value::Int(val) => write!(f, "{}", val),
       Value::UInt(val) => write!(f, "{}", val),
+      Value::BigInt(ref val) => write!(f, "{}", val),
+      Value::BigUInt(ref val) => write!(f, "{}", val),
       Value::Float(val) => write!(f, "{}", val),
       Value::UFloat(val) => write!(f, "{}", val),
       Value::Char(val) => write!(f, "{}", val),
@@ -70,8 +321,14 @@ impl std::ops::Add for Value
   {
     match (self.clone(), other) 
     {
+      // Целые
+      (x, y) if x.isInteger() && y.isInteger() =>
+        integerOperation(&x, &y, |a, b| a.checked_add(b), |a, b| a+b),
+      (x, y) if (x.isBig() || y.isBig()) && x.toF64().is_some() && y.toF64().is_some() =>
+        floatOperation(&x, &y, |a, b| a+b),
       // None
       // None + None обрабатывается в _
+      (Value::None(), y) if y.isBig() => y,
       (Value::None(), Value::Int(y))    => Value::Int(y),
       (Value::None(), Value::UInt(y))   => Value::UInt(y),
       (Value::None(), Value::Float(y))  => Value::Float(y),
@@ -79,18 +336,16 @@ impl std::ops::Add for Value
       (Value::None(), Value::Char(y))   => Value::Char(y),
       (Value::None(), Value::String(y)) => Value::String(y),
       // Int
-      (Value::Int(x), Value::Int(y))    => Value::Int   (x+y),
-      (Value::Int(x), Value::UInt(y))   => Value::Int   (x+ y as i64),
       (Value::Int(x), Value::Float(y))  => Value::Float (x as f64 +y),
       (Value::Int(x), Value::UFloat(y)) => Value::Float (x as f64 +f64::from(y)),
-      (Value::Int(x), Value::Char(y))   => Value::Int   (x+ y as i64),
       (Value::Int(x), Value::String(y)) => Value::String(x.to_string() +&y),
+      // Целые и Char
+      (x, Value::Char(y)) if x.isInteger() => integerOperation(&x, &Value::UInt(y as u64), |a, b| a.checked_add(b), |a, b| a+b),
+      // Большие целые и String
+      (x, Value::String(y)) if x.isBig() => Value::String(x.to_string() +&y),
       // UInt
-      (Value::UInt(x), Value::UInt(y))   => Value::UInt  (x+y),
-      (Value::UInt(x), Value::Int(y))    => Value::Int   (x as i64 +y),
       (Value::UInt(x), Value::Float(y))  => Value::Float (x as f64 +y),
       (Value::UInt(x), Value::UFloat(y)) => Value::UFloat(uf64::from(x) +y),
-      (Value::UInt(x), Value::Char(y))   => Value::UInt  (x+ y as u64),
       (Value::UInt(x), Value::String(y)) => Value::String(x.to_string() +&y),
       // Float
       (Value::Float(x), Value::Float(y))  => Value::Float (x+y),
@@ -104,37 +359,9 @@ impl std::ops::Add for Value
       (Value::UFloat(x), Value::UInt(y))   => Value::UFloat(x+ uf64::from(y)),
       (Value::UFloat(x), Value::Float(y))  => Value::Float (f64::from(x) +y),
       (Value::UFloat(x), Value::String(y)) => Value::String(x.to_string() +&y),
-      // Char
-      (Value::Char(x), Value::Char(y)) => 
-      {
-        Value::Char(
-          match std::char::from_u32(x as u32 + y as u32)
-          {
-            Some(resultChar) => {resultChar} 
-            None             => {'\0'}
-          }
-        )
-      },
-      (Value::Char(x), Value::Int(y)) => 
-      {
-        Value::Char(
-          match std::char::from_u32((x as i64 +y) as u32)
-          {
-            Some(resultChar) => {resultChar} 
-            None             => {'\0'}
-          }
-        )
-      },
-      (Value::Char(x), Value::UInt(y)) => 
-      {
-        Value::Char(
-          match std::char::from_u32((x as u64 +y) as u32) 
-          {
-            Some(resultChar) => {resultChar} 
-            None             => {'\0'}
-          }
-        )
-      },
+      // Char: символы соединяются в строку, а целое смещает код символа
+      (Value::Char(x), Value::Char(y))   => Value::String(format!("{}{}", x, y)),
+      (Value::Char(x), y) if y.isInteger() => charOffset(x, y.toBigInt().unwrap_or_default()),
       (Value::Char(x), Value::String(y)) => Value::String(x.to_string()+ &y),
       // String
       (Value::String(x), Value::String(y)) => Value::String(x+ &y),
@@ -143,6 +370,7 @@ impl std::ops::Add for Value
       (Value::String(x), Value::Float(y))  => Value::String(x+ &y.to_string()),
       (Value::String(x), Value::UFloat(y)) => Value::String(x+ &y.to_string()),
       (Value::String(x), Value::Char(y))   => Value::String(x+ &y.to_string()),
+      (Value::String(x), y) if y.isBig()   => Value::String(x+ &y.to_string()),
       //
       _ => self
     }
@@ -159,8 +387,16 @@ impl std::ops::Sub for Value
   {
     match (self.clone(), other) 
     {
+      // Целые: UInt - UInt не опускается ниже 0
+      (x, y) if x.isUnsigned() && y.isUnsigned() =>
+        integerOperation(&x, &y, |a, b| Some( (a-b).max(0) ), |a, b| match a > b { true => a-b, false => BigInt::zero() }),
+      (x, y) if x.isInteger() && y.isInteger() =>
+        integerOperation(&x, &y, |a, b| a.checked_sub(b), |a, b| a-b),
+      (x, y) if (x.isBig() || y.isBig()) && x.toF64().is_some() && y.toF64().is_some() =>
+        floatOperation(&x, &y, |a, b| a-b),
       // None
       // None + None обрабатывается в _
+      (Value::None(), y) if y.isBig() => y,
       (Value::None(), Value::Int(y))    => Value::Int(y),
       (Value::None(), Value::UInt(y))   => Value::UInt(y),
       (Value::None(), Value::Float(y))  => Value::Float(y),
@@ -168,24 +404,13 @@ impl std::ops::Sub for Value
       (Value::None(), Value::Char(y))   => Value::Char(y),
       (Value::None(), Value::String(y)) => Value::String(y),
       // Int
-      (Value::Int(x), Value::Int(y))    => Value::Int  (x-y),
-      (Value::Int(x), Value::UInt(y))   => Value::Int  (x- y as i64),
       (Value::Int(x), Value::Float(y))  => Value::Float(x as f64 -y),
       (Value::Int(x), Value::UFloat(y)) => Value::Float(x as f64 -f64::from(y)),
-      (Value::Int(x), Value::Char(y))   => Value::Int  (x- y as i64),
+      // Целые и Char
+      (x, Value::Char(y)) if x.isInteger() => integerOperation(&x, &Value::Int(y as i64), |a, b| a.checked_sub(b), |a, b| a-b),
       // UInt
-      (Value::UInt(x), Value::UInt(y)) => 
-      {
-        match y > x 
-        {
-          true  => { Value::UInt(0) }  
-          false => { Value::UInt(x-y) }
-        }
-      },
-      (Value::UInt(x), Value::Int(y))    => Value::Int   (x as i64 -y),
       (Value::UInt(x), Value::Float(y))  => Value::Float (x as f64 -y),
       (Value::UInt(x), Value::UFloat(y)) => Value::UFloat(uf64::from(x) -y),
-      (Value::UInt(x), Value::Char(y))   => Value::UInt  (x- y as u64),
       // Float
       (Value::Float(x), Value::Float(y))  => Value::Float(x-y),
       (Value::Float(x), Value::Int(y))    => Value::Float(x- y as f64),
@@ -196,37 +421,9 @@ impl std::ops::Sub for Value
       (Value::UFloat(x), Value::Int(y))    => Value::Float (f64::from(x)- y as f64),
       (Value::UFloat(x), Value::UInt(y))   => Value::UFloat(x- uf64::from(y)),
       (Value::UFloat(x), Value::Float(y))  => Value::Float (f64::from(x) -y),
-      // Char
-      (Value::Char(x), Value::Char(y)) => 
-      {
-        Value::Char(
-          match std::char::from_u32(x as u32 - y as u32)
-          {
-            Some(resultChar) => {resultChar} 
-            None => {'\0'}
-          }
-        )
-      },
-      (Value::Char(x), Value::Int(y)) => 
-      {
-        Value::Char(
-          match std::char::from_u32((x as i64 -y) as u32)
-          {
-            Some(resultChar) => {resultChar} 
-            None => {'\0'}
-          }
-        )
-      },
-      (Value::Char(x), Value::UInt(y)) => 
-      {
-        Value::Char(
-          match std::char::from_u32((x as u64 -y) as u32)
-          {
-            Some(resultChar) => {resultChar} 
-            None => {'\0'}
-          }
-        )
-      },
+      // Char: разница символов - это разница их кодов, а целое смещает код символа
+      (Value::Char(x), Value::Char(y)) => Value::Int(x as i64 - y as i64),
+      (Value::Char(x), y) if y.isInteger() => charOffset(x, -y.toBigInt().unwrap_or_default()),
       //
       _ => self
     }
@@ -243,8 +440,14 @@ impl std::ops::Mul for Value
   {
     match (self.clone(), other) 
     {
+      // Целые
+      (x, y) if x.isInteger() && y.isInteger() =>
+        integerOperation(&x, &y, |a, b| a.checked_mul(b), |a, b| a*b),
+      (x, y) if (x.isBig() || y.isBig()) && x.toF64().is_some() && y.toF64().is_some() =>
+        floatOperation(&x, &y, |a, b| a*b),
       // None
       // None + None обрабатывается в _
+      (Value::None(), y) if y.isBig() => y,
       (Value::None(), Value::Int(y))    => Value::Int(y),
       (Value::None(), Value::UInt(y))   => Value::UInt(y),
       (Value::None(), Value::Float(y))  => Value::Float(y),
@@ -252,13 +455,9 @@ impl std::ops::Mul for Value
       (Value::None(), Value::Char(y))   => Value::Char(y),
       (Value::None(), Value::String(y)) => Value::String(y),
       // Int
-      (Value::Int(x), Value::Int(y))    => Value::Int  (x*y),
-      (Value::Int(x), Value::UInt(y))   => Value::Int  (x* y as i64),
       (Value::Int(x), Value::Float(y))  => Value::Float(x as f64 *y),
       (Value::Int(x), Value::UFloat(y)) => Value::Float(x as f64 /f64::from(y)),
       // UInt
-      (Value::UInt(x), Value::UInt(y))   => Value::UInt  (x*y),
-      (Value::UInt(x), Value::Int(y))    => Value::Int   (x as i64 *y),
       (Value::UInt(x), Value::Float(y))  => Value::Float (x as f64 *y),
       (Value::UInt(x), Value::UFloat(y)) => Value::UFloat(uf64::from(x) *y),
       // Float
@@ -271,6 +470,11 @@ impl std::ops::Mul for Value
       (Value::UFloat(x), Value::Int(y))    => Value::Float (f64::from(x)* y as f64),
       (Value::UFloat(x), Value::UInt(y))   => Value::UFloat(x* uf64::from(y)),
       (Value::UFloat(x), Value::Float(y))  => Value::Float (f64::from(x) *y),
+      // Повторение строки и символа
+      (Value::String(x), y) if y.isInteger() => repeatString(&x, &y),
+      (x, Value::String(y)) if x.isInteger() => repeatString(&y, &x),
+      (Value::Char(x), y) if y.isInteger()   => repeatString(&x.to_string(), &y),
+      (x, Value::Char(y)) if x.isInteger()   => repeatString(&y.to_string(), &x),
       //
       _ => self
     }
@@ -280,6 +484,7 @@ impl std::ops::Mul for Value
 // =================================================================================================
 
 // divide
+// Деление на 0 - это левая часть, так же как у остатка и деления с округлением вниз
 impl std::ops::Div for Value 
 {
   type Output = Self;
@@ -287,8 +492,15 @@ impl std::ops::Div for Value
   {
     match (self.clone(), other) 
     {
+      (_, y) if y.toF64() == Some(0.0) => self,
+      // Целые
+      (x, y) if x.isInteger() && y.isInteger() =>
+        integerOperation(&x, &y, |a, b| a.checked_div(b), |a, b| a/b),
+      (x, y) if (x.isBig() || y.isBig()) && x.toF64().is_some() && y.toF64().is_some() =>
+        floatOperation(&x, &y, |a, b| a/b),
       // None
       // None + None обрабатывается в _
+      (Value::None(), y) if y.isBig() => y,
       (Value::None(), Value::Int(y))    => Value::Int(y),
       (Value::None(), Value::UInt(y))   => Value::UInt(y),
       (Value::None(), Value::Float(y))  => Value::Float(y),
@@ -296,13 +508,9 @@ impl std::ops::Div for Value
       (Value::None(), Value::Char(y))   => Value::Char(y),
       (Value::None(), Value::String(y)) => Value::String(y),
       // Int
-      (Value::Int(x), Value::Int(y))    => Value::Int  (x/y),
-      (Value::Int(x), Value::UInt(y))   => Value::Int  (x/ y as i64),
       (Value::Int(x), Value::Float(y))  => Value::Float(x as f64 /y),
       (Value::Int(x), Value::UFloat(y)) => Value::Float(x as f64 /f64::from(y)),
       // UInt
-      (Value::UInt(x), Value::UInt(y))   => Value::UInt  (x/y),
-      (Value::UInt(x), Value::Int(y))    => Value::Int   (x as i64 /y),
       (Value::UInt(x), Value::Float(y))  => Value::Float (x as f64 /y),
       (Value::UInt(x), Value::UFloat(y)) => Value::UFloat(uf64::from(x) /y),
       // Float
@@ -321,4 +529,167 @@ impl std::ops::Div for Value
   }
 }
 
-// =================================================================================================
\ No newline at end of file
+// =================================================================================================
+
+// modulo
+// Остаток берёт знак делителя, как при делении с округлением вниз:
+// 7 % 3 = 1, -7 % 3 = 2, 7 % -3 = -2, -7.5 % 2 = 0.5;
+// Так a = (a // b) * b + a % b для любых знаков.
+// Остаток от деления на 0 - это левая часть, так же как деление uf64 на 0
+impl std::ops::Rem for Value
+{
+  type Output = Self;
+  fn rem(self, other: Self) -> Self
+  {
+    match (self.clone(), other)
+    {
+      (_, y) if y.toF64() == Some(0.0) => self,
+      // Целые
+      (x, y) if x.isInteger() && y.isInteger() =>
+        integerOperation(&x, &y, floorRem, |a, b|
+        {
+          let r: BigInt = &a % &b;
+          match !r.is_zero() && (r < BigInt::zero()) != (b < BigInt::zero())
+          {
+            true  => r + b,
+            false => r
+          }
+        }),
+      // Дробные и смешанные
+      (x, y) if x.toF64().is_some() && y.toF64().is_some() =>
+        floatOperation(&x, &y, |a, b|
+        {
+          let r: f64 = a % b;
+          match r != 0.0 && (r < 0.0) != (b < 0.0)
+          {
+            true  => r + b,
+            false => r
+          }
+        }),
+      // None
+      (Value::None(), y) => y,
+      //
+      _ => self
+    }
+  }
+}
+
+// =================================================================================================
+
+/// Наибольший размер целого результата степени в битах;
+/// Степень с результатом больше считается через f64, иначе вычисление не ограничено по времени
+const maxPowBits: u64 = 1 << 16;
+
+/// Оценивает размер целого результата степени в битах снизу
+fn powBits(x: &Value, exponent: u32) -> u64
+{
+  x.toBigInt()
+    .map_or(0, |x: BigInt| x.bits().saturating_sub(1))
+    .saturating_mul(exponent as u64)
+}
+
+impl Value
+{
+  /// Возводит в степень;
+  /// Целое в целой неотрицательной степени остаётся целым и становится BigInt, если не помещается.
+  /// Отрицательная или дробная степень, а также целый результат больше maxPowBits считается через f64
+  pub fn pow(self, other: Self) -> Self
+  {
+    let exponent: Option<u32> = match other.isInteger()
+    {
+      true  => other.toBigInt().and_then(|exponent: BigInt| exponent.to_u32()),
+      false => None
+    };
+    match (self.clone(), exponent)
+    {
+      // Целые
+      (x, Some(exponent)) if x.isInteger() && powBits(&x, exponent) <= maxPowBits =>
+        integerOperation(
+          &x,
+          &Value::UInt(exponent as u64),
+          |a, b| a.checked_pow(b as u32),
+          |a, b| a.pow(b.to_u32().unwrap_or_default())
+        ),
+      // Дробные, смешанные и отрицательная степень
+      (x, _) if x.toF64().is_some() && other.toF64().is_some() =>
+        floatOperation(&x, &other, f64::powf),
+      // None
+      (Value::None(), _) => other,
+      //
+      _ => self
+    }
+  }
+
+  /// Делит с округлением частного вниз: 7 // 2 = 3, -7 // 2 = -4, 7.5 // 2 = 3;
+  /// Целые остаются целыми, дробные остаются дробными.
+  /// Деление на 0 возвращает левую часть
+  pub fn integerDivide(self, other: Self) -> Self
+  {
+    match (self.clone(), other)
+    {
+      (_, y) if y.toF64() == Some(0.0) => self,
+      // Целые
+      (x, y) if x.isInteger() && y.isInteger() =>
+        integerOperation(&x, &y, floorDiv, |a, b|
+        {
+          let q: BigInt = &a / &b;
+          match !(&a % &b).is_zero() && (a < BigInt::zero()) != (b < BigInt::zero())
+          {
+            true  => q - 1,
+            false => q
+          }
+        }),
+      // Дробные и смешанные
+      (x, y) if x.toF64().is_some() && y.toF64().is_some() =>
+        floatOperation(&x, &y, |a, b| (a / b).floor()),
+      // None
+      (Value::None(), y) => y,
+      //
+      _ => self
+    }
+  }
+
+  /// Модуль числа; Целые остаются целыми, но уже без знака
+  pub fn abs(self) -> Self
+  {
+    match self
+    {
+      x if x.isInteger() => match x.toBigInt()
+      {
+        Some(value) => Value::fromBigInt( BigInt::from(value.magnitude().clone()), true ),
+        None => Value::None()
+      },
+      Value::Float(x)  => Value::UFloat( uf64::from(x.abs()) ),
+      Value::UFloat(x) => Value::UFloat(x),
+      _ => Value::None()
+    }
+  }
+
+  /// Округляет число до целого через rounding: floor, ceil или round;
+  /// Целые не меняются, а у NaN и бесконечности нет целого - будет None
+  pub fn roundWith(self, rounding: fn(f64) -> f64) -> Self
+  {
+    match self.isInteger()
+    {
+      true  => self,
+      false => match self.toF64().map(rounding).and_then(BigInt::from_f64)
+      {
+        Some(result) => Value::fromBigInt(result, true),
+        None => Value::None()
+      }
+    }
+  }
+
+  /// Вычисляет функцию от числа через f64;
+  /// Не числа дают None
+  pub fn floatMap(self, function: fn(f64) -> f64) -> Self
+  {
+    match self.toF64()
+    {
+      Some(x) => Value::Float( function(x) ),
+      None => Value::None()
+    }
+  }
+}
+
+// =================================================================================================
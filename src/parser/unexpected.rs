@@ -0,0 +1,204 @@
+use std::cell::Cell;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use crate::parser::parser::readLines;
+use crate::parser::structure::structure::{Structure, StructureMut};
+use crate::parser::structure::structureType::StructureType;
+use crate::tokenizer::types::line::Line;
+use crate::tokenizer::types::token::Token;
+use crate::tokenizer::types::tokenType::TokenType;
+// =================================================================================================
+
+// Обработка непредвиденного поведения;
+// Ошибок нет, есть только непредвиденное поведение, которым можно управлять.
+// Для этого к структуре (или любой внешней структуре) прикрепляется обработчик:
+//
+//   a
+//     ! exec                   # только для exec, `!` без имени - для всего
+//       println(kind)          # имя события
+//       println(source)        # место события `структура:линия`
+//       println(values.0)      # значения события
+//       ? attempt < 3          # сколько раз уже повторяли
+//         retry()              # повторить операцию
+//       = "default"            # иначе подставить своё значение
+//     x = exec("nothing")
+//
+// Обработчик должен быть объявлен до места события; Ищется он от структуры события вверх.
+
+// =================================================================================================
+
+/// Префикс имён структур-обработчиков
+const handlerPrefix: &str = "!";
+
+thread_local!
+{
+  /// Был ли вызван retry() в текущем обработчике;
+  /// У каждого потока свои обработчики, поэтому и флаг свой
+  static RetryRequested: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Итог обработки непредвиденного поведения
+pub(crate) enum Unexpected
+{
+  /// Обработчик подставил своё значение через `= value`
+  Substitute(Token),
+  /// Обработчик попросил повторить операцию через retry()
+  Retry,
+  /// Обработчика нет, либо он ничего не вернул
+  Nothing
+}
+
+// =================================================================================================
+
+/// Ищет объявление обработчика `! kind` с вложением и прикрепляет его к структуре
+pub(super) fn searchHandler(
+  lineTokens: &[Token],
+  lineLines: &Option< Vec< Arc<RwLock<Line>> > >,
+  parentLink: &Arc<RwLock<Structure>>
+) -> bool
+{
+  match *lineTokens[0].getDataType() == TokenType::Not && lineLines.is_some()
+  {
+    false => return false,
+    true => {}
+  }
+  let kind: String = match lineTokens.get(1)
+  {
+    None => String::new(),
+    Some(kindToken) => kindToken.getData().toString().unwrap_or_default()
+  };
+
+  let name: String = format!("{}{}", handlerPrefix, kind);
+
+  // Блок может читаться повторно (например, тело метода), тогда обработчик заменяется
+  let handlerLink: Option< Arc<RwLock<Structure>> > = parentLink.read().unwrap().getOwnStructureByName(&name);
+  match handlerLink
+  {
+    Some(handlerLink) => handlerLink.write().unwrap().lines = lineLines.clone(),
+    None =>
+      parentLink.read().unwrap().pushStructure(
+        Arc::new(RwLock::new(Structure::new(
+          Some(name),
+          StructureMut::Constant,
+          StructureType::Method,
+          lineLines.clone(),
+          Some(parentLink.clone())
+        )))
+      )
+  }
+  true
+}
+
+/// Помечает текущий обработчик на повтор операции
+pub(crate) fn requestRetry()
+{
+  RetryRequested.set(true);
+}
+
+// =================================================================================================
+
+/// Ищет ближайший обработчик события от структуры вверх;
+/// Обработчик конкретного события важнее обработчика всего на том же уровне
+fn getHandler(structure: &Structure, kind: &str) -> Option< Arc<RwLock<Structure>> >
+{
+  let getOwnHandler = |structure: &Structure| -> Option< Arc<RwLock<Structure>> >
+  {
+    structure.getOwnStructureByName( &format!("{}{}", handlerPrefix, kind) )
+      .or_else(|| structure.getOwnStructureByName(handlerPrefix))
+  };
+
+  match getOwnHandler(structure)
+  { None => {} Some(handlerLink) =>
+  {
+    return Some(handlerLink);
+  }}
+  let mut parentLink: Option< Arc<RwLock<Structure>> > = structure.parent.clone();
+  while let Some(currentLink) = parentLink
+  {
+    let current: RwLockReadGuard<Structure> = currentLink.read().unwrap();
+    match getOwnHandler(&current)
+    { None => {} Some(handlerLink) =>
+    {
+      return Some(handlerLink);
+    }}
+    parentLink = current.parent.clone();
+  }
+  None
+}
+
+/// Создаёт константу со значением для структуры обработчика
+fn newBinding(name: &str, structureType: StructureType, tokens: Vec<Token>) -> Arc<RwLock<Structure>>
+{
+  let lines: Option< Vec< Arc<RwLock<Line>> > > = match tokens.len() == 0
+  {
+    true  => None,
+    false => Some(
+      tokens.into_iter()
+        .map(|token: Token| Arc::new(RwLock::new(Line {
+          tokens: Some(vec![token]),
+          indent: None,
+          lines:  None,
+          parent: None
+        })))
+        .collect()
+    )
+  };
+  Arc::new(RwLock::new(Structure::new(
+    Some(String::from(name)),
+    StructureMut::Constant,
+    structureType,
+    lines,
+    None
+  )))
+}
+
+/// Сообщает о непредвиденном поведении в структуре и запускает ближайший обработчик;
+///
+/// kind - имя события, values - значения события, attempt - сколько раз уже был повтор
+pub(crate) fn unexpected(structure: &Structure, kind: &str, values: Vec<Token>, attempt: usize) -> Unexpected
+{
+  let handlerLink: Arc<RwLock<Structure>> = match getHandler(structure, kind)
+  {
+    None => return Unexpected::Nothing,
+    Some(handlerLink) => handlerLink
+  };
+
+  // Каждый запуск обработчика идёт во временной структуре, чтобы не копить значения
+  let handlerStructure: Structure =
+  {
+    let handler: RwLockReadGuard<Structure> = handlerLink.read().unwrap();
+    Structure::new(
+      Some(String::from(handlerPrefix)),
+      StructureMut::Constant,
+      StructureType::Method,
+      handler.lines.clone(),
+      handler.parent.clone()
+    )
+  };
+  let source: String = format!(
+    "{}:{}",
+    structure.name.clone().unwrap_or_default(),
+    structure.lineIndex+1
+  );
+  handlerStructure.pushStructure( newBinding("kind",    StructureType::None, vec![Token::new(TokenType::String, String::from(kind))]) );
+  handlerStructure.pushStructure( newBinding("source",  StructureType::None, vec![Token::new(TokenType::String, source)]) );
+  handlerStructure.pushStructure( newBinding("values",  StructureType::List, values) );
+  handlerStructure.pushStructure( newBinding("attempt", StructureType::Usize, vec![Token::new(TokenType::UInt, attempt.to_string())]) );
+  let handlerStructureLink: Arc<RwLock<Structure>> = Arc::new(RwLock::new(handlerStructure));
+
+  // Обработчики могут быть вложены, поэтому сохраняем флаг внешнего
+  let outerRetry: bool = RetryRequested.replace(false);
+  readLines(handlerStructureLink.clone());
+  let retry: bool = RetryRequested.replace(outerRetry);
+
+  match retry
+  {
+    true  => Unexpected::Retry,
+    false => match handlerStructureLink.read().unwrap().result.clone()
+    {
+      None => Unexpected::Nothing,
+      Some(result) => Unexpected::Substitute(result)
+    }
+  }
+}
+
+// =================================================================================================
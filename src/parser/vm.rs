@@ -0,0 +1,672 @@
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::sync::atomic::Ordering;
+use crate::parser::matchBlock::isMatchKeyword;
+use crate::parser::parser::{isMathOperator, linearStructure, readBlock, setResult};
+use crate::parser::reactive::isReactKeyword;
+use crate::parser::runtime::{withRuntime, Runtime};
+use crate::parser::structure::ir::{compile, Ir, LineCache, Node, Single};
+use crate::parser::structure::methods::import::isImportKeyword;
+use crate::parser::structure::structure::{Structure, StructureMut};
+use crate::parser::structure::tokenValue::calculate::{calculateValues, getTokenValue};
+use crate::parser::structure::tokenValue::precedence::{
+  getPrecedence, isExpressionOperator, negate, not, operate, unaryPrecedence, Operand
+};
+use crate::parser::structure::tokenValue::value::Value;
+use crate::tokenizer::types::line::Line;
+use crate::tokenizer::types::token::Token;
+use crate::tokenizer::types::tokenType::TokenType;
+// =================================================================================================
+
+// Виртуальная машина;
+// Вместо обхода токенов линия один раз переводится в байткод для стековой машины:
+// операнды кладутся на стек, операторы забирают два верхних значения и кладут результат.
+//
+// В байткод переводятся:
+//   - выражения: вызовы методов, процедур и встроенных функций, форматные строки,
+//     все операторы с их порядком, как его понимает evaluateOperands.
+//     Сначала слева направо кладутся все операнды, после чего операции считаются над ними,
+//     так же как при обходе дерева;
+//   - линии-выражения и результаты `= value`;
+//   - линейные записи `a = value`, `a~ = value`, `a~~ = value`, `a: U8 = value`;
+//     Правая часть считается на стеке, а объявление или изменение структуры с учётом её
+//     изменяемости и типа делает linearStructure, как и в readLines.
+//     Запись в константу и составные операторы (+= и т.д.) выполняются через readLines;
+//   - цепочки условных блоков `?`: условия считаются на стеке, а выбранный блок
+//     читается через readBlock, как в searchStructure, вместе с повтором блока на go().
+//
+// Остальные линии выполняются так же, как в readLines: структуры с вложенными линиями
+// (методы), реакции, match, import, обработчики `!`.
+// Их вложенные линии читаются через readLines, а значит тоже на виртуальной машине,
+// поэтому порядок чтения линий, lineIndex и бюджет линий у обоих движков одинаковые.
+//
+// Обход дерева (readLines) остаётся эталоном: вывод программы не зависит от выбранного движка.
+//
+// Из CLI: rts run --engine=vm file.rt
+// Из библиотеки: RTS::setEngine(Engine::Vm)
+
+// =================================================================================================
+
+/// Движок выполнения линий
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine
+{
+  /// Обход дерева линий и токенов
+  Tree,
+  /// Байткод для виртуальной машины
+  Vm
+}
+
+impl Runtime
+{
+  /// Выбирает движок выполнения
+  pub fn setEngine(&self, engine: Engine) -> ()
+  {
+    self.vmEngine.store(engine == Engine::Vm, Ordering::SeqCst);
+  }
+}
+
+/// Выбирает движок выполнения текущей среды
+pub fn setEngine(engine: Engine) -> ()
+{
+  withRuntime(|runtime: &Runtime| runtime.setEngine(engine));
+}
+
+/// Проверяет, что выбран движок Engine::Vm
+pub(crate) fn isVmEngine() -> bool
+{
+  withRuntime(|runtime: &Runtime| runtime.vmEngine.load(Ordering::Relaxed))
+}
+
+// =================================================================================================
+
+/// Инструкция виртуальной машины
+enum Instruction
+{
+  /// Положить токен и его значение, если оно уже известно
+  Push(Token, Option<Value>),
+  /// Положить значение структуры по имени из выражения
+  Load(Token),
+  /// Положить выражение из одного токена
+  Single(Single),
+  /// Вызвать метод: имя, скобка параметров и выражение этой скобки
+  Call(Token, Token, Arc<Ir>),
+  /// Забрать столько операндов и положить результат операций над ними
+  Reduce(usize, Vec<Step>),
+  /// Вычислить выражение обходом токенов
+  Evaluate(Arc<Ir>)
+}
+
+/// Шаг вычисления операций над уже полученными операндами, в обратной польской записи
+enum Step
+{
+  /// Взять следующий операнд
+  Operand,
+  /// Взять None вместо операнда, которого нет
+  None,
+  /// Забрать два значения и положить результат операции
+  Operate(TokenType),
+  /// Поменять знак значения
+  Negate,
+  /// Логическое отрицание значения
+  Not
+}
+
+/// Байткод линии
+enum LineCode
+{
+  /// Выражение, результат которого не нужен
+  Statement(Vec<Instruction>),
+  /// Результат структуры `= value`
+  Return(Vec<Instruction>),
+  /// Линейная запись `a = value`: токены линии и байткод правой части
+  Assign(Vec<Token>, Vec<Instruction>),
+  /// Цепочка условных блоков `?`, которая начинается на этой линии
+  Branches(Vec<Branch>),
+  /// Линия, которая выполняется через readLines
+  Interpret
+}
+
+/// Условный блок цепочки `?`
+struct Branch
+{
+  /// Байткод условия; None - это блок else
+  condition: Option< Vec<Instruction> >,
+  /// Линии блока
+  lines: Option< Vec< Arc<RwLock<Line>> > >
+}
+
+/// Байткод линий одной среды выполнения
+#[derive(Default)]
+pub(crate) struct VmState
+{
+  lines: LineCache<LineCode>
+}
+
+impl VmState
+{
+  /// Забывает байткод всех линий
+  pub(crate) fn clear(&self) -> ()
+  {
+    self.lines.clear();
+  }
+}
+
+// =================================================================================================
+
+/// Переводит операнд в байткод
+fn compileOperand(node: &Node, code: &mut Vec<Instruction>) -> ()
+{
+  match node
+  {
+    Node::Literal(token, value) => code.push( Instruction::Push(token.clone(), Some(value.clone())) ),
+    Node::Name(token) => code.push( Instruction::Load(token.clone()) ),
+    Node::Group(ir) => compileExpression(ir, code),
+    Node::Call(token, bracket, group) => code.push( Instruction::Call(token.clone(), bracket.clone(), group.clone()) )
+  }
+}
+
+/// Получает оператор элемента выражения, как его видит climb:
+/// сам оператор, либо + перед числом без оператора; None, если дальше не выражение.
+/// Тип значения имени, скобки или метода известен только при вычислении, поэтому для них тоже None
+fn getOperator(node: &Node) -> Option<(TokenType, u8, bool)>
+{
+  match node
+  {
+    Node::Literal(token, _) => match getPrecedence(token.getDataType())
+    {
+      Some((precedence, rightAssociative)) => Some((*token.getDataType(), precedence, rightAssociative)),
+      None if matches!(*token.getDataType(), TokenType::Int | TokenType::Float) => Some((TokenType::Plus, 4, false)),
+      None => None
+    }
+    _ => None
+  }
+}
+
+/// Переводит операции выражения в шаги, повторяя readExpression
+fn compileOperation(nodes: &[Node], i: &mut usize, minPrecedence: u8, steps: &mut Vec<Step>) -> ()
+{
+  compileUnary(nodes, i, steps);
+  while *i < nodes.len()
+  {
+    let (op, precedence, rightAssociative): (TokenType, u8, bool) = match getOperator(&nodes[*i])
+    {
+      None => break, // Дальше не выражение
+      Some(operator) => operator
+    };
+    match precedence < minPrecedence
+    { false => {} true =>
+    {
+      break;
+    }}
+    match &nodes[*i]
+    {
+      Node::Literal(token, _) if *token.getDataType() == op => *i += 1, // Пропускаем оператор
+      _ => {}                                                            // Число само является правой частью
+    }
+    match *i < nodes.len()
+    { true => {} false =>
+    { // Оператор без правой части ничего не меняет
+      break;
+    }}
+    compileOperation(
+      nodes,
+      i,
+      match rightAssociative
+      {
+        true  => precedence,
+        false => precedence+1
+      },
+      steps
+    );
+    steps.push( Step::Operate(op) );
+  }
+}
+
+/// Переводит операнд вместе с унарными операторами перед ним в шаги, повторяя readUnary
+fn compileUnary(nodes: &[Node], i: &mut usize, steps: &mut Vec<Step>) -> ()
+{
+  match nodes.get(*i)
+  {
+    None => steps.push( Step::None ),
+    Some(node) =>
+    {
+      *i += 1;
+      match node
+      {
+        Node::Literal(token, _) => match *token.getDataType()
+        {
+          TokenType::Plus  => compileOperation(nodes, i, unaryPrecedence, steps),
+          TokenType::Minus =>
+          {
+            compileOperation(nodes, i, unaryPrecedence, steps);
+            steps.push( Step::Negate );
+          }
+          TokenType::Not =>
+          {
+            compileOperation(nodes, i, unaryPrecedence, steps);
+            steps.push( Step::Not );
+          }
+          dataType if isExpressionOperator(&dataType) => steps.push( Step::None ),
+          _ => steps.push( Step::Operand )
+        }
+        _ => steps.push( Step::Operand )
+      }
+    }
+  }
+}
+
+/// Переводит выражение из нескольких элементов в байткод;
+/// false, если часть элементов не вошла бы в выражение, тогда его считает evaluate
+fn compileChain(nodes: &[Node], code: &mut Vec<Instruction>) -> bool
+{
+  let mut steps: Vec<Step> = Vec::new();
+  let mut i: usize = 0;
+  compileOperation(nodes, &mut i, 0, &mut steps);
+  match i == nodes.len()
+  {
+    false => return false,
+    true  => {}
+  }
+  // Операнды кладутся на стек все сразу и слева направо, как в evaluateChain
+  let mut count: usize = 0;
+  for node in nodes
+  {
+    match node
+    {
+      Node::Literal(token, _) if isExpressionOperator(token.getDataType()) => {}
+      _ =>
+      {
+        compileOperand(node, code);
+        count += 1;
+      }
+    }
+  }
+  match steps.len() == 1 && count == 1
+  {
+    true  => {} // Операций нет, результат - это сам операнд
+    false => code.push( Instruction::Reduce(count, steps) )
+  }
+  true
+}
+
+/// Переводит выражение в байткод; Результат выражения остаётся на стеке
+fn compileExpression(ir: &Arc<Ir>, code: &mut Vec<Instruction>) -> ()
+{
+  match &**ir
+  {
+    Ir::Single(Single::Literal(token)) => code.push( Instruction::Push(token.clone(), None) ),
+    Ir::Single(single) => code.push( Instruction::Single(single.clone()) ),
+    Ir::Chain(nodes) => match compileChain(nodes, code)
+    {
+      true  => {}
+      false => code.push( Instruction::Evaluate(ir.clone()) )
+    }
+    _ => code.push( Instruction::Evaluate(ir.clone()) )
+  }
+}
+
+/// Получает индекс `=` линейной записи, если её можно перевести в байткод;
+/// Левая часть - это имя с флагом изменяемости и типом, как её понимает linearStructure,
+/// а правая часть не пустая и не является реакцией или import.
+/// Составные операторы (+= и т.д.) считают сначала старое значение, поэтому они идут через readLines
+fn assignIndex(tokens: &[Token]) -> Option<usize>
+{
+  let operatorIndex: usize = tokens.iter()
+    .position(|token: &Token| isMathOperator(*token.getDataType()))?;
+  let operator: TokenType = *tokens[operatorIndex].getDataType();
+  match operator == TokenType::Equals
+  {
+    false => return None,
+    true  => {}
+  }
+  let nameLength: usize = tokens[..operatorIndex].iter()
+    .position(|token: &Token| *token.getDataType() == TokenType::Colon)
+    .unwrap_or(operatorIndex);
+  let right: &[Token] = &tokens[operatorIndex+1..];
+  match
+    (nameLength == 1 || (nameLength == 2 && matches!(tokens[1].getDataType(), TokenType::Tilde | TokenType::DoubleTilde))) &&
+    !right.is_empty() &&
+    !isReactKeyword(&right[0]) && !isImportKeyword(&right[0]) &&
+    // linearStructure делит линию по каждому такому оператору
+    !right.iter().any(|token: &Token| *token.getDataType() == operator)
+  {
+    true  => Some(operatorIndex),
+    false => None
+  }
+}
+
+/// Переводит цепочку условных блоков `?`, которая начинается с линии lineIndex, в байткод;
+/// Цепочка - это линии подряд, которые начинаются с `?`, как их собирает searchStructure
+fn compileBranches(lines: &[ Arc<RwLock<Line>> ], lineIndex: usize) -> LineCode
+{
+  let mut branches: Vec<Branch> = Vec::new();
+  for lineLink in &lines[lineIndex..]
+  {
+    let line: RwLockReadGuard<Line> = lineLink.read().unwrap();
+    let tokens: &Vec<Token> = match &line.tokens
+    {
+      None => break,
+      Some(tokens) => tokens
+    };
+    match *tokens[0].getDataType() != TokenType::Question
+    { false => {} true =>
+    {
+      break;
+    }}
+    let condition: Option< Vec<Instruction> > = match tokens.len() > 1
+    {
+      false => None,
+      true  =>
+      {
+        let mut code: Vec<Instruction> = Vec::new();
+        compileExpression(&Arc::new(compile(&tokens[1..])), &mut code);
+        Some(code)
+      }
+    };
+    branches.push( Branch { condition, lines: line.lines.clone() } );
+  }
+  LineCode::Branches(branches)
+}
+
+/// Переводит линию в байткод; lines - линии структуры, где lineIndex это индекс этой линии.
+/// Проверки повторяют порядок searchStructure и searchReturn
+fn compileLine(tokens: &[Token], hasLines: bool, lines: &[ Arc<RwLock<Line>> ], lineIndex: usize) -> LineCode
+{
+  let mut code: Vec<Instruction> = Vec::new();
+  match *tokens[0].getDataType()
+  {
+    // Выражение, которое не является линейной записью и import
+    TokenType::Word if !hasLines &&
+      !matches!(tokens[1].getDataType(), TokenType::Tilde | TokenType::DoubleTilde) &&
+      !tokens.iter().any(|token: &Token| isMathOperator(*token.getDataType()) || *token.getDataType() == TokenType::Colon) &&
+      !(tokens.len() == 2 && isImportKeyword(&tokens[0])) =>
+    {
+      compileExpression(&Arc::new(compile(tokens)), &mut code);
+      LineCode::Statement(code)
+    }
+    // Линейная запись
+    TokenType::Word if !hasLines => match assignIndex(tokens)
+    {
+      None => LineCode::Interpret,
+      Some(operatorIndex) =>
+      {
+        compileExpression(&Arc::new(compile(&tokens[operatorIndex+1..])), &mut code);
+        LineCode::Assign(tokens.to_vec(), code)
+      }
+    }
+    // Результат, который не является реакцией
+    TokenType::Equals if !hasLines && !isReactKeyword(&tokens[1]) =>
+    {
+      compileExpression(&Arc::new(compile(&tokens[1..])), &mut code);
+      LineCode::Return(code)
+    }
+    // Условный блок, который не является блоком сопоставления или реакцией
+    TokenType::Question if hasLines &&
+      !tokens.iter().any(|token: &Token| isMatchKeyword(token) || isReactKeyword(token)) =>
+      compileBranches(lines, lineIndex),
+    _ => LineCode::Interpret
+  }
+}
+
+// =================================================================================================
+
+/// Выполняет байткод выражения и возвращает его результат
+fn run(structure: &Structure, code: &[Instruction]) -> Token
+{
+  let mut stack: Vec<Operand> = Vec::with_capacity(code.len());
+  for instruction in code
+  {
+    match instruction
+    {
+      Instruction::Push(token, value) => stack.push( (token.clone(), value.clone()) ),
+      Instruction::Load(token) =>
+      {
+        let mut value: Vec<Token> = vec![token.clone()];
+        structure.replaceStructureByName(&mut value, 0);
+        stack.push( (value.remove(0), None) );
+      }
+      Instruction::Single(single) => stack.push( (structure.evaluateSingle(single), None) ),
+      Instruction::Call(token, bracket, group) =>
+      {
+        let mut value: Vec<Token> = vec![token.clone(), bracket.clone()];
+        let mut valueLength: usize = 2;
+        structure.callExpression(&mut value, &mut valueLength, 0);
+        let result: Token = value.remove(0);
+        match valueLength == 2
+        {
+          false => stack.push( (result, None) ),
+          true  =>
+          { // Метод из ссылки оставляет скобку, тогда её значение прибавляется к числу
+            let argument: Token = structure.evaluate(group);
+            match matches!(argument.getDataType(), TokenType::Int | TokenType::Float)
+            {
+              false => stack.push( (result, None) ),
+              true  =>
+              {
+                let resultValue: Value = getTokenValue(&result);
+                let argumentValue: Value = getTokenValue(&argument);
+                stack.push( (
+                  calculateValues(&TokenType::Plus, *result.getDataType(), resultValue, *argument.getDataType(), argumentValue),
+                  None
+                ) );
+              }
+            }
+          }
+        }
+      }
+      Instruction::Reduce(count, steps) =>
+      {
+        let mut operands = stack.split_off(stack.len()-count).into_iter();
+        let mut values: Vec<Operand> = Vec::with_capacity(*count);
+        for step in steps
+        {
+          match step
+          {
+            Step::Operand => values.push( operands.next().unwrap() ),
+            Step::None => values.push( (Token::newEmpty(TokenType::None), None) ),
+            Step::Operate(operator) =>
+            {
+              let right: Operand = values.pop().unwrap();
+              let left:  Operand = values.pop().unwrap();
+              values.push( operate(operator, left, right) );
+            }
+            Step::Negate =>
+            {
+              let value: Operand = values.pop().unwrap();
+              values.push( negate(value) );
+            }
+            Step::Not =>
+            {
+              let value: Operand = values.pop().unwrap();
+              values.push( not(value) );
+            }
+          }
+        }
+        stack.push( values.pop().unwrap() );
+      }
+      Instruction::Evaluate(ir) => stack.push( (structure.evaluate(ir), None) )
+    }
+  }
+  match stack.pop()
+  {
+    Some((token, _)) => token,
+    None => Token::newEmpty(TokenType::None)
+  }
+}
+
+/// Проверяет, что условие верно
+fn isTrue(structureLink: &Arc<RwLock<Structure>>, code: &[Instruction]) -> bool
+{
+  run(&structureLink.read().unwrap(), code)
+    .getData().toString()
+    .is_some_and(|data: String| data == "1")
+}
+
+/// Выполняет цепочку условных блоков;
+/// Блок, который заканчивается на go(), читается снова, пока условия выбирают его
+fn runBranches(branches: &[Branch], structureLink: &Arc<RwLock<Structure>>) -> ()
+{
+  loop
+  {
+    let branch: &Branch = match branches.iter().find(|branch: &&Branch|
+      match &branch.condition
+      {
+        None => true, // else
+        Some(code) => isTrue(structureLink, code)
+      })
+    {
+      None => return,
+      Some(branch) => branch
+    };
+    let name: &str = match branch.condition
+    {
+      None    => "else",
+      Some(_) => "if-elif"
+    };
+    match readBlock(name, branch.lines.clone(), structureLink.clone())
+    {
+      false => return,
+      true  => {}
+    }
+  }
+}
+
+/// Выполняет линию на виртуальной машине;
+/// Возвращает false, если линию нужно выполнить через readLines.
+/// Цепочка условных блоков сдвигает lineIndex на её последнюю линию, как в searchStructure
+pub(crate) fn runLine(
+  lineLink:      &Arc<RwLock<Line>>,
+  line:          &RwLockReadGuard<Line>,
+  structureLink: &Arc<RwLock<Structure>>,
+  lineIndex:     *mut usize
+) -> bool
+{
+  let tokens: &[Token] = line.tokens.as_deref().unwrap_or_default();
+  match tokens.len() < 2
+  { // Одиночные токены объявляют структуры
+    true  => return false,
+    false => {}
+  }
+  let lineCode: Arc<LineCode> = withRuntime(|runtime|
+    runtime.vm.lines.get(lineLink, ||
+    {
+      let structure: RwLockReadGuard<Structure> = structureLink.read().unwrap();
+      compileLine(tokens, line.lines.is_some(), structure.lines.as_deref().unwrap_or_default(), unsafe{*lineIndex})
+    })
+  );
+  match &*lineCode
+  {
+    LineCode::Interpret => false,
+    LineCode::Statement(code) =>
+    {
+      run(&structureLink.read().unwrap(), code);
+      true
+    }
+    LineCode::Return(code) =>
+    {
+      let result: Token = run(&structureLink.read().unwrap(), code);
+      setResult(structureLink, result);
+      true
+    }
+    LineCode::Assign(lineTokens, code) =>
+    {
+      // Константу изменить нельзя, об этом сообщает readLines, не считая правую часть
+      let name: String = lineTokens[0].getData().toString().unwrap_or_default();
+      let target: Option< Arc<RwLock<Structure>> > = structureLink.read().unwrap().getWritableStructureByName(&name);
+      match target.is_some_and(|target: Arc<RwLock<Structure>>| target.read().unwrap().mutable == StructureMut::Constant)
+      { false => {} true =>
+      {
+        return false;
+      }}
+      let value: Token = run(&structureLink.read().unwrap(), code);
+      linearStructure(lineTokens, Some(value), structureLink.clone())
+    }
+    LineCode::Branches(branches) =>
+    {
+      runBranches(branches, structureLink);
+      unsafe{*lineIndex += branches.len()-1}
+      true
+    }
+  }
+}
+
+// =================================================================================================
+
+#[cfg(test)]
+mod tests
+{
+  use std::fs;
+  use std::io::{self, Write};
+  use std::panic::{catch_unwind, AssertUnwindSafe};
+  use std::path::{Path, PathBuf};
+  use std::sync::{Arc, Mutex, RwLock};
+  use crate::parser::runtime::Runtime;
+  use crate::parser::vm::Engine;
+  use crate::tokenizer::tokenizer::readTokens;
+  use crate::tokenizer::types::line::Line;
+  // ===============================================================================================
+
+  /// Вывод программы, который можно прочитать после запуска
+  #[derive(Clone, Default)]
+  struct SharedOutput(Arc< Mutex< Vec<u8> > >);
+
+  impl Write for SharedOutput
+  {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize>
+    {
+      self.0.lock().unwrap().extend_from_slice(buffer);
+      Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()>
+    {
+      Ok(())
+    }
+  }
+
+  /// Вспомогательная функция:
+  /// Запускает файл на движке с одним зерном случайных чисел и получает вывод и код завершения;
+  /// Паника (например, в t7.rt) тоже сравнивается, её код завершения - None
+  fn runFile(path: &Path, engine: Engine) -> (String, Option<i32>)
+  {
+    let runtime: Arc<Runtime> = Runtime::new();
+    let output: SharedOutput = SharedOutput::default();
+    runtime.setOutput(Box::new(output.clone()));
+    runtime.setEngine(engine);
+    runtime.setSeed(Some(0));
+    runtime.setFilePath(path.display().to_string());
+    let lines: Vec< Arc<RwLock<Line>> > = readTokens(fs::read(path).unwrap(), false);
+    let code: Option<i32> = catch_unwind(AssertUnwindSafe(|| runtime.run(lines))).ok();
+    let stdout: String = String::from_utf8_lossy(&output.0.lock().unwrap()).to_string();
+    (stdout, code)
+  }
+
+  /// Вспомогательная функция:
+  /// Получает .rt файлы каталога без вложенных каталогов
+  fn collectScripts(path: &Path) -> Vec<PathBuf>
+  {
+    let mut files: Vec<PathBuf> = fs::read_dir(path).unwrap()
+      .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+      .filter(|file: &PathBuf| file.extension().is_some_and(|extension| extension == "rt"))
+      .collect();
+    files.sort();
+    files
+  }
+
+  // ===============================================================================================
+
+  /// Проверяет, что программы из release и release/tests выводят на обоих движках одно и то же
+  #[test]
+  fn releaseEngines() -> ()
+  {
+    let release: PathBuf = Path::new(env!("CARGO_MANIFEST_DIR")).join("release");
+    let files: Vec<PathBuf> = [collectScripts(&release), collectScripts(&release.join("tests"))].concat();
+    assert!(!files.is_empty());
+    for file in files
+    {
+      assert_eq!(runFile(&file, Engine::Tree), runFile(&file, Engine::Vm), "{}", file.display());
+    }
+  }
+
+  // ===============================================================================================
+}
@@ -0,0 +1 @@
+pub mod tester;
\ No newline at end of file
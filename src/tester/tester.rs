@@ -0,0 +1,369 @@
+/* /tester
+  Встроенный запуск тестов `rts test [paths]`
+
+  Тестами считаются .rt файлы в переданных путях (по умолчанию tests):
+  - Каждая структура верхнего уровня с именем test* запускается отдельно,
+    в новом процессе `rts test-run <file> <structure>` (т.е. с новой средой выполнения);
+    Тест не пройден, если процесс завершился не с кодом 0, например из-за assert();
+  - Если рядом с файлом лежит .out файл, то файл запускается целиком,
+    а его stdout сравнивается с содержимым .out файла.
+
+  Файлы без test* структур и без .out файла считаются вспомогательными и не запускаются.
+  Каждый тест запускается на обоих движках: обходом дерева и на виртуальной машине (--engine=vm).
+  Процесс теста, который не завершился за processTimeout, останавливается, а тест не пройден.
+
+  `rts test --engines [paths]` запускает каждый .rt файл целиком на обоих движках
+  с одним зерном случайных чисел и сравнивает их stdout и код завершения.
+*/
+
+use std::{
+  env,
+  fs,
+  io::Read,
+  path::{Path, PathBuf},
+  process::{Child, Command, ExitStatus, Output, Stdio},
+  sync::{Arc, RwLock, RwLockReadGuard},
+  thread::{self, JoinHandle},
+  time::{Duration, Instant}
+};
+use crate::logger::logger::{log, logSeparator};
+use crate::tokenizer::tokenizer::readTokens;
+use crate::tokenizer::types::line::Line;
+use crate::tokenizer::types::token::Token;
+use crate::tokenizer::types::tokenType::TokenType;
+
+// =================================================================================================
+
+/// Путь к тестам по умолчанию
+const defaultTestsPath: &str = "tests";
+/// Начало имени тестовой структуры
+const testPrefix: &str = "test";
+/// Движки, на которых запускается каждый тест: метка в выводе и параметры rts
+const engines: [(&str, &[&str]); 2] = [
+  ("",    &[]),
+  (" vm", &["--engine=vm"])
+];
+/// Зерно случайных чисел для сравнения движков, чтобы случайные значения на них совпадали
+const compareSeed: &str = "0";
+/// Время, после которого процесс теста останавливается, а тест считается не пройденным
+const processTimeout: Duration = Duration::from_secs(30);
+/// Как часто проверяется, что процесс теста завершился
+const processPollInterval: Duration = Duration::from_millis(10);
+
+// =================================================================================================
+
+/// Собирает все .rt файлы по пути; Каталоги читаются рекурсивно
+fn collectFiles(path: &Path, files: &mut Vec<PathBuf>) -> ()
+{
+  match path.is_dir()
+  {
+    false =>
+      match path.extension().and_then(|extension| extension.to_str()) == Some("rt")
+      {
+        false => {}
+        true  => files.push(path.to_path_buf())
+      }
+    true =>
+    {
+      let mut entries: Vec<PathBuf> = match fs::read_dir(path)
+      {
+        Err(_) => return,
+        Ok(entries) => entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect()
+      };
+      entries.sort();
+      for entry in entries
+      {
+        collectFiles(&entry, files);
+      }
+    }
+  }
+}
+
+/// Получает имена test* структур верхнего уровня в файле
+fn getTestStructures(buffer: Vec<u8>) -> Vec<String>
+{
+  readTokens(buffer, false).iter()
+    .filter_map(|lineLink: &Arc<RwLock<Line>>|
+    {
+      let line: RwLockReadGuard<Line> = lineLink.read().unwrap();
+      let tokens: &Vec<Token> = line.tokens.as_ref()?;
+      match line.lines.is_some() && tokens.len() == 1 && *tokens[0].getDataType() == TokenType::Word
+      {
+        false => None,
+        true  =>
+        {
+          let name: String = tokens[0].getData().toString()?;
+          match name.starts_with(testPrefix)
+          {
+            true  => Some(name),
+            false => None
+          }
+        }
+      }
+    })
+    .collect()
+}
+
+/// Читает поток процесса до конца в отдельном потоке, чтобы процесс не ждал заполненный канал
+fn readPipe(pipe: Option<impl Read + Send + 'static>) -> JoinHandle< Vec<u8> >
+{
+  thread::spawn(move ||
+  {
+    let mut buffer: Vec<u8> = Vec::new();
+    match pipe
+    { None => {} Some(mut pipe) =>
+    {
+      let _ = pipe.read_to_end(&mut buffer);
+    }}
+    buffer
+  })
+}
+
+/// Запускает rts с параметрами в отдельном процессе;
+/// Процесс без stdin, а если он не завершился за processTimeout, то он останавливается.
+/// Возвращает причину, если процесс не удалось запустить или дождаться
+fn runProcess(arguments: &[&str]) -> Result<Output, String>
+{
+  let unableToRun = || String::from("Unable to run process");
+  let executable: PathBuf = env::current_exe().map_err(|_| unableToRun())?;
+  let mut child: Child = Command::new(executable)
+    .args(arguments)
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .map_err(|_| unableToRun())?;
+  let stdout: JoinHandle< Vec<u8> > = readPipe(child.stdout.take());
+  let stderr: JoinHandle< Vec<u8> > = readPipe(child.stderr.take());
+
+  let deadline: Instant = Instant::now()+processTimeout;
+  let status: ExitStatus = loop
+  {
+    match child.try_wait()
+    {
+      Err(_) => return Err( unableToRun() ),
+      Ok(Some(status)) => break status,
+      Ok(None) if Instant::now() < deadline => thread::sleep(processPollInterval),
+      Ok(None) =>
+      { // Потоки чтения остаются ждать, пока закроются каналы процесса
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err( format!("Timeout [{}s]", processTimeout.as_secs()) );
+      }
+    }
+  };
+  Ok(Output {
+    status,
+    stdout: stdout.join().unwrap_or_default(),
+    stderr: stderr.join().unwrap_or_default()
+  })
+}
+
+/// Выводит stderr упавшего процесса под именем теста
+fn printFailure(output: &Output) -> ()
+{
+  let stderr: String = String::from_utf8_lossy(&output.stderr).to_string();
+  for line in stderr.lines()
+  {
+    eprintln!("     {}", line);
+  }
+}
+
+/// Сравнивает stdout с ожидаемым выводом;
+/// Возвращает номер первой отличающейся линии и обе линии
+fn compareOutput(expected: &str, actual: &str) -> Option<(usize, String, String)>
+{
+  let expectedLines: Vec<&str> = expected.lines().collect();
+  let actualLines:   Vec<&str> = actual.lines().collect();
+  let length: usize = expectedLines.len().max(actualLines.len());
+  for i in 0..length
+  {
+    let expectedLine: &str = expectedLines.get(i).copied().unwrap_or("<end>");
+    let actualLine:   &str = actualLines.get(i).copied().unwrap_or("<end>");
+    match expectedLine == actualLine
+    {
+      true  => {}
+      false => return Some((i+1, expectedLine.to_string(), actualLine.to_string()))
+    }
+  }
+  None
+}
+
+/// Собирает .rt файлы по путям, либо по пути по умолчанию
+fn collectPaths(paths: &[String]) -> Vec<PathBuf>
+{
+  let mut files: Vec<PathBuf> = Vec::new();
+  match paths.len() == 0
+  {
+    true  => collectFiles(Path::new(defaultTestsPath), &mut files),
+    false =>
+      for path in paths
+      {
+        collectFiles(Path::new(path), &mut files);
+      }
+  }
+  files
+}
+
+/// Выводит итог и получает код завершения: 0 если все тесты прошли, иначе 1
+fn summary(passed: usize, failed: usize) -> i32
+{
+  logSeparator(&format!("Tests: {} passed, {} failed", passed, failed));
+  match failed == 0
+  {
+    true  => 0,
+    false => 1
+  }
+}
+
+// =================================================================================================
+
+/// Запускает тесты по путям и выводит итог;
+/// Возвращает код завершения: 0 если все тесты прошли, иначе 1
+pub fn runTests(paths: &[String]) -> i32
+{
+  match paths.first().map(|path: &String| path.as_str())
+  {
+    Some("--engines") => return compareEngines(&paths[1..]),
+    _ => {}
+  }
+  let files: Vec<PathBuf> = collectPaths(paths);
+
+  let mut passed: usize = 0;
+  let mut failed: usize = 0;
+  for file in files
+  {
+    let filePath: String = file.display().to_string();
+    let buffer: Vec<u8> = match fs::read(&file)
+    {
+      Ok(buffer) => buffer,
+      Err(_) =>
+      {
+        log("err", &format!("Unable to read file [{}]", filePath));
+        failed += 1;
+        continue;
+      }
+    };
+
+    // Сравнение stdout с .out файлом
+    match fs::read_to_string(file.with_extension("out"))
+    { Err(_) => {} Ok(expected) => for (engine, options) in engines
+    {
+      let result: Result<(), String> = match runProcess(&[&["run"], options, &[filePath.as_str()]].concat())
+      {
+        Err(reason) => Err(reason),
+        Ok(output) =>
+        {
+          let actual: String = String::from_utf8_lossy(&output.stdout).to_string();
+          match compareOutput(&expected, &actual)
+          {
+            None => match output.status.success()
+            {
+              true  => Ok(()),
+              false =>
+              {
+                printFailure(&output);
+                Err( format!("Exit code [{}]", output.status.code().unwrap_or(-1)) )
+              }
+            }
+            Some((lineNumber, expectedLine, actualLine)) =>
+              Err( format!("Line {}: expected [{}], got [{}]", lineNumber, expectedLine, actualLine) )
+          }
+        }
+      };
+      match result
+      {
+        Ok(()) =>
+        {
+          log("ok", &format!("{} [out{}]", filePath, engine));
+          passed += 1;
+        }
+        Err(reason) =>
+        {
+          log("err", &format!("{} [out{}] {}", filePath, engine, reason));
+          failed += 1;
+        }
+      }
+    }}
+
+    // Отдельный запуск каждой test* структуры
+    for testStructure in getTestStructures(buffer)
+    {
+      for (engine, options) in engines
+      {
+        match runProcess(&[&["test-run"], options, &[filePath.as_str(), testStructure.as_str()]].concat())
+        {
+          Ok(output) if output.status.success() =>
+          {
+            log("ok", &format!("{} {}{}", filePath, testStructure, engine));
+            passed += 1;
+          }
+          Ok(output) =>
+          {
+            log("err", &format!("{} {}{}", filePath, testStructure, engine));
+            printFailure(&output);
+            failed += 1;
+          }
+          Err(reason) =>
+          {
+            log("err", &format!("{} {}{} {}", filePath, testStructure, engine, reason));
+            failed += 1;
+          }
+        }
+      }
+    }
+  }
+
+  summary(passed, failed)
+}
+
+/// Запускает каждый файл по путям на обоих движках и сравнивает stdout и код завершения;
+/// Возвращает код завершения: 0 если вывод везде совпал, иначе 1
+fn compareEngines(paths: &[String]) -> i32
+{
+  let mut passed: usize = 0;
+  let mut failed: usize = 0;
+  for file in collectPaths(paths)
+  {
+    let filePath: String = file.display().to_string();
+    let outputs: Vec< Result<Output, String> > = engines.iter()
+      .map(|(_, options)| runProcess(&[&["run", "--seed", compareSeed], *options, &[filePath.as_str()]].concat()))
+      .collect();
+    let result: Result<(), String> = match (&outputs[0], &outputs[1])
+    {
+      (Ok(tree), Ok(vm)) =>
+        match compareOutput(&String::from_utf8_lossy(&tree.stdout), &String::from_utf8_lossy(&vm.stdout))
+        {
+          Some((lineNumber, treeLine, vmLine)) =>
+            Err( format!("Line {}: tree [{}], vm [{}]", lineNumber, treeLine, vmLine) ),
+          None => match tree.status.code() == vm.status.code()
+          {
+            true  => Ok(()),
+            false => Err( format!(
+              "Exit code: tree [{}], vm [{}]",
+              tree.status.code().unwrap_or(-1),
+              vm.status.code().unwrap_or(-1)
+            ))
+          }
+        }
+      (Err(reason), _) | (_, Err(reason)) => Err( reason.clone() )
+    };
+    match result
+    {
+      Ok(()) =>
+      {
+        log("ok", &format!("{} [engines]", filePath));
+        passed += 1;
+      }
+      Err(reason) =>
+      {
+        log("err", &format!("{} [engines] {}", filePath, reason));
+        failed += 1;
+      }
+    }
+  }
+
+  summary(passed, failed)
+}
+
+// =================================================================================================
@@ -18,9 +18,17 @@ pub fn bracketNesting(tokens: &mut Vec<Token>, beginType: &TokenType, endType: &
     }}
   }
   */
-  // Вкладывание
+  // Вкладывание каждой скобки верхнего уровня, от последней к первой;
+  // Иначе в `a(x) + b(y)` вкладывалась бы только последняя скобка
   let mut index: usize = tokens.len();
-  blockNesting(tokens, beginType, endType, &mut index);
+  while index > 0
+  {
+    match tokens[index-1].getDataType() == endType
+    {
+      false => index -= 1,
+      true  => blockNesting(tokens, beginType, endType, &mut index)
+    }
+  }
 }
 
 /// Эта функция является дочерней bracketNesting;
@@ -314,6 +322,45 @@ mod tests
   }
 
   // ===============================================================================================
+
+  /// Проверяет соседние скобки на одном уровне: `a(x) + b(y)`
+  #[test]
+  fn siblingBrackets() -> ()
+  {
+    let mut tokens: Vec<Token> = 
+      vec![
+        createToken(TokenType::Word, "a"),
+        createToken(TokenType::CircleBracketBegin, "("),
+        createToken(TokenType::Word, "x"),
+        createToken(TokenType::CircleBracketEnd, ")"),
+        createToken(TokenType::Plus, "+"),
+        createToken(TokenType::Word, "b"),
+        createToken(TokenType::CircleBracketBegin, "("),
+        createToken(TokenType::Word, "y"),
+        createToken(TokenType::CircleBracketEnd, ")"),
+      ];
+
+    //
+    bracketNesting(&mut tokens, &TokenType::CircleBracketBegin, &TokenType::CircleBracketEnd);
+
+    //
+    #[cfg(not(feature = "analyzer"))]
+    assert_eq!(tokens.len(), 5, "Должны остаться имена, оператор и обе открывающие скобки");
+    
+    // Обе скобки получают свои линии, а не только последняя
+    let brackets: Vec<&Token> = tokens.iter()
+      .filter(|token: &&Token| token.getDataType().to_string() == TokenType::CircleBracketBegin.to_string())
+      .collect();
+    assert_eq!(brackets.len(), 2, "Ожидались 2 открывающие скобки");
+    checkLines(brackets[0].lines.as_ref().expect("Ожидались линии первой скобки"), &[
+      (TokenType::Word, "x"),
+    ]);
+    checkLines(brackets[1].lines.as_ref().expect("Ожидались линии второй скобки"), &[
+      (TokenType::Word, "y"),
+    ]);
+  }
+
+  // ===============================================================================================
 }
 
 // =================================================================================================
\ No newline at end of file
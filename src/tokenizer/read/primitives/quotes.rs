@@ -3,11 +3,12 @@ use crate::tokenizer::types::tokenType::TokenType;
 // =================================================================================================
 
 /// Проверяет buffer по index и так находит возможные
-/// Char, String, RawString
+/// Char, String, RawString;
+/// Содержимое кавычек собирается по байтам и читается как UTF-8 целиком
 pub fn getQuotes(buffer: &[u8], index: &mut usize, formatted: bool) -> Token 
 {
   let byte1: u8 = buffer[*index]; // Начальный символ кавычки
-  let mut result: String = String::new();
+  let mut bytes: Vec<u8> = Vec::new();
 
   *index += 1;
 
@@ -43,7 +44,7 @@ pub fn getQuotes(buffer: &[u8], index: &mut usize, formatted: bool) -> Token
         // Нечетное количество обратных слэшей — кавычка экранирована
         match backslashCount%2 
         {
-          1 => result.push(byte2 as char), // Экранированная кавычка
+          1 => bytes.push(byte2), // Экранированная кавычка
           _ => 
           {
             *index += 1; // Завершение строки
@@ -52,18 +53,19 @@ pub fn getQuotes(buffer: &[u8], index: &mut usize, formatted: bool) -> Token
         }
       }
       // Все иные символы, входящие между кавычек;
-      _ => { result.push(byte2 as char); }
+      _ => { bytes.push(byte2); }
     }
 
     *index += 1;
   }
 
+  let result: String = String::from_utf8_lossy(&bytes).into_owned();
   // Проверяем тип кавычки и возвращаем соответствующий токен
   match byte1 
   {
     b'\'' => 
     { 
-      if formatted || result.len() == 1 
+      if formatted || result.chars().count() == 1 
       { // Одинарные кавычки должны содержать только один символ - если не formatted
         Token::new(
           if formatted { TokenType::FormattedChar } else { TokenType::Char },
@@ -106,6 +108,10 @@ mod tests
       ("\"esc'\"", TokenType::String, "esc'", false),
       ("\"esc`\"", TokenType::String, "esc`", false),
       ("`esc'`", TokenType::RawString, "esc'", false),
+      //
+      ("'é'", TokenType::Char, "é", false),
+      ("\"héllo\"", TokenType::String, "héllo", false),
+      ("`straße`", TokenType::RawString, "straße", false),
       // todo Тут проблемы что нельзя отладить \ перед quotes - оно не работает;
       //  Хотя в коде обычно это работало. Возможно что тут передать нельзя.
     ] {
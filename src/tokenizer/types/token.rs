@@ -75,6 +75,88 @@ impl Token
       end: 0
     }
   }
+  /// Список значений; Держит элементы в одной вложенной линии
+  pub fn newList(
+    elements: Vec<Token>
+  ) -> Self
+  {
+    let mut token: Token = Token::newNesting(
+      vec![
+        Line
+        {
+          tokens: Some(elements),
+          indent: None,
+          lines: None,
+          parent: None
+        }
+      ]
+    );
+    token.dataType = TokenType::Link; // todo: Речь не о Link, а об Array?
+    token
+  }
+  /// Получает элементы, если это список
+  pub fn getList(&self) -> Option<&Vec<Token>>
+  {
+    match self.dataType == TokenType::Link && self.data.toString().is_none()
+    { true => {} false =>
+    {
+      return None;
+    }}
+    match self.lines.as_deref()
+    {
+      Some([line]) if !isField(line) => line.tokens.as_ref(),
+      _ => None
+    }
+  }
+  /// Запись из именованных значений; Держит каждое поле в своей линии `name = value`
+  pub fn newRecord(
+    fields: Vec<(String, Token)>
+  ) -> Self
+  {
+    let mut token: Token = Token::newNesting(
+      fields.into_iter()
+        .map(|(name, value): (String, Token)|
+          Line
+          {
+            tokens: Some(vec![
+              Token::new(TokenType::Word, name),
+              Token::newEmpty(TokenType::Equals),
+              value
+            ]),
+            indent: None,
+            lines: None,
+            parent: None
+          }
+        )
+        .collect()
+    );
+    token.dataType = TokenType::Link;
+    token
+  }
+  /// Получает поля по порядку, если это запись
+  pub fn getRecord(&self) -> Option< Vec<(String, &Token)> >
+  {
+    match self.dataType == TokenType::Link && self.data.toString().is_none()
+    { true => {} false =>
+    {
+      return None;
+    }}
+    let lines: &Vec<Line> = self.lines.as_ref()?;
+    match !lines.is_empty() && lines.iter().all(isField)
+    { true => {} false =>
+    {
+      return None;
+    }}
+    Some(
+      lines.iter()
+        .filter_map(|line: &Line| match line.tokens.as_deref()
+        {
+          Some([name, _, value]) => Some(( name.getData().toString().unwrap_or_default(), value )),
+          _ => None
+        })
+        .collect()
+    )
+  }
 
   // convert data
   // todo: фиг его знает что это за ерунда,
@@ -140,6 +222,17 @@ impl Token
   }
 }
 
+/// Проверяет, что линия это поле записи `name = value`
+fn isField(line: &Line) -> bool
+{
+  match line.tokens.as_deref()
+  {
+    Some([name, equals, _]) =>
+      *name.getDataType() == TokenType::Word && *equals.getDataType() == TokenType::Equals,
+    _ => false
+  }
+}
+
 impl fmt::Display for Token
 { // todo: debug only ?
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result